@@ -1,50 +1,41 @@
+import os
 import time
 import requests
 
-local = False 
-reset_from_zero = False # False to continue from where it left off  
+local = False
+reset_from_zero = False # False to continue from where it left off
 fly_app_name = "devhub-cache-api-rs-2"
-# ~120 calls for devhub
-# ~20 calls for infra
-# ~40 calls for events
-max_calls = 120 # This is for devhub to catch up to the latest block
+admin_api_key = os.environ["ADMIN_API_KEY"] # ROCKET_ADMIN_API_KEY of the app
 
 base_url = f"http://localhost:8080/" if local else f"https://{fly_app_name}.fly.dev/"
+headers = {"Authorization": f"Bearer {admin_api_key}"}
 
-def call_api(count):
-    url = f"{base_url}proposals"  # Replace with your API URL
-    try:
-        response = requests.get(url)
-        if response.status_code == 200:
-            print(f"{count} API call successful: - response length {response.json().get('total_records')}")
-        else:
-            print("API call failed with status code:", response.status_code)
-    except requests.exceptions.RequestException as e:
-        print("An error occurred:", e)
-    except Exception as e:
-        print("An error2 occurred:", e)
-        print(response.json())
+def start_sync():
+    kind = "sync_from_start" if reset_from_zero else "continue_sync"
+    response = requests.post(f"{base_url}admin/jobs", json={"kind": kind}, headers=headers)
+    if response.status_code == 409:
+        print("A sync is already running:", response.text)
+        return None
+    response.raise_for_status()
+    job = response.json()
+    print(f"Started {kind} job {job['id']}")
+    return job["id"]
 
-def reset_cache():
-    url = f"{base_url}proposals/info/reset"  # Replace with your API URL
-    try:
-        response = requests.get(url)
-        if response.status_code == 200:
-            print("Cache reset successful")
-        else:
-            print("Cache reset failed with status code:", response.status_code)
-    except requests.exceptions.RequestException as e:
-        print("An error occurred:", e)
+def wait_for(job_id):
+    # The sync runs in the background, so only its progress is polled
+    while True:
+        job = requests.get(f"{base_url}admin/jobs/{job_id}", headers=headers).json()
+        print(f"Job {job_id} {job['status']}: {job['pages_fetched']} pages, {job['transactions_fetched']} transactions, last block {job['last_block']}")
+        if job["status"] not in ("queued", "running"):
+            if job["error"]:
+                print("Error:", job["error"])
+            return
+        time.sleep(10)
 
 def main():
-    if reset_from_zero:
-        reset_cache()
-    count = 0
-    while count < max_calls: 
-        call_api(count)
-        count += 1
-        # 6 calls/minute limit of near blocks https://nearblocks.io/apis#
-        time.sleep(0.5)  # Wait for 11 seconds before the next call
+    job_id = start_sync()
+    if job_id is not None:
+        wait_for(job_id)
 
 if __name__ == "__main__":
     main()
@@ -0,0 +1,85 @@
+use serde::Serialize;
+use serde_json::Value;
+
+// Fields that identify a snapshot rather than describe its content
+const SNAPSHOT_KEY_FIELDS: [&str; 2] = ["ts", "block_height"];
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FieldChange {
+    pub field: String,
+    pub from: Value,
+    pub to: Value,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SnapshotDiff {
+    pub from_ts: i64,
+    pub to_ts: i64,
+    pub changes: Vec<FieldChange>,
+}
+
+/// Compares two snapshots field by field, ignoring `ts` and `block_height`.
+pub fn diff_snapshots<S: Serialize>(from: &S, to: &S) -> anyhow::Result<Vec<FieldChange>> {
+    let (Value::Object(from), Value::Object(to)) =
+        (serde_json::to_value(from)?, serde_json::to_value(to)?)
+    else {
+        return Err(anyhow::anyhow!("Snapshots must serialize to JSON objects"));
+    };
+
+    let mut changes: Vec<FieldChange> = to
+        .iter()
+        .filter(|(field, _)| !SNAPSHOT_KEY_FIELDS.contains(&field.as_str()))
+        .filter_map(|(field, to_value)| {
+            let from_value = from.get(field).cloned().unwrap_or(Value::Null);
+            (&from_value != to_value).then(|| FieldChange {
+                field: field.clone(),
+                from: from_value,
+                to: to_value.clone(),
+            })
+        })
+        .collect();
+    changes.sort_by(|a, b| a.field.cmp(&b.field));
+
+    Ok(changes)
+}
+
+/// Picks two snapshots out of a history (any order) by timestamp and diffs them.
+/// Without timestamps the second newest snapshot is compared to the newest.
+pub(crate) fn diff_snapshot_history<S: Serialize>(
+    snapshots: &[S],
+    ts_of: impl Fn(&S) -> i64,
+    from_ts: Option<i64>,
+    to_ts: Option<i64>,
+) -> anyhow::Result<SnapshotDiff> {
+    let mut sorted: Vec<&S> = snapshots.iter().collect();
+    sorted.sort_by_key(|s| std::cmp::Reverse(ts_of(s)));
+
+    let find = |ts: i64| {
+        sorted
+            .iter()
+            .copied()
+            .find(|s| ts_of(s) == ts)
+            .ok_or_else(|| anyhow::anyhow!("No snapshot with ts {}", ts))
+    };
+
+    let to = match to_ts {
+        Some(ts) => find(ts)?,
+        None => *sorted
+            .first()
+            .ok_or_else(|| anyhow::anyhow!("No snapshots to diff"))?,
+    };
+    let from = match from_ts {
+        Some(ts) => find(ts)?,
+        None => sorted
+            .iter()
+            .copied()
+            .find(|s| ts_of(s) < ts_of(to))
+            .unwrap_or(to),
+    };
+
+    Ok(SnapshotDiff {
+        from_ts: ts_of(from),
+        to_ts: ts_of(to),
+        changes: diff_snapshots(from, to)?,
+    })
+}
@@ -0,0 +1,179 @@
+//! Typed async client for the Devhub Cache API.
+//!
+//! Responses are deserialized into the same `db_types` and `PaginatedResponse`
+//! definitions the API serializes, so the two can't drift apart.
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use reqwest::Client;
+use serde::de::DeserializeOwned;
+use std::future::Future;
+
+pub mod diff;
+pub mod query;
+
+pub use devhub_cache_api::db::db_types::{
+    ProposalSnapshotRecord, ProposalWithLatestSnapshotView, RfpSnapshotRecord,
+    RfpWithLatestSnapshotView,
+};
+pub use devhub_cache_api::types::PaginatedResponse;
+pub use devhub_shared::proposal::VersionedProposal;
+pub use devhub_shared::rfp::VersionedRFP;
+pub use diff::{FieldChange, SnapshotDiff};
+pub use query::{Order, ProposalQuery, RfpQuery};
+
+/// Status code and raw body of a response, independent of the HTTP stack.
+#[derive(Debug, Clone)]
+pub struct TransportResponse {
+    pub status: u16,
+    pub body: String,
+}
+
+/// Sends a GET request for a path (including query string) relative to the API root.
+///
+/// Implemented by [`HttpTransport`] for real deployments; tests can implement it on top
+/// of `rocket::local::asynchronous::Client` to exercise the client without a server.
+pub trait Transport {
+    fn get(&self, path: &str) -> impl Future<Output = anyhow::Result<TransportResponse>> + Send;
+}
+
+#[derive(Clone)]
+pub struct HttpTransport {
+    base_url: String,
+    client: Client,
+}
+
+impl HttpTransport {
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self {
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            client: Client::new(),
+        }
+    }
+}
+
+impl Transport for HttpTransport {
+    async fn get(&self, path: &str) -> anyhow::Result<TransportResponse> {
+        let url = format!("{}{}", self.base_url, path);
+        let response = self.client.get(&url).send().await?;
+        let status = response.status().as_u16();
+        let body = response.text().await?;
+        Ok(TransportResponse { status, body })
+    }
+}
+
+#[derive(Clone)]
+pub struct CacheClient<T = HttpTransport> {
+    transport: T,
+}
+
+impl CacheClient<HttpTransport> {
+    /// Client for a deployed cache API, e.g. `https://devhub-cache-api-rs-2.fly.dev`.
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self::with_transport(HttpTransport::new(base_url))
+    }
+}
+
+impl<T: Transport> CacheClient<T> {
+    pub fn with_transport(transport: T) -> Self {
+        Self { transport }
+    }
+
+    pub async fn list_proposals(
+        &self,
+        query: &ProposalQuery,
+    ) -> anyhow::Result<PaginatedResponse<ProposalWithLatestSnapshotView>> {
+        self.get_json(&format!("/proposals{}", query.to_query_string()))
+            .await
+    }
+
+    pub async fn search_proposals(
+        &self,
+        input: &str,
+    ) -> anyhow::Result<PaginatedResponse<ProposalWithLatestSnapshotView>> {
+        self.get_json(&format!("/proposals/search/{}", encode_segment(input)))
+            .await
+    }
+
+    /// Current proposal state, read by the API directly from the contract.
+    pub async fn get_proposal(&self, proposal_id: i32) -> anyhow::Result<VersionedProposal> {
+        self.get_json(&format!("/proposal/{}", proposal_id)).await
+    }
+
+    /// All cached snapshots of a proposal, newest first.
+    pub async fn get_proposal_snapshots(
+        &self,
+        proposal_id: i32,
+    ) -> anyhow::Result<Vec<ProposalSnapshotRecord>> {
+        self.get_json(&format!("/proposal/{}/snapshots", proposal_id))
+            .await
+    }
+
+    /// Field-level diff between two snapshots of a proposal. Defaults to the
+    /// previous snapshot versus the latest one.
+    pub async fn diff_proposal(
+        &self,
+        proposal_id: i32,
+        from_ts: Option<i64>,
+        to_ts: Option<i64>,
+    ) -> anyhow::Result<SnapshotDiff> {
+        let snapshots = self.get_proposal_snapshots(proposal_id).await?;
+        diff::diff_snapshot_history(&snapshots, |s| s.ts, from_ts, to_ts)
+    }
+
+    pub async fn list_rfps(
+        &self,
+        query: &RfpQuery,
+    ) -> anyhow::Result<PaginatedResponse<RfpWithLatestSnapshotView>> {
+        self.get_json(&format!("/rfps{}", query.to_query_string()))
+            .await
+    }
+
+    pub async fn search_rfps(
+        &self,
+        input: &str,
+    ) -> anyhow::Result<PaginatedResponse<RfpWithLatestSnapshotView>> {
+        self.get_json(&format!("/rfps/search/{}", encode_segment(input)))
+            .await
+    }
+
+    /// Current RFP state, read by the API directly from the contract.
+    pub async fn get_rfp(&self, rfp_id: i32) -> anyhow::Result<VersionedRFP> {
+        self.get_json(&format!("/rfp/{}", rfp_id)).await
+    }
+
+    /// All cached snapshots of an RFP, newest first.
+    pub async fn get_rfp_snapshots(&self, rfp_id: i32) -> anyhow::Result<Vec<RfpSnapshotRecord>> {
+        self.get_json(&format!("/rfp/{}/snapshots", rfp_id)).await
+    }
+
+    /// Field-level diff between two snapshots of an RFP. Defaults to the
+    /// previous snapshot versus the latest one.
+    pub async fn diff_rfp(
+        &self,
+        rfp_id: i32,
+        from_ts: Option<i64>,
+        to_ts: Option<i64>,
+    ) -> anyhow::Result<SnapshotDiff> {
+        let snapshots = self.get_rfp_snapshots(rfp_id).await?;
+        diff::diff_snapshot_history(&snapshots, |s| s.ts, from_ts, to_ts)
+    }
+
+    async fn get_json<R: DeserializeOwned>(&self, path: &str) -> anyhow::Result<R> {
+        let response = self.transport.get(path).await?;
+
+        if !(200..300).contains(&response.status) {
+            return Err(anyhow::anyhow!(
+                "Cache API error response {} for {}: {}",
+                response.status,
+                path,
+                response.body
+            ));
+        }
+
+        serde_json::from_str::<R>(&response.body)
+            .map_err(|e| anyhow::anyhow!("Failed to parse cache API response for {}: {}", path, e))
+    }
+}
+
+fn encode_segment(input: &str) -> String {
+    utf8_percent_encode(input, NON_ALPHANUMERIC).to_string()
+}
@@ -0,0 +1,263 @@
+use devhub_cache_api::entrypoints::{
+    proposal::proposal_types::GetProposalFilters, rfp::rfp_types::GetRfpFilters,
+};
+use url::form_urlencoded::Serializer;
+
+/// Orders accepted by the list endpoints.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Order {
+    TsAsc,
+    TsDesc,
+    IdAsc,
+    IdDesc,
+    /// Most viewed first. Only proposals count views; RFP lists fall back to `IdDesc`.
+    ViewsDesc,
+}
+
+impl Order {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Order::TsAsc => "ts_asc",
+            Order::TsDesc => "ts_desc",
+            Order::IdAsc => "id_asc",
+            Order::IdDesc => "id_desc",
+            Order::ViewsDesc => "views_desc",
+        }
+    }
+}
+
+/// Builder for `GET /proposals`.
+///
+/// ```
+/// use devhub_cache_client::{Order, ProposalQuery};
+///
+/// let query = ProposalQuery::new()
+///     .order(Order::TsDesc)
+///     .limit(20)
+///     .category("DevDAO Platform")
+///     .label("near-protocol");
+/// assert!(query.to_query_string().starts_with("?order=ts_desc&limit=20"));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct ProposalQuery {
+    order: Option<Order>,
+    limit: Option<i64>,
+    offset: Option<i64>,
+    filters: Option<GetProposalFilters>,
+}
+
+impl ProposalQuery {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn order(mut self, order: Order) -> Self {
+        self.order = Some(order);
+        self
+    }
+
+    pub fn limit(mut self, limit: i64) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    pub fn offset(mut self, offset: i64) -> Self {
+        self.offset = Some(offset);
+        self
+    }
+
+    pub fn category(mut self, category: impl Into<String>) -> Self {
+        self.filters_mut().category = Some(category.into());
+        self
+    }
+
+    /// Matches proposals with any of the given labels; can be called repeatedly.
+    pub fn label(mut self, label: impl Into<String>) -> Self {
+        self.filters_mut()
+            .labels
+            .get_or_insert_with(Vec::new)
+            .push(label.into());
+        self
+    }
+
+    pub fn author_id(mut self, author_id: impl Into<String>) -> Self {
+        self.filters_mut().author_id = Some(author_id.into());
+        self
+    }
+
+    pub fn stage(mut self, stage: impl Into<String>) -> Self {
+        self.filters_mut().stage = Some(stage.into());
+        self
+    }
+
+    /// Only proposals whose latest snapshot is newer than this timestamp (ns).
+    pub fn block_timestamp(mut self, block_timestamp: i64) -> Self {
+        self.filters_mut().block_timestamp = Some(block_timestamp);
+        self
+    }
+
+    fn filters_mut(&mut self) -> &mut GetProposalFilters {
+        self.filters.get_or_insert(GetProposalFilters {
+            category: None,
+            labels: None,
+            input: None,
+            author_id: None,
+            stage: None,
+            block_timestamp: None,
+        })
+    }
+
+    pub fn to_query_string(&self) -> String {
+        let mut serializer = Serializer::new(String::new());
+        append_paging(&mut serializer, self.order, self.limit, self.offset);
+        if let Some(filters) = &self.filters {
+            append_filters(
+                &mut serializer,
+                filters.category.as_deref(),
+                filters.labels.as_deref(),
+                filters.author_id.as_deref(),
+                filters.stage.as_deref(),
+                filters.block_timestamp,
+            );
+        }
+        with_prefix(serializer.finish())
+    }
+}
+
+/// Builder for `GET /rfps`.
+#[derive(Clone, Debug, Default)]
+pub struct RfpQuery {
+    order: Option<Order>,
+    limit: Option<i64>,
+    offset: Option<i64>,
+    filters: Option<GetRfpFilters>,
+}
+
+impl RfpQuery {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn order(mut self, order: Order) -> Self {
+        self.order = Some(order);
+        self
+    }
+
+    pub fn limit(mut self, limit: i64) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    pub fn offset(mut self, offset: i64) -> Self {
+        self.offset = Some(offset);
+        self
+    }
+
+    pub fn category(mut self, category: impl Into<String>) -> Self {
+        self.filters_mut().category = Some(category.into());
+        self
+    }
+
+    /// Matches RFPs with any of the given labels; can be called repeatedly.
+    pub fn label(mut self, label: impl Into<String>) -> Self {
+        self.filters_mut()
+            .labels
+            .get_or_insert_with(Vec::new)
+            .push(label.into());
+        self
+    }
+
+    pub fn author_id(mut self, author_id: impl Into<String>) -> Self {
+        self.filters_mut().author_id = Some(author_id.into());
+        self
+    }
+
+    pub fn stage(mut self, stage: impl Into<String>) -> Self {
+        self.filters_mut().stage = Some(stage.into());
+        self
+    }
+
+    /// Only RFPs whose latest snapshot is newer than this timestamp (ns).
+    pub fn block_timestamp(mut self, block_timestamp: i64) -> Self {
+        self.filters_mut().block_timestamp = Some(block_timestamp);
+        self
+    }
+
+    fn filters_mut(&mut self) -> &mut GetRfpFilters {
+        self.filters.get_or_insert(GetRfpFilters {
+            category: None,
+            labels: None,
+            input: None,
+            author_id: None,
+            stage: None,
+            block_timestamp: None,
+        })
+    }
+
+    pub fn to_query_string(&self) -> String {
+        let mut serializer = Serializer::new(String::new());
+        append_paging(&mut serializer, self.order, self.limit, self.offset);
+        if let Some(filters) = &self.filters {
+            append_filters(
+                &mut serializer,
+                filters.category.as_deref(),
+                filters.labels.as_deref(),
+                filters.author_id.as_deref(),
+                filters.stage.as_deref(),
+                filters.block_timestamp,
+            );
+        }
+        with_prefix(serializer.finish())
+    }
+}
+
+fn append_paging(
+    serializer: &mut Serializer<String>,
+    order: Option<Order>,
+    limit: Option<i64>,
+    offset: Option<i64>,
+) {
+    if let Some(order) = order {
+        serializer.append_pair("order", order.as_str());
+    }
+    if let Some(limit) = limit {
+        serializer.append_pair("limit", &limit.to_string());
+    }
+    if let Some(offset) = offset {
+        serializer.append_pair("offset", &offset.to_string());
+    }
+}
+
+// Rocket parses the `filters` struct from dotted field names, repeating the key for vectors
+fn append_filters(
+    serializer: &mut Serializer<String>,
+    category: Option<&str>,
+    labels: Option<&[String]>,
+    author_id: Option<&str>,
+    stage: Option<&str>,
+    block_timestamp: Option<i64>,
+) {
+    if let Some(category) = category {
+        serializer.append_pair("filters.category", category);
+    }
+    for label in labels.unwrap_or_default() {
+        serializer.append_pair("filters.labels", label);
+    }
+    if let Some(author_id) = author_id {
+        serializer.append_pair("filters.author_id", author_id);
+    }
+    if let Some(stage) = stage {
+        serializer.append_pair("filters.stage", stage);
+    }
+    if let Some(block_timestamp) = block_timestamp {
+        serializer.append_pair("filters.block_timestamp", &block_timestamp.to_string());
+    }
+}
+
+fn with_prefix(query: String) -> String {
+    if query.is_empty() {
+        query
+    } else {
+        format!("?{}", query)
+    }
+}
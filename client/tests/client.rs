@@ -0,0 +1,198 @@
+use devhub_cache_api::db::db_types::{ProposalSnapshotRecord, RfpSnapshotRecord};
+use devhub_cache_api::db::DB;
+use devhub_cache_client::{
+    CacheClient, Order, ProposalQuery, RfpQuery, Transport, TransportResponse,
+};
+use rocket::local::asynchronous::Client;
+use rocket_db_pools::Database;
+use serde_json::json;
+
+// Ids far above anything on mainnet so seeded rows don't collide with indexed data
+const PROPOSAL_ID: i32 = 9_000_026;
+const RFP_ID: i32 = 9_000_026;
+const AUTHOR: &str = "cache-client-test.near";
+
+// Tests run concurrently against the same database, so seeding is serialized
+static SEED_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+struct LocalTransport(Client);
+
+impl Transport for LocalTransport {
+    async fn get(&self, path: &str) -> anyhow::Result<TransportResponse> {
+        let response = self.0.get(path.to_string()).dispatch().await;
+        let status = response.status().code;
+        let body = response.into_string().await.unwrap_or_default();
+        Ok(TransportResponse { status, body })
+    }
+}
+
+fn proposal_snapshot(ts: i64, name: &str, stage: &str) -> ProposalSnapshotRecord {
+    ProposalSnapshotRecord {
+        proposal_id: PROPOSAL_ID,
+        block_height: ts,
+        ts,
+        editor_id: AUTHOR.to_string(),
+        social_db_post_block_height: 1,
+        labels: json!(["client-test"]),
+        proposal_version: "V0".to_string(),
+        proposal_body_version: "V2".to_string(),
+        name: Some(name.to_string()),
+        category: Some("Client Test".to_string()),
+        summary: Some("Summary".to_string()),
+        description: Some("Description".to_string()),
+        linked_proposals: Some(json!([])),
+        linked_rfp: None,
+        requested_sponsorship_usd_amount: Some(100),
+        requested_sponsorship_paid_in_currency: Some("USDC".to_string()),
+        requested_sponsor: Some("neardevdao.near".to_string()),
+        receiver_account: Some(AUTHOR.to_string()),
+        supervisor: None,
+        timeline: Some(json!(format!("{{\"status\":\"{}\"}}", stage))),
+        views: None,
+        state_block_height: None,
+        approximate: false,
+    }
+}
+
+fn rfp_snapshot(ts: i64, name: &str) -> RfpSnapshotRecord {
+    RfpSnapshotRecord {
+        rfp_id: RFP_ID,
+        block_height: ts,
+        ts,
+        editor_id: AUTHOR.to_string(),
+        social_db_post_block_height: 1,
+        labels: json!(["client-test"]),
+        linked_proposals: Some(json!([])),
+        rfp_version: "V0".to_string(),
+        rfp_body_version: "V0".to_string(),
+        name: Some(name.to_string()),
+        category: None,
+        summary: Some("Summary".to_string()),
+        description: Some("Description".to_string()),
+        timeline: Some(json!("{\"status\":\"ACCEPTING_SUBMISSIONS\"}")),
+        submission_deadline: 0,
+        views: Some(0),
+        state_block_height: None,
+        approximate: false,
+    }
+}
+
+async fn seeded_client() -> CacheClient<LocalTransport> {
+    let rocket_client = Client::tracked(devhub_cache_api::rocket(None))
+        .await
+        .expect("valid `Rocket`");
+    let db = DB::fetch(rocket_client.rocket()).expect("database is attached");
+
+    let _guard = SEED_LOCK.lock().await;
+    let mut tx = db.begin().await.unwrap();
+    DB::upsert_proposal(&mut tx, PROPOSAL_ID as u32, AUTHOR.to_string())
+        .await
+        .unwrap();
+    DB::insert_proposal_snapshot(&mut tx, &proposal_snapshot(1_000, "First name", "DRAFT"))
+        .await
+        .unwrap();
+    DB::insert_proposal_snapshot(&mut tx, &proposal_snapshot(2_000, "Second name", "REVIEW"))
+        .await
+        .unwrap();
+    DB::upsert_rfp(&mut tx, RFP_ID as u32, AUTHOR.to_string())
+        .await
+        .unwrap();
+    DB::insert_rfp_snapshot(&mut tx, &rfp_snapshot(1_000, "Client test rfp"))
+        .await
+        .unwrap();
+    DB::insert_rfp_snapshot(&mut tx, &rfp_snapshot(2_000, "Client test rfp renamed"))
+        .await
+        .unwrap();
+    tx.commit().await.unwrap();
+
+    CacheClient::with_transport(LocalTransport(rocket_client))
+}
+
+#[rocket::async_test]
+async fn test_client_lists_and_filters_proposals() {
+    let client = seeded_client().await;
+
+    let query = ProposalQuery::new()
+        .order(Order::IdDesc)
+        .limit(5)
+        .author_id(AUTHOR)
+        .category("Client Test")
+        .label("client-test")
+        .stage("review");
+    let result = client.list_proposals(&query).await.unwrap();
+
+    assert_eq!(result.total_records, 1);
+    assert_eq!(result.limit, 5);
+    assert_eq!(result.records[0].proposal_id, PROPOSAL_ID);
+    assert_eq!(result.records[0].name.as_deref(), Some("Second name"));
+
+    let most_viewed = ProposalQuery::new()
+        .order(Order::ViewsDesc)
+        .author_id(AUTHOR);
+    assert!(most_viewed
+        .to_query_string()
+        .starts_with("?order=views_desc"));
+    let result = client.list_proposals(&most_viewed).await.unwrap();
+    assert_eq!(result.records[0].proposal_id, PROPOSAL_ID);
+    assert_eq!(result.records[0].views, Some(0));
+
+    let draft_only = ProposalQuery::new().author_id(AUTHOR).stage("draft");
+    let result = client.list_proposals(&draft_only).await.unwrap();
+    assert_eq!(result.total_records, 0);
+}
+
+#[rocket::async_test]
+async fn test_client_search_snapshots_and_diff_proposal() {
+    let client = seeded_client().await;
+
+    let found = client
+        .search_proposals(&PROPOSAL_ID.to_string())
+        .await
+        .unwrap();
+    assert_eq!(found.records[0].author_id, AUTHOR);
+
+    let snapshots = client.get_proposal_snapshots(PROPOSAL_ID).await.unwrap();
+    assert_eq!(
+        snapshots.iter().map(|s| s.ts).collect::<Vec<_>>(),
+        vec![2_000, 1_000]
+    );
+
+    let diff = client.diff_proposal(PROPOSAL_ID, None, None).await.unwrap();
+    assert_eq!((diff.from_ts, diff.to_ts), (1_000, 2_000));
+    let fields: Vec<&str> = diff.changes.iter().map(|c| c.field.as_str()).collect();
+    assert_eq!(fields, vec!["name", "timeline"]);
+    assert_eq!(diff.changes[0].to, json!("Second name"));
+}
+
+#[rocket::async_test]
+async fn test_client_lists_searches_and_diffs_rfps() {
+    let client = seeded_client().await;
+
+    let result = client
+        .list_rfps(&RfpQuery::new().author_id(AUTHOR).limit(1))
+        .await
+        .unwrap();
+    assert_eq!(result.total_records, 1);
+    assert_eq!(result.records[0].rfp_id, RFP_ID);
+
+    let found = client.search_rfps("Client test rfp renamed").await.unwrap();
+    assert!(found.records.iter().any(|r| r.rfp_id == RFP_ID));
+
+    let diff = client
+        .diff_rfp(RFP_ID, Some(1_000), Some(2_000))
+        .await
+        .unwrap();
+    assert_eq!(diff.changes.len(), 1);
+    assert_eq!(diff.changes[0].field, "name");
+}
+
+#[rocket::async_test]
+async fn test_client_reports_error_status() {
+    let client = seeded_client().await;
+
+    let error = client.diff_proposal(PROPOSAL_ID, Some(42), None).await;
+    assert!(error.is_err());
+
+    let error = client.search_rfps("").await.unwrap_err();
+    assert!(error.to_string().contains("404"));
+}
@@ -0,0 +1,23 @@
+-- Notify /events listeners when a snapshot is committed. The payload is only a wake-up;
+-- listeners read the new rows back from the snapshot tables by block height.
+
+CREATE INDEX
+  idx_proposal_snapshots_block_height ON proposal_snapshots (block_height);
+
+CREATE INDEX
+  idx_rfp_snapshots_block_height ON rfp_snapshots (block_height);
+
+CREATE OR REPLACE FUNCTION notify_snapshot_event() RETURNS trigger AS $$
+BEGIN
+  PERFORM pg_notify('snapshot_events', NEW.block_height::text);
+  RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER proposal_snapshots_notify
+  AFTER INSERT ON proposal_snapshots
+  FOR EACH ROW EXECUTE FUNCTION notify_snapshot_event();
+
+CREATE TRIGGER rfp_snapshots_notify
+  AFTER INSERT ON rfp_snapshots
+  FOR EACH ROW EXECUTE FUNCTION notify_snapshot_event();
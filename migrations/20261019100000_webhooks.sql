@@ -0,0 +1,53 @@
+-- Outbound webhooks. Deliveries are queued in the same transaction as the proposal
+-- snapshot that triggered them and sent by a background worker.
+
+CREATE TABLE IF NOT EXISTS
+  webhook_subscriptions (
+    id serial primary key,
+    url varchar not null,
+    secret varchar not null,
+    event_types text[] not null,
+    category varchar,
+    author_id varchar,
+    active boolean not null default true,
+    created_at timestamptz not null default now()
+  );
+
+CREATE TABLE IF NOT EXISTS
+  webhook_deliveries (
+    id bigserial primary key,
+    subscription_id int not null REFERENCES webhook_subscriptions (id),
+    event_type varchar not null,
+    proposal_id int not null,
+    block_height bigint not null,
+    payload jsonb not null,
+    -- pending, delivered or failed
+    status varchar not null default 'pending',
+    attempts int not null default 0,
+    last_status_code int,
+    last_error varchar,
+    next_attempt_at timestamptz not null default now(),
+    created_at timestamptz not null default now(),
+    delivered_at timestamptz
+  );
+
+CREATE TABLE IF NOT EXISTS
+  webhook_delivery_attempts (
+    id bigserial primary key,
+    delivery_id bigint not null REFERENCES webhook_deliveries (id),
+    status_code int,
+    error varchar,
+    duration_ms bigint not null,
+    attempted_at timestamptz not null default now()
+  );
+
+CREATE INDEX
+  idx_webhook_deliveries_due ON webhook_deliveries (next_attempt_at)
+WHERE
+  status = 'pending';
+
+CREATE INDEX
+  idx_webhook_deliveries_subscription_id ON webhook_deliveries (subscription_id);
+
+CREATE INDEX
+  idx_webhook_delivery_attempts_delivery_id ON webhook_delivery_attempts (delivery_id);
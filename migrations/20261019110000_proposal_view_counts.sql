@@ -0,0 +1,60 @@
+-- View counters live outside the immutable snapshots. A view is counted at most once
+-- per client per dedup window, tracked in proposal_view_clients.
+
+CREATE TABLE IF NOT EXISTS
+  proposal_view_counts (
+    proposal_id int primary key REFERENCES proposals (id) ON DELETE CASCADE,
+    views int not null default 0
+  );
+
+CREATE TABLE IF NOT EXISTS
+  proposal_view_clients (
+    proposal_id int not null REFERENCES proposals (id) ON DELETE CASCADE,
+    -- sha256 of the client identifier, raw IPs are not stored
+    client_key varchar not null,
+    last_counted_at timestamptz not null,
+    primary key (proposal_id, client_key)
+  );
+
+CREATE INDEX IF NOT EXISTS
+  idx_proposal_view_counts_views ON proposal_view_counts (views);
+
+CREATE OR REPLACE VIEW
+  proposals_with_latest_snapshot AS
+SELECT
+  ps.proposal_id,
+  p.author_id,
+  ps.block_height,
+  ps.ts,
+  ps.editor_id,
+  ps.social_db_post_block_height,
+  ps.labels,
+  ps.proposal_version,
+  ps.proposal_body_version,
+  ps.name,
+  ps.category,
+  ps.summary,
+  ps.description,
+  ps.linked_proposals,
+  ps.linked_rfp,
+  ps.requested_sponsorship_usd_amount,
+  ps.requested_sponsorship_paid_in_currency,
+  ps.requested_sponsor,
+  ps.receiver_account,
+  ps.supervisor,
+  ps.timeline,
+  COALESCE(vc.views, 0) AS views
+FROM
+  proposals p
+  INNER JOIN (
+    SELECT
+      proposal_id,
+      MAX(ts) AS max_ts
+    FROM
+      proposal_snapshots
+    GROUP BY
+      proposal_id
+  ) latest_snapshots ON p.id = latest_snapshots.proposal_id
+  INNER JOIN proposal_snapshots ps ON latest_snapshots.proposal_id = ps.proposal_id
+  AND latest_snapshots.max_ts = ps.ts
+  LEFT JOIN proposal_view_counts vc ON vc.proposal_id = p.id;
@@ -0,0 +1,24 @@
+-- Reconciliation runs compare the cached latest snapshots with contract state over RPC.
+-- Issues found (and whether they were repaired) are kept as a JSON report per run.
+
+CREATE TABLE IF NOT EXISTS
+  reconciliation_runs (
+    id serial primary key,
+    -- manual or scheduled
+    trigger varchar not null,
+    repair boolean not null,
+    -- running, completed or failed
+    status varchar not null default 'running',
+    checked_proposals int not null default 0,
+    checked_rfps int not null default 0,
+    issues jsonb not null default '[]',
+    error varchar,
+    started_at timestamptz not null default now(),
+    finished_at timestamptz
+  );
+
+-- At most one run at a time
+CREATE UNIQUE INDEX IF NOT EXISTS
+  idx_reconciliation_runs_running ON reconciliation_runs ((true))
+WHERE
+  status = 'running';
@@ -0,0 +1,10 @@
+-- NearBlocks sync progress, checkpointed after every processed page.
+-- `cursor` holds the next page to fetch while a sync is unfinished and is '' otherwise.
+
+ALTER TABLE last_updated_info ADD COLUMN IF NOT EXISTS sync_pages_fetched bigint NOT NULL DEFAULT 0;
+
+ALTER TABLE last_updated_info ADD COLUMN IF NOT EXISTS sync_last_block bigint;
+
+ALTER TABLE last_updated_info ADD COLUMN IF NOT EXISTS sync_updated_at timestamptz;
+
+UPDATE last_updated_info SET cursor = '' WHERE cursor = 'None';
@@ -0,0 +1,25 @@
+-- Background jobs for long-running work such as NearBlocks syncs, so it doesn't run inside
+-- an HTTP request. Only one job per contract can be queued or running at a time.
+
+CREATE TABLE IF NOT EXISTS
+  jobs (
+    id serial primary key,
+    kind varchar not null,
+    contract varchar not null,
+    status varchar not null default 'queued' CHECK (
+      status IN ('queued', 'running', 'completed', 'failed', 'cancelled')
+    ),
+    max_transactions bigint,
+    pages_fetched bigint not null default 0,
+    transactions_fetched bigint not null default 0,
+    last_block bigint,
+    cancel_requested boolean not null default false,
+    error varchar,
+    created_at timestamptz not null default now(),
+    started_at timestamptz,
+    finished_at timestamptz
+  );
+
+CREATE UNIQUE INDEX IF NOT EXISTS jobs_one_active_per_contract ON jobs (contract)
+WHERE
+  status IN ('queued', 'running');
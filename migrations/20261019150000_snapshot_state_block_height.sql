@@ -0,0 +1,6 @@
+-- Block the contract state behind each snapshot was read at. NULL for snapshots
+-- stored before ingestion started recording it.
+
+ALTER TABLE proposal_snapshots ADD COLUMN IF NOT EXISTS state_block_height bigint;
+
+ALTER TABLE rfp_snapshots ADD COLUMN IF NOT EXISTS state_block_height bigint;
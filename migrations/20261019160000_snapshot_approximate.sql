@@ -0,0 +1,6 @@
+-- Set when the snapshot's state could not be read as of its receipt and the latest
+-- final state was stored instead.
+
+ALTER TABLE proposal_snapshots ADD COLUMN IF NOT EXISTS approximate boolean NOT NULL DEFAULT false;
+
+ALTER TABLE rfp_snapshots ADD COLUMN IF NOT EXISTS approximate boolean NOT NULL DEFAULT false;
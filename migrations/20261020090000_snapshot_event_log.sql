@@ -0,0 +1,59 @@
+-- Ordered log of snapshot writes behind /events. Block heights can't serve as the
+-- cursor: backfills, repairs and rewrites commit snapshots below heights a client has
+-- already seen. Every insert or change of a snapshot appends an entry instead, and the
+-- entry id is the SSE event id.
+
+CREATE TABLE IF NOT EXISTS
+  snapshot_event_log (
+    id bigserial primary key,
+    -- 'proposal' or 'rfp'
+    kind varchar not null,
+    entity_id int not null,
+    ts bigint not null
+  );
+
+CREATE OR REPLACE FUNCTION notify_snapshot_event() RETURNS trigger AS $$
+DECLARE
+  entity_id int;
+  event_id bigint;
+BEGIN
+  IF TG_ARGV[0] = 'proposal' THEN
+    entity_id := NEW.proposal_id;
+  ELSE
+    entity_id := NEW.rfp_id;
+  END IF;
+
+  -- Held until commit, so writers draw ids one transaction at a time and an id is
+  -- never committed after a larger one became visible to a reader
+  PERFORM pg_advisory_xact_lock(hashtext('snapshot_event_log'));
+
+  INSERT INTO snapshot_event_log (kind, entity_id, ts)
+  VALUES (TG_ARGV[0], entity_id, NEW.ts)
+  RETURNING id INTO event_id;
+
+  PERFORM pg_notify('snapshot_events', event_id::text);
+  RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS proposal_snapshots_notify ON proposal_snapshots;
+
+DROP TRIGGER IF EXISTS rfp_snapshots_notify ON rfp_snapshots;
+
+CREATE TRIGGER proposal_snapshots_notify
+  AFTER INSERT ON proposal_snapshots
+  FOR EACH ROW EXECUTE FUNCTION notify_snapshot_event('proposal');
+
+CREATE TRIGGER proposal_snapshots_notify_update
+  AFTER UPDATE ON proposal_snapshots
+  FOR EACH ROW WHEN (OLD.* IS DISTINCT FROM NEW.*)
+  EXECUTE FUNCTION notify_snapshot_event('proposal');
+
+CREATE TRIGGER rfp_snapshots_notify
+  AFTER INSERT ON rfp_snapshots
+  FOR EACH ROW EXECUTE FUNCTION notify_snapshot_event('rfp');
+
+CREATE TRIGGER rfp_snapshots_notify_update
+  AFTER UPDATE ON rfp_snapshots
+  FOR EACH ROW WHEN (OLD.* IS DISTINCT FROM NEW.*)
+  EXECUTE FUNCTION notify_snapshot_event('rfp');
@@ -0,0 +1,4 @@
+-- Rows older than the dedup window are pruned periodically by last_counted_at.
+
+CREATE INDEX IF NOT EXISTS
+  idx_proposal_view_clients_last_counted_at ON proposal_view_clients (last_counted_at);
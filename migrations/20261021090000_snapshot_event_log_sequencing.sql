@@ -0,0 +1,76 @@
+-- Snapshot writers no longer take a global lock to draw event ids, which made every
+-- transaction that stores snapshots wait for the others to commit. The trigger now
+-- appends entries without an id, and assign_snapshot_event_ids() numbers committed
+-- entries afterwards. Passes run one at a time and only see committed entries, so
+-- ids still become visible in order and a reader never sees an id appear below its
+-- cursor. Only inserted snapshots are logged; rewriting a stored one is not an event.
+
+ALTER TABLE snapshot_event_log
+ADD COLUMN entry_id bigserial;
+
+ALTER TABLE snapshot_event_log
+DROP CONSTRAINT snapshot_event_log_pkey;
+
+ALTER TABLE snapshot_event_log
+ADD PRIMARY KEY (entry_id);
+
+ALTER TABLE snapshot_event_log
+ALTER COLUMN id
+DROP NOT NULL,
+ALTER COLUMN id
+DROP DEFAULT;
+
+CREATE UNIQUE INDEX IF NOT EXISTS snapshot_event_log_id_idx ON snapshot_event_log (id);
+
+CREATE INDEX IF NOT EXISTS snapshot_event_log_unassigned_idx ON snapshot_event_log (entry_id)
+WHERE
+  id IS NULL;
+
+CREATE OR REPLACE FUNCTION notify_snapshot_event() RETURNS trigger AS $$
+DECLARE
+  entity_id int;
+BEGIN
+  IF TG_ARGV[0] = 'proposal' THEN
+    entity_id := NEW.proposal_id;
+  ELSE
+    entity_id := NEW.rfp_id;
+  END IF;
+
+  INSERT INTO snapshot_event_log (kind, entity_id, ts)
+  VALUES (TG_ARGV[0], entity_id, NEW.ts);
+
+  -- Delivered on commit; the listener then assigns the ids
+  PERFORM pg_notify('snapshot_events', '');
+  RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+-- Numbers every committed entry that has no id yet and returns how many it numbered
+CREATE OR REPLACE FUNCTION assign_snapshot_event_ids() RETURNS bigint AS $$
+DECLARE
+  assigned bigint;
+BEGIN
+  -- Held until commit, so the next pass sees these ids before drawing larger ones
+  PERFORM pg_advisory_xact_lock(hashtext('snapshot_event_log'));
+
+  UPDATE snapshot_event_log l
+  SET id = numbered.id
+  FROM (
+    SELECT entry_id, nextval('snapshot_event_log_id_seq') AS id
+    FROM (
+      SELECT entry_id
+      FROM snapshot_event_log
+      WHERE id IS NULL
+      ORDER BY entry_id
+    ) pending
+  ) numbered
+  WHERE l.entry_id = numbered.entry_id;
+
+  GET DIAGNOSTICS assigned = ROW_COUNT;
+  RETURN assigned;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS proposal_snapshots_notify_update ON proposal_snapshots;
+
+DROP TRIGGER IF EXISTS rfp_snapshots_notify_update ON rfp_snapshots;
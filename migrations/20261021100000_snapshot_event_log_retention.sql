@@ -0,0 +1,7 @@
+-- Numbered events older than the retention window are pruned periodically by logged_at.
+
+ALTER TABLE snapshot_event_log
+ADD COLUMN IF NOT EXISTS logged_at timestamptz NOT NULL DEFAULT now();
+
+CREATE INDEX IF NOT EXISTS
+  idx_snapshot_event_log_logged_at ON snapshot_event_log (logged_at);
@@ -0,0 +1,42 @@
+use rocket::http::Status;
+use rocket::request::{self, FromRequest, Request};
+use sha2::{Digest, Sha256};
+use subtle::ConstantTimeEq;
+
+/// Guard for admin routes: requires `Authorization: Bearer <admin_api_key>`, where the key
+/// comes from the Rocket config (`ROCKET_ADMIN_API_KEY`). Admin routes are disabled when no
+/// key is configured.
+pub struct Admin;
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Admin {
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        let Ok(admin_api_key) = request
+            .rocket()
+            .figment()
+            .extract_inner::<String>("admin_api_key")
+        else {
+            return request::Outcome::Error((Status::Forbidden, ()));
+        };
+
+        let bearer = request
+            .headers()
+            .get_one("Authorization")
+            .and_then(|value| value.strip_prefix("Bearer "));
+        match bearer {
+            Some(token) if !admin_api_key.is_empty() && token_matches(token, &admin_api_key) => {
+                request::Outcome::Success(Admin)
+            }
+            _ => request::Outcome::Error((Status::Unauthorized, ())),
+        }
+    }
+}
+
+// Compares digests in constant time, so response timing reveals neither the key nor its length
+fn token_matches(token: &str, admin_api_key: &str) -> bool {
+    Sha256::digest(token.as_bytes())
+        .ct_eq(&Sha256::digest(admin_api_key.as_bytes()))
+        .into()
+}
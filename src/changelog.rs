@@ -1,14 +1,103 @@
 use crate::db::db_types::{ProposalSnapshotRecord, RfpSnapshotRecord};
 use crate::db::DB;
+use crate::entrypoints::cache::invalidate;
 use crate::entrypoints::proposal::proposal_types::FromContractProposal;
 use crate::entrypoints::rfp::rfp_types::FromContractRFP;
-use crate::rpc_service::{ChangeLog, ChangeLogType, RpcService};
+use crate::rpc_service::{
+    proposal_snapshot_since, rfp_snapshot_since, ChangeLog, ChangeLogType, RpcService,
+};
 use devhub_shared::proposal::VersionedProposal;
 use devhub_shared::rfp::VersionedRFP;
+use futures::{stream, StreamExt, TryStreamExt};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::sync::RwLock;
+use utoipa::ToSchema;
+
+/// Outcome of the latest changelog ingestions in this process.
+#[derive(Clone, Debug, Default, Serialize, ToSchema)]
+pub struct IngestionStatus {
+    pub last_success_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub last_error: Option<String>,
+    pub last_error_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+static INGESTION_STATUS: RwLock<IngestionStatus> = RwLock::new(IngestionStatus {
+    last_success_at: None,
+    last_error: None,
+    last_error_at: None,
+});
+
+pub fn ingestion_status() -> IngestionStatus {
+    INGESTION_STATUS
+        .read()
+        .map(|status| status.clone())
+        .unwrap_or_default()
+}
+
+fn record_ingestion<T>(result: &anyhow::Result<T>) {
+    let Ok(mut status) = INGESTION_STATUS.write() else {
+        return;
+    };
+    match result {
+        Ok(_) => status.last_success_at = Some(chrono::Utc::now()),
+        Err(e) => {
+            status.last_error = Some(e.to_string());
+            status.last_error_at = Some(chrono::Utc::now());
+        }
+    }
+}
+
 pub async fn fetch_changelog_from_rpc(
     db: &DB,
     rpc_service: &RpcService,
     after_block: Option<i64>,
+) -> anyhow::Result<usize> {
+    let result = ingest_changelog(db, rpc_service, after_block).await;
+    record_ingestion(&result);
+    result
+}
+
+/// Changes applied per database transaction.
+const CHANGELOG_BATCH_SIZE: usize = 50;
+/// Contract reads in flight while applying a changelog.
+const CHANGELOG_FETCH_CONCURRENCY: usize = 8;
+
+/// Contract state for one changelog entry, ready to store.
+enum ChangeSnapshot {
+    Proposal {
+        author_id: String,
+        snapshot: ProposalSnapshotRecord,
+    },
+    Rfp {
+        author_id: String,
+        snapshot: RfpSnapshotRecord,
+    },
+}
+
+/// Changes in block order with repeats of the same entity at the same block dropped.
+/// Changes to an entity at different blocks are all kept, each gets its own snapshot.
+pub fn dedupe_changes(changes: &[ChangeLog]) -> Vec<ChangeLog> {
+    let mut seen = HashSet::new();
+    let mut unique: Vec<ChangeLog> = changes
+        .iter()
+        .filter(|change| {
+            let entity = match change.change_log_type {
+                ChangeLogType::Proposal(id) => (false, id),
+                ChangeLogType::RFP(id) => (true, id),
+            };
+            seen.insert((entity, change.block_id))
+        })
+        .cloned()
+        .collect();
+    unique.sort_by_key(|change| change.block_id);
+    unique
+}
+
+async fn ingest_changelog(
+    db: &DB,
+    rpc_service: &RpcService,
+    after_block: Option<i64>,
 ) -> anyhow::Result<usize> {
     let result = match rpc_service.get_change_log_since(after_block.unwrap()).await {
         Ok(change_log) => change_log,
@@ -18,127 +107,220 @@ pub async fn fetch_changelog_from_rpc(
         }
     };
 
-    for change in result.clone() {
-        // Get the latest proposal
-        match change.change_log_type {
-            ChangeLogType::Proposal(proposal_id) => {
-                handle_proposal_change(db, rpc_service, proposal_id, &change).await?;
+    // A failed batch is not committed, so the next run retries it from the last stored block
+    for batch in dedupe_changes(&result).chunks(CHANGELOG_BATCH_SIZE) {
+        let snapshots: Vec<ChangeSnapshot> = stream::iter(batch.iter().cloned())
+            .map(|change| async move { fetch_change_snapshot(rpc_service, &change).await })
+            .buffered(CHANGELOG_FETCH_CONCURRENCY)
+            .try_collect()
+            .await?;
+        let last_block = batch.iter().map(|change| change.block_id).max();
+        store_batch(db, &snapshots, last_block).await?;
+    }
+    Ok(result.len())
+}
+
+async fn fetch_change_snapshot(
+    rpc_service: &RpcService,
+    change: &ChangeLog,
+) -> anyhow::Result<ChangeSnapshot> {
+    match change.change_log_type {
+        ChangeLogType::Proposal(proposal_id) => {
+            fetch_proposal_change(rpc_service, proposal_id, change).await
+        }
+        ChangeLogType::RFP(rfp_id) => fetch_rfp_change(rpc_service, rfp_id, change).await,
+    }
+}
+
+async fn store_batch(
+    db: &DB,
+    snapshots: &[ChangeSnapshot],
+    last_block: Option<u64>,
+) -> anyhow::Result<()> {
+    let mut tx = db.begin().await.map_err(|e| {
+        eprintln!("Failed to begin transaction: {:?}", e);
+        anyhow::anyhow!("Failed to begin transaction")
+    })?;
+
+    for change in snapshots {
+        match change {
+            ChangeSnapshot::Proposal {
+                author_id,
+                snapshot,
+            } => {
+                let proposal_id = snapshot.proposal_id as u32;
+                DB::upsert_proposal(&mut tx, proposal_id, author_id.clone())
+                    .await
+                    .map_err(|e| {
+                        eprintln!("Failed to upsert proposal {}: {:?}", proposal_id, e);
+                        anyhow::anyhow!("Failed to upsert proposal")
+                    })?;
+                DB::insert_live_proposal_snapshot(&mut tx, snapshot)
+                    .await
+                    .map_err(|e| {
+                        eprintln!(
+                            "Failed to insert proposal snapshot for proposal {}: {:?}",
+                            proposal_id, e
+                        );
+                        anyhow::anyhow!("Failed to insert proposal snapshot")
+                    })?;
             }
-            ChangeLogType::RFP(rfp_id) => {
-                handle_rfp_change(db, rpc_service, rfp_id, &change).await?;
+            ChangeSnapshot::Rfp {
+                author_id,
+                snapshot,
+            } => {
+                let rfp_id = snapshot.rfp_id as u32;
+                DB::upsert_rfp(&mut tx, rfp_id, author_id.clone())
+                    .await
+                    .map_err(|e| {
+                        eprintln!("Failed to upsert rfp {}: {:?}", rfp_id, e);
+                        anyhow::anyhow!("Failed to upsert rfp")
+                    })?;
+                DB::insert_rfp_snapshot(&mut tx, snapshot)
+                    .await
+                    .map_err(|e| {
+                        eprintln!("Failed to insert rfp snapshot for rfp {}: {:?}", rfp_id, e);
+                        anyhow::anyhow!("Failed to insert rfp snapshot")
+                    })?;
             }
         }
     }
-    Ok(result.len())
+
+    if let Some(last_block) = last_block {
+        DB::set_last_updated_block_on_tx(&mut tx, last_block as i64)
+            .await
+            .map_err(|e| {
+                eprintln!("Failed to set last updated block on tx: {:?}", e);
+                anyhow::anyhow!("Failed to set last updated block on tx")
+            })?;
+    }
+
+    tx.commit().await.map_err(|e| {
+        eprintln!("Failed to commit transaction: {:?}", e);
+        anyhow::anyhow!("Failed to commit transaction")
+    })?;
+    invalidate();
+    Ok(())
 }
 
-async fn handle_proposal_change(
-    db: &DB,
+async fn fetch_proposal_change(
     rpc_service: &RpcService,
     proposal_id: u32,
     change: &ChangeLog,
-) -> anyhow::Result<()> {
-    let versioned_proposal = match rpc_service.get_proposal(proposal_id as i32).await {
-        Ok(proposal) => proposal.data,
+) -> anyhow::Result<ChangeSnapshot> {
+    let (versioned_proposal, state_block_height, approximate) = match rpc_service
+        .get_proposal_after_receipt(proposal_id as i32, change.block_id as i64, |proposal| {
+            proposal_snapshot_since(proposal, change.block_timestamp)
+        })
+        .await
+    {
+        Ok(read) => (
+            read.state.data,
+            read.state.block_height as i64,
+            read.approximate,
+        ),
         Err(e) => {
             eprintln!("Error fetching proposal: {:?}", e);
             return Err(anyhow::anyhow!("Error fetching proposal"));
         }
     };
-    // Add proposal
-    let mut tx = db.begin().await.map_err(|e| {
-        eprintln!("Failed to begin transaction: {:?}", e);
-        anyhow::anyhow!("Failed to begin transaction")
-    })?;
 
     let author_id = match versioned_proposal.clone() {
         VersionedProposal::V0(proposal) => proposal.author_id,
     };
 
-    DB::upsert_proposal(&mut tx, proposal_id, author_id.to_string())
-        .await
-        .map_err(|e| {
-            eprintln!("Failed to upsert proposal {}: {:?}", proposal_id, e);
-            anyhow::anyhow!("Failed to upsert proposal")
-        })?;
-    let snapshot = ProposalSnapshotRecord::from_contract_proposal(
+    let mut snapshot = ProposalSnapshotRecord::from_contract_proposal(
         versioned_proposal.into(),
         change.block_timestamp as i64,
         change.block_id as i64,
     );
-    DB::insert_proposal_snapshot(&mut tx, &snapshot)
-        .await
-        .map_err(|e| {
-            eprintln!(
-                "Failed to insert proposal snapshot for proposal {}: {:?}",
-                proposal_id, e
-            );
-            anyhow::anyhow!("Failed to insert proposal snapshot")
-        })?;
-    DB::set_last_updated_block_on_tx(&mut tx, change.block_id as i64)
-        .await
-        .map_err(|e| {
-            eprintln!("Failed to set last updated block on tx: {:?}", e);
-            anyhow::anyhow!("Failed to set last updated block on tx")
-        })?;
-
-    tx.commit().await.map_err(|e| {
-        eprintln!("Failed to commit transaction: {:?}", e);
-        anyhow::anyhow!("Failed to commit transaction")
-    })?;
-    Ok(())
+    snapshot.state_block_height = Some(state_block_height);
+    snapshot.approximate = approximate;
+    Ok(ChangeSnapshot::Proposal {
+        author_id: author_id.to_string(),
+        snapshot,
+    })
 }
 
-async fn handle_rfp_change(
-    db: &DB,
+async fn fetch_rfp_change(
     rpc_service: &RpcService,
     rfp_id: u32,
     change: &ChangeLog,
-) -> anyhow::Result<()> {
-    let versioned_rfp = match rpc_service.get_rfp(rfp_id as i32).await {
-        Ok(rfp) => rfp.data,
+) -> anyhow::Result<ChangeSnapshot> {
+    let (versioned_rfp, state_block_height, approximate) = match rpc_service
+        .get_rfp_after_receipt(rfp_id as i32, change.block_id as i64, |rfp| {
+            rfp_snapshot_since(rfp, change.block_timestamp)
+        })
+        .await
+    {
+        Ok(read) => (
+            read.state.data,
+            read.state.block_height as i64,
+            read.approximate,
+        ),
         Err(e) => {
             eprintln!("Error fetching rfp: {:?}", e);
             return Err(anyhow::anyhow!("Error fetching rfp"));
         }
     };
-    // Add rfp
-    let mut tx = db.begin().await.map_err(|e| {
-        eprintln!("Failed to begin transaction: {:?}", e);
-        anyhow::anyhow!("Failed to begin transaction")
-    })?;
+
     let author_id = match versioned_rfp.clone() {
         VersionedRFP::V0(rfp) => rfp.author_id,
     };
 
-    DB::upsert_rfp(&mut tx, rfp_id, author_id.to_string())
-        .await
-        .map_err(|e| {
-            eprintln!("Failed to upsert rfp {}: {:?}", rfp_id, e);
-            anyhow::anyhow!("Failed to upsert rfp")
-        })?;
-    let snapshot = RfpSnapshotRecord::from_contract_rfp(
+    let mut snapshot = RfpSnapshotRecord::from_contract_rfp(
         versioned_rfp.into(),
         change.block_timestamp as i64,
         change.block_id as i64,
     );
-    DB::insert_rfp_snapshot(&mut tx, &snapshot)
-        .await
-        .map_err(|e| {
-            eprintln!("Failed to insert rfp snapshot for rfp {}: {:?}", rfp_id, e);
-            anyhow::anyhow!("Failed to insert rfp snapshot")
-        })?;
+    snapshot.state_block_height = Some(state_block_height);
+    snapshot.approximate = approximate;
+    Ok(ChangeSnapshot::Rfp {
+        author_id: author_id.to_string(),
+        snapshot,
+    })
+}
 
-    DB::set_last_updated_block_on_tx(&mut tx, change.block_id as i64)
-        .await
-        .map_err(|e| {
-            eprintln!("Failed to set last updated block on tx: {:?}", e);
-            anyhow::anyhow!("Failed to set last updated block on tx")
-        })?;
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    tx.commit().await.map_err(|e| {
-        eprintln!("Failed to commit transaction: {:?}", e);
-        anyhow::anyhow!("Failed to commit transaction")
-    })?;
-    Ok(())
+    fn change(kind: ChangeLogType, block_id: u64) -> ChangeLog {
+        ChangeLog {
+            block_id,
+            block_timestamp: block_id * 1_000_000_000,
+            change_log_type: kind,
+        }
+    }
+
+    #[test]
+    fn test_dedupe_changes() {
+        let changes = vec![
+            change(ChangeLogType::Proposal(1), 20),
+            change(ChangeLogType::Proposal(1), 10),
+            change(ChangeLogType::Proposal(1), 20),
+            change(ChangeLogType::RFP(1), 20),
+            change(ChangeLogType::Proposal(2), 15),
+            change(ChangeLogType::Proposal(1), 10),
+        ];
+        let unique: Vec<(String, u64)> = dedupe_changes(&changes)
+            .iter()
+            .map(|change| {
+                let entity = match change.change_log_type {
+                    ChangeLogType::Proposal(id) => format!("proposal {}", id),
+                    ChangeLogType::RFP(id) => format!("rfp {}", id),
+                };
+                (entity, change.block_id)
+            })
+            .collect();
+        assert_eq!(
+            unique,
+            vec![
+                ("proposal 1".to_string(), 10),
+                ("proposal 2".to_string(), 15),
+                ("proposal 1".to_string(), 20),
+                ("rfp 1".to_string(), 20),
+            ]
+        );
+    }
 }
@@ -1,3 +1,4 @@
+use async_graphql::SimpleObject;
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use utoipa::ToSchema;
@@ -17,10 +18,19 @@ pub struct ProposalRecord {
 pub struct LastUpdatedInfo {
     pub after_date: i64,
     pub after_block: i64,
+    /// NearBlocks cursor of the next page of an unfinished sync, empty otherwise
     pub cursor: String,
+    /// Pages processed by the current or last NearBlocks sync
+    #[serde(default)]
+    pub sync_pages_fetched: i64,
+    /// Block of the last transaction processed by the NearBlocks sync
+    #[serde(default)]
+    pub sync_last_block: Option<i64>,
+    #[serde(default)]
+    pub sync_updated_at: Option<chrono::DateTime<chrono::Utc>>,
 }
 
-#[derive(Debug, Clone, FromRow, Serialize, Deserialize, ToSchema)]
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize, ToSchema, SimpleObject)]
 pub struct ProposalSnapshotRecord {
     pub proposal_id: i32,
     pub block_height: BlockHeight,
@@ -43,6 +53,14 @@ pub struct ProposalSnapshotRecord {
     pub supervisor: Option<String>,
     pub timeline: Option<serde_json::Value>,
     pub views: Option<i32>,
+    /// Block the contract state was read at, when it was recorded.
+    #[sqlx(default)]
+    #[serde(default)]
+    pub state_block_height: Option<BlockHeight>,
+    /// The state is the latest final one rather than the one its receipt produced.
+    #[sqlx(default)]
+    #[serde(default)]
+    pub approximate: bool,
 }
 
 #[derive(Debug, Clone, FromRow, Serialize, Deserialize, ToSchema)]
@@ -56,7 +74,8 @@ pub struct DumpRecord {
     pub proposal_id: i32,
 }
 
-#[derive(Debug, Clone, FromRow, Serialize, Deserialize, ToSchema)]
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize, ToSchema, SimpleObject)]
+#[graphql(complex)]
 pub struct ProposalWithLatestSnapshotView {
     pub proposal_id: i32,
     pub author_id: String,
@@ -71,7 +90,10 @@ pub struct ProposalWithLatestSnapshotView {
     pub category: Option<String>,
     pub summary: Option<String>,
     pub description: Option<String>,
+    // Resolved into entities by the GraphQL `linkedProposals` and `linkedRfp` fields
+    #[graphql(name = "linkedProposalIds")]
     pub linked_proposals: Option<serde_json::Value>,
+    #[graphql(name = "linkedRfpId")]
     pub linked_rfp: Option<i32>,
     pub requested_sponsorship_usd_amount: Option<i32>,
     pub requested_sponsorship_paid_in_currency: Option<String>,
@@ -88,7 +110,7 @@ pub struct RfpRecord {
     pub author_id: String,
 }
 
-#[derive(Debug, Clone, FromRow, Serialize, Deserialize, ToSchema)]
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize, ToSchema, SimpleObject)]
 pub struct RfpSnapshotRecord {
     pub rfp_id: i32,
     pub block_height: BlockHeight,
@@ -106,9 +128,18 @@ pub struct RfpSnapshotRecord {
     pub timeline: Option<serde_json::Value>,
     pub submission_deadline: Timestamp,
     pub views: Option<i32>,
+    /// Block the contract state was read at, when it was recorded.
+    #[sqlx(default)]
+    #[serde(default)]
+    pub state_block_height: Option<BlockHeight>,
+    /// The state is the latest final one rather than the one its receipt produced.
+    #[sqlx(default)]
+    #[serde(default)]
+    pub approximate: bool,
 }
 
-#[derive(Debug, Clone, FromRow, Serialize, Deserialize, ToSchema)]
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize, ToSchema, SimpleObject)]
+#[graphql(complex)]
 pub struct RfpWithLatestSnapshotView {
     pub rfp_id: i32,
     pub author_id: String,
@@ -117,6 +148,7 @@ pub struct RfpWithLatestSnapshotView {
     pub editor_id: String,
     pub social_db_post_block_height: BlockHeight,
     pub labels: serde_json::Value,
+    #[graphql(name = "linkedProposalIds")]
     pub linked_proposals: Option<serde_json::Value>,
     pub rfp_version: String,
     pub rfp_body_version: String,
@@ -139,3 +171,180 @@ pub struct RfpDumpRecord {
     pub author: String,
     pub rfp_id: i32,
 }
+
+/// One committed proposal or RFP snapshot, as pushed on `/events`.
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize, ToSchema)]
+pub struct SnapshotEvent {
+    pub event_id: i64,
+    /// `proposal` or `rfp`
+    pub kind: String,
+    pub id: i32,
+    pub block_height: BlockHeight,
+    pub ts: Timestamp,
+    pub editor_id: String,
+    pub author_id: String,
+    pub name: Option<String>,
+    pub category: Option<String>,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize, ToSchema)]
+pub struct WebhookSubscription {
+    pub id: i32,
+    pub url: String,
+    #[serde(skip_serializing)]
+    pub secret: String,
+    pub event_types: Vec<String>,
+    pub category: Option<String>,
+    pub author_id: Option<String>,
+    pub active: bool,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize, ToSchema)]
+pub struct WebhookDelivery {
+    pub id: i64,
+    pub subscription_id: i32,
+    pub event_type: String,
+    pub proposal_id: i32,
+    pub block_height: BlockHeight,
+    pub payload: serde_json::Value,
+    pub status: String,
+    pub attempts: i32,
+    pub last_status_code: Option<i32>,
+    pub last_error: Option<String>,
+    pub next_attempt_at: chrono::DateTime<chrono::Utc>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub delivered_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize, ToSchema)]
+pub struct WebhookDeliveryAttempt {
+    pub id: i64,
+    pub delivery_id: i64,
+    pub status_code: Option<i32>,
+    pub error: Option<String>,
+    pub duration_ms: i64,
+    pub attempted_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// A pending delivery claimed by the worker, with what it needs to send it.
+#[derive(Debug, Clone, FromRow)]
+pub struct DueWebhookDelivery {
+    pub id: i64,
+    pub event_type: String,
+    pub payload: serde_json::Value,
+    pub attempts: i32,
+    pub url: String,
+    pub secret: String,
+}
+
+/// A creation or stage change of a proposal or RFP, derived from its snapshot history.
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize, ToSchema)]
+pub struct FeedEntryRecord {
+    pub id: i32,
+    pub author_id: String,
+    pub block_height: BlockHeight,
+    pub ts: Timestamp,
+    pub editor_id: String,
+    pub name: Option<String>,
+    pub category: Option<String>,
+    pub summary: Option<String>,
+    pub stage: Option<String>,
+    pub previous_stage: Option<String>,
+    /// The first snapshot of the entity
+    pub created: bool,
+}
+
+/// Funding totals for one group of a `GROUPING SETS` query; `dimension` is `total`
+/// for the row covering every proposal.
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize, ToSchema)]
+pub struct FundingStatsRecord {
+    pub dimension: String,
+    pub key: Option<String>,
+    pub proposals: i64,
+    pub requested_usd: i64,
+    pub approved_usd: i64,
+    pub paid_usd: i64,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize, ToSchema)]
+pub struct AccountActivityRecord {
+    pub proposals_authored: i64,
+    pub proposals_edited: i64,
+    pub proposals_supervised: i64,
+    pub proposals_sponsor_requested: i64,
+    pub proposals_receiver: i64,
+    pub rfps_authored: i64,
+    pub rfps_edited: i64,
+}
+
+/// Completed stays in a stage: how long proposals took to move on from it.
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize, ToSchema)]
+pub struct StageDurationRecord {
+    pub stage: String,
+    pub completed: i64,
+    pub median_seconds: f64,
+    pub p90_seconds: f64,
+}
+
+/// Proposals entering and leaving a stage during a week starting on Monday.
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize, ToSchema)]
+pub struct StageWeekRecord {
+    /// `YYYY-MM-DD` of the Monday
+    pub week: String,
+    pub stage: String,
+    pub entered: i64,
+    pub left: i64,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize, ToSchema)]
+pub struct SlaBreachRecord {
+    pub proposal_id: i32,
+    pub name: Option<String>,
+    pub author_id: String,
+    pub stage: String,
+    /// When the proposal entered its current stage, in nanoseconds
+    pub entered_ts: Timestamp,
+    pub elapsed_seconds: i64,
+}
+
+/// A label or category and how many entities currently use it.
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize, ToSchema)]
+pub struct TaxonomyCount {
+    pub value: String,
+    pub count: i64,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize, ToSchema)]
+pub struct ReconciliationRun {
+    pub id: i32,
+    pub trigger: String,
+    pub repair: bool,
+    pub status: String,
+    pub checked_proposals: i32,
+    pub checked_rfps: i32,
+    /// `ReconciliationIssue`s found by the run
+    pub issues: serde_json::Value,
+    pub error: Option<String>,
+    pub started_at: chrono::DateTime<chrono::Utc>,
+    pub finished_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize, ToSchema)]
+pub struct Job {
+    pub id: i32,
+    /// `sync_from_start` or `continue_sync`
+    pub kind: String,
+    pub contract: String,
+    /// `queued`, `running`, `completed`, `failed` or `cancelled`
+    pub status: String,
+    pub max_transactions: Option<i64>,
+    pub pages_fetched: i64,
+    pub transactions_fetched: i64,
+    pub last_block: Option<i64>,
+    pub cancel_requested: bool,
+    pub error: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub started_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub finished_at: Option<chrono::DateTime<chrono::Utc>>,
+}
@@ -1,7 +1,15 @@
 use crate::{
-    entrypoints::{proposal::proposal_types::GetProposalFilters, rfp::rfp_types::GetRfpFilters},
+    entrypoints::{
+        accounts::accounts_types::{ProposalRelation, RfpRelation},
+        events::events_types::EventFilters,
+        proposal::proposal_types::GetProposalFilters,
+        rfp::rfp_types::GetRfpFilters,
+    },
     timestamp_to_date_string,
+    types::TaxonomyKind,
+    webhooks,
 };
+use rocket::futures::stream::BoxStream;
 use rocket::{
     fairing::{self, AdHoc},
     Build, Rocket,
@@ -13,11 +21,18 @@ use sqlx::{migrate, query, Error, PgPool, Postgres, Transaction};
 #[database("my_db")]
 pub struct DB(PgPool);
 
+/// Postgres channel notified when a snapshot write commits, see the
+/// `snapshot_event_log_sequencing` migration.
+pub const SNAPSHOT_EVENTS_CHANNEL: &str = "snapshot_events";
+
 pub mod db_types;
 
 use db_types::{
-    BlockHeight, LastUpdatedInfo, ProposalSnapshotRecord, ProposalWithLatestSnapshotView,
-    RfpSnapshotRecord, RfpWithLatestSnapshotView,
+    AccountActivityRecord, BlockHeight, DueWebhookDelivery, FeedEntryRecord, FundingStatsRecord,
+    Job, LastUpdatedInfo, ProposalSnapshotRecord, ProposalWithLatestSnapshotView,
+    ReconciliationRun, RfpSnapshotRecord, RfpWithLatestSnapshotView, SlaBreachRecord,
+    SnapshotEvent, StageDurationRecord, StageWeekRecord, TaxonomyCount, Timestamp, WebhookDelivery,
+    WebhookDeliveryAttempt, WebhookSubscription,
 };
 
 impl DB {
@@ -63,7 +78,9 @@ impl DB {
     pub async fn get_last_updated_info(&self) -> Result<LastUpdatedInfo, Error> {
         let rec = query!(
             r#"
-            SELECT after_date, after_block, cursor FROM last_updated_info
+            SELECT after_date, after_block, cursor, sync_pages_fetched, sync_last_block,
+              sync_updated_at
+            FROM last_updated_info
             "#
         )
         .fetch_one(&self.0)
@@ -72,6 +89,9 @@ impl DB {
             after_date: rec.after_date,
             after_block: rec.after_block,
             cursor: rec.cursor,
+            sync_pages_fetched: rec.sync_pages_fetched,
+            sync_last_block: rec.sync_last_block,
+            sync_updated_at: rec.sync_updated_at,
         })
     }
 
@@ -163,6 +183,72 @@ impl DB {
         Ok(())
     }
 
+    /// Clears the progress of any previous NearBlocks sync before a new one starts.
+    pub async fn start_nearblocks_sync(&self) -> Result<(), Error> {
+        sqlx::query!(
+            r#"
+          UPDATE last_updated_info
+          SET cursor = '', sync_pages_fetched = 0, sync_last_block = NULL, sync_updated_at = now()
+          "#
+        )
+        .execute(&self.0)
+        .await?;
+        Ok(())
+    }
+
+    /// Stores NearBlocks sync progress once a page is processed. `cursor` is the next page
+    /// to fetch, or empty when the sync is done.
+    pub async fn checkpoint_nearblocks_sync(
+        &self,
+        cursor: &str,
+        pages_fetched: i64,
+        last_block: Option<BlockHeight>,
+    ) -> Result<(), Error> {
+        sqlx::query!(
+            r#"
+          UPDATE last_updated_info
+          SET
+            cursor = $1,
+            sync_pages_fetched = $2,
+            sync_last_block = COALESCE($3, sync_last_block),
+            sync_updated_at = now()
+          "#,
+            cursor,
+            pages_fetched,
+            last_block
+        )
+        .execute(&self.0)
+        .await?;
+        Ok(())
+    }
+
+    /// Stores a snapshot found by live changelog ingestion and queues the webhook events
+    /// it stands for. Syncs from NearBlocks, backfills and repairs store history and go
+    /// through `insert_proposal_snapshot`, so subscribers aren't sent old events.
+    pub async fn insert_live_proposal_snapshot(
+        tx: &mut Transaction<'static, Postgres>,
+        snapshot: &ProposalSnapshotRecord,
+    ) -> anyhow::Result<()> {
+        let latest = DB::get_latest_proposal_snapshot_on_tx(tx, snapshot.proposal_id)
+            .await
+            .map_err(|e| {
+                eprintln!("Failed to get latest proposal snapshot: {:?}", e);
+                anyhow::anyhow!("Failed to get latest proposal snapshot")
+            })?;
+
+        DB::insert_proposal_snapshot(tx, snapshot).await?;
+
+        // Re-indexed or out of order snapshots aren't news
+        if latest.as_ref().is_some_and(|l| l.ts >= snapshot.ts) {
+            return Ok(());
+        }
+        for event in webhooks::proposal_events(latest.as_ref(), snapshot) {
+            let payload = webhooks::proposal_event_payload(event, latest.as_ref(), snapshot);
+            DB::enqueue_webhook_deliveries(tx, event.as_str(), snapshot, payload).await?;
+        }
+        Ok(())
+    }
+
     pub async fn insert_proposal_snapshot(
         tx: &mut Transaction<'static, Postgres>,
         snapshot: &ProposalSnapshotRecord,
@@ -191,11 +277,13 @@ impl DB {
               receiver_account,
               supervisor,
               timeline,
-              views
+              views,
+              state_block_height,
+              approximate
           ) VALUES (
               $1, $2, $3, $4, $5, $6, $7, $8,
               $9, $10, $11, $12, $13, $14,
-              $15, $16, $17, $18, $19, $20, $21
+              $15, $16, $17, $18, $19, $20, $21, $22, $23
           ) ON CONFLICT (proposal_id, ts) DO UPDATE SET
               block_height = $2,
               editor_id = $4,
@@ -215,7 +303,9 @@ impl DB {
               receiver_account = $18,
               supervisor = $19,
               timeline = $20,
-              views = $21
+              views = $21,
+              state_block_height = $22,
+              approximate = $23
           "#,
             snapshot.proposal_id,
             snapshot.block_height,
@@ -237,7 +327,9 @@ impl DB {
             snapshot.receiver_account,
             snapshot.supervisor,
             snapshot.timeline,
-            snapshot.views
+            snapshot.views,
+            snapshot.state_block_height,
+            snapshot.approximate
         )
         .execute(tx.as_mut())
         .await;
@@ -271,22 +363,11 @@ impl DB {
             "ts_desc" => "ps.ts DESC",
             "id_asc" => "ps.proposal_id ASC",
             "id_desc" => "ps.proposal_id DESC",
+            "views_desc" => "ps.views DESC, ps.proposal_id DESC",
             _ => "ps.proposal_id DESC", // Default to DESC if the order is not recognized
         };
 
-        let stage = filters.as_ref().and_then(|f| f.stage.as_ref());
-        // Set 'stage_clause' to None if 'stage' is None
-        let stage_clause: Option<String> = stage.and_then(|s| match s.to_uppercase().as_str() {
-            "DRAFT" => Some("DRAFT".to_string()),
-            "REVIEW" => Some("REVIEW".to_string()),
-            "APPROVED" => Some("APPROVED".to_string()),
-            "REJECTED" => Some("REJECTED".to_string()),
-            "CANCELLED" => Some("CANCELLED".to_string()),
-            "CONDITIONAL" => Some("CONDITIONALLY".to_string()),
-            "PAYMENT" => Some("PAYMENT".to_string()),
-            "FUNDED" => Some("FUNDED".to_string()),
-            _ => None,
-        });
+        let stage_clause = proposal_stage_clause(&filters);
 
         // Build the SQL query with the validated order clause
         let data_sql = format!(
@@ -529,10 +610,12 @@ impl DB {
               description,
               timeline,
               submission_deadline,
-              views
+              views,
+              state_block_height,
+              approximate
           ) VALUES (
               $1, $2, $3, $4, $5, $6, $7, $8,
-              $9, $10, $11, $12, $13, $14, $15, $16
+              $9, $10, $11, $12, $13, $14, $15, $16, $17, $18
           ) ON CONFLICT (rfp_id, ts) DO UPDATE SET
               block_height = $2,
               editor_id = $4,
@@ -547,7 +630,9 @@ impl DB {
               description = $13,
               timeline = $14,
               submission_deadline = $15,
-              views = $16
+              views = $16,
+              state_block_height = $17,
+              approximate = $18
           "#,
             snapshot.rfp_id,
             snapshot.block_height,
@@ -564,7 +649,9 @@ impl DB {
             snapshot.description,
             snapshot.timeline,
             snapshot.submission_deadline,
-            snapshot.views
+            snapshot.views,
+            snapshot.state_block_height,
+            snapshot.approximate
         )
         .execute(tx.as_mut())
         .await;
@@ -597,15 +684,7 @@ impl DB {
             _ => "ps.rfp_id DESC", // Default to DESC if the order is not recognized
         };
 
-        // Extract and validate the stage filter
-        let stage = filters.as_ref().and_then(|f| f.stage.as_ref());
-        let stage_clause: Option<String> = stage.and_then(|s| match s.to_uppercase().as_str() {
-            "ACCEPTING_SUBMISSIONS" => Some("ACCEPTING_SUBMISSIONS".to_string()),
-            "EVALUATION" => Some("EVALUATION".to_string()),
-            "PROPOSAL_SELECTED" => Some("PROPOSAL_SELECTED".to_string()),
-            "CANCELLED" => Some("CANCELLED".to_string()),
-            _ => None,
-        });
+        let stage_clause = rfp_stage_clause(&filters);
 
         // Build the SQL query for fetching data with the validated order clause
         let data_sql = format!(
@@ -843,8 +922,1452 @@ impl DB {
 
         Ok(snapshot)
     }
+
+    // Batch lookups used by the GraphQL data loaders
+
+    pub async fn get_proposals_with_latest_snapshot_by_ids(
+        &self,
+        ids: &[i32],
+    ) -> Result<Vec<ProposalWithLatestSnapshotView>, sqlx::Error> {
+        let sql = r#"
+          SELECT *
+          FROM proposals_with_latest_snapshot ps
+          WHERE ps.proposal_id = ANY($1)
+        "#;
+        sqlx::query_as::<_, ProposalWithLatestSnapshotView>(sql)
+            .bind(ids)
+            .fetch_all(&self.0)
+            .await
+    }
+
+    pub async fn get_rfps_with_latest_snapshot_by_ids(
+        &self,
+        ids: &[i32],
+    ) -> Result<Vec<RfpWithLatestSnapshotView>, sqlx::Error> {
+        let sql = r#"
+          SELECT *
+          FROM rfps_with_latest_snapshot ps
+          WHERE ps.rfp_id = ANY($1)
+        "#;
+        sqlx::query_as::<_, RfpWithLatestSnapshotView>(sql)
+            .bind(ids)
+            .fetch_all(&self.0)
+            .await
+    }
+
+    pub async fn get_proposal_snapshots_by_ids(
+        &self,
+        ids: &[i32],
+    ) -> Result<Vec<ProposalSnapshotRecord>, sqlx::Error> {
+        let sql = r#"
+          SELECT *
+          FROM proposal_snapshots
+          WHERE proposal_id = ANY($1)
+          ORDER BY proposal_id, ts DESC
+        "#;
+        sqlx::query_as::<_, ProposalSnapshotRecord>(sql)
+            .bind(ids)
+            .fetch_all(&self.0)
+            .await
+    }
+
+    pub async fn get_rfp_snapshots_by_ids(
+        &self,
+        ids: &[i32],
+    ) -> Result<Vec<RfpSnapshotRecord>, sqlx::Error> {
+        let sql = r#"
+          SELECT *
+          FROM rfp_snapshots
+          WHERE rfp_id = ANY($1)
+          ORDER BY rfp_id, ts DESC
+        "#;
+        sqlx::query_as::<_, RfpSnapshotRecord>(sql)
+            .bind(ids)
+            .fetch_all(&self.0)
+            .await
+    }
+
+    // Reverse link lookups, the other side of `linked_proposals` and `linked_rfp`
+
+    pub async fn get_proposals_linking_to_proposal(
+        &self,
+        proposal_id: i32,
+    ) -> Result<Vec<ProposalWithLatestSnapshotView>, sqlx::Error> {
+        let sql = r#"
+          SELECT *
+          FROM proposals_with_latest_snapshot ps
+          WHERE ps.linked_proposals @> jsonb_build_array($1::int)
+          ORDER BY ps.proposal_id
+        "#;
+        sqlx::query_as::<_, ProposalWithLatestSnapshotView>(sql)
+            .bind(proposal_id)
+            .fetch_all(&self.0)
+            .await
+    }
+
+    pub async fn get_proposals_linking_to_rfp(
+        &self,
+        rfp_id: i32,
+    ) -> Result<Vec<ProposalWithLatestSnapshotView>, sqlx::Error> {
+        let sql = r#"
+          SELECT *
+          FROM proposals_with_latest_snapshot ps
+          WHERE ps.linked_rfp = $1
+          ORDER BY ps.proposal_id
+        "#;
+        sqlx::query_as::<_, ProposalWithLatestSnapshotView>(sql)
+            .bind(rfp_id)
+            .fetch_all(&self.0)
+            .await
+    }
+
+    pub async fn get_rfps_linking_to_proposal(
+        &self,
+        proposal_id: i32,
+    ) -> Result<Vec<RfpWithLatestSnapshotView>, sqlx::Error> {
+        let sql = r#"
+          SELECT *
+          FROM rfps_with_latest_snapshot ps
+          WHERE ps.linked_proposals @> jsonb_build_array($1::int)
+          ORDER BY ps.rfp_id
+        "#;
+        sqlx::query_as::<_, RfpWithLatestSnapshotView>(sql)
+            .bind(proposal_id)
+            .fetch_all(&self.0)
+            .await
+    }
+
+    // Snapshot events, ordered by their `snapshot_event_log` id, which is the SSE event id
+
+    /// Reads up to `limit` events after `after_event` and returns the ones matching
+    /// `filters`, with the last event id read, matching or not. That id is `None` when
+    /// there is nothing after `after_event`.
+    pub async fn get_snapshot_events(
+        &self,
+        after_event: i64,
+        filters: &EventFilters,
+        limit: i64,
+    ) -> Result<(Vec<SnapshotEvent>, Option<i64>), sqlx::Error> {
+        // Ids become visible in order, so the second query sees every event up to the
+        // last one the first query read
+        let scan_sql = r#"
+          SELECT MAX(id)
+          FROM (
+            SELECT id
+            FROM snapshot_event_log
+            WHERE id > $1
+            ORDER BY id
+            LIMIT $2
+          ) scanned
+        "#;
+        let Some(scanned_to) = sqlx::query_scalar::<_, Option<i64>>(scan_sql)
+            .bind(after_event)
+            .bind(limit)
+            .fetch_one(&self.0)
+            .await?
+        else {
+            return Ok((vec![], None));
+        };
+
+        let sql = r#"
+          SELECT *
+          FROM (
+            SELECT
+              l.id AS event_id,
+              'proposal' AS kind,
+              ps.proposal_id AS id,
+              ps.block_height,
+              ps.ts,
+              ps.editor_id,
+              p.author_id,
+              ps.name,
+              ps.category
+            FROM snapshot_event_log l
+            INNER JOIN proposal_snapshots ps ON ps.proposal_id = l.entity_id AND ps.ts = l.ts
+            INNER JOIN proposals p ON p.id = ps.proposal_id
+            WHERE l.id > $1 AND l.id <= $6 AND l.kind = 'proposal'
+            UNION ALL
+            SELECT
+              l.id AS event_id,
+              'rfp' AS kind,
+              rs.rfp_id AS id,
+              rs.block_height,
+              rs.ts,
+              rs.editor_id,
+              r.author_id,
+              rs.name,
+              rs.category
+            FROM snapshot_event_log l
+            INNER JOIN rfp_snapshots rs ON rs.rfp_id = l.entity_id AND rs.ts = l.ts
+            INNER JOIN rfps r ON r.id = rs.rfp_id
+            WHERE l.id > $1 AND l.id <= $6 AND l.kind = 'rfp'
+          ) e
+          WHERE
+            ($2::text IS NULL OR e.kind = $2)
+            AND ($3::int IS NULL OR e.id = $3)
+            AND ($4::text IS NULL OR e.author_id = $4)
+            AND ($5::text IS NULL OR e.category = $5)
+          ORDER BY e.event_id
+        "#;
+        let events = sqlx::query_as::<_, SnapshotEvent>(sql)
+            .bind(after_event)
+            .bind(filters.kind.as_ref())
+            .bind(filters.id)
+            .bind(filters.author_id.as_ref())
+            .bind(filters.category.as_ref())
+            .bind(scanned_to)
+            .fetch_all(&self.0)
+            .await?;
+        Ok((events, Some(scanned_to)))
+    }
+
+    /// Numbers the committed events that have no id yet, see the
+    /// `snapshot_event_log_sequencing` migration. Returns how many were numbered.
+    pub async fn assign_snapshot_event_ids(&self) -> Result<i64, sqlx::Error> {
+        let sql = r#"
+          SELECT assign_snapshot_event_ids()
+        "#;
+        sqlx::query_scalar::<_, i64>(sql).fetch_one(&self.0).await
+    }
+
+    /// Drops numbered events logged more than `retention_secs` ago. Returns how many
+    /// were removed.
+    pub async fn prune_snapshot_events(&self, retention_secs: i64) -> Result<u64, sqlx::Error> {
+        let sql = r#"
+          DELETE FROM snapshot_event_log
+          WHERE id IS NOT NULL
+            AND logged_at <= now() - make_interval(secs => $1::bigint)
+        "#;
+        let result = sqlx::query(sql)
+            .bind(retention_secs)
+            .execute(&self.0)
+            .await?;
+        Ok(result.rows_affected())
+    }
+
+    pub async fn get_latest_snapshot_event_id(&self) -> Result<i64, sqlx::Error> {
+        let sql = r#"
+          SELECT COALESCE(MAX(id), 0) FROM snapshot_event_log
+        "#;
+        sqlx::query_scalar::<_, i64>(sql).fetch_one(&self.0).await
+    }
+
+    // Functions for webhooks
+
+    async fn get_latest_proposal_snapshot_on_tx(
+        tx: &mut Transaction<'static, Postgres>,
+        proposal_id: i32,
+    ) -> Result<Option<ProposalSnapshotRecord>, sqlx::Error> {
+        let sql = r#"
+          SELECT *
+          FROM proposal_snapshots
+          WHERE proposal_id = $1
+          ORDER BY ts DESC
+          LIMIT 1
+        "#;
+        sqlx::query_as::<_, ProposalSnapshotRecord>(sql)
+            .bind(proposal_id)
+            .fetch_optional(tx.as_mut())
+            .await
+    }
+
+    // Queues one delivery per active subscription whose filters match the proposal
+    pub async fn enqueue_webhook_deliveries(
+        tx: &mut Transaction<'static, Postgres>,
+        event_type: &str,
+        snapshot: &ProposalSnapshotRecord,
+        payload: serde_json::Value,
+    ) -> anyhow::Result<()> {
+        query!(
+            r#"
+          INSERT INTO webhook_deliveries (
+              subscription_id,
+              event_type,
+              proposal_id,
+              block_height,
+              payload
+          )
+          SELECT
+              s.id,
+              $1::varchar,
+              p.id,
+              $3,
+              jsonb_set($4, '{author_id}', to_jsonb(p.author_id))
+          FROM webhook_subscriptions s
+          INNER JOIN proposals p ON p.id = $2
+          WHERE s.active
+              AND $1::text = ANY(s.event_types)
+              AND (s.category IS NULL OR s.category = $5)
+              AND (s.author_id IS NULL OR s.author_id = p.author_id)
+          "#,
+            event_type,
+            snapshot.proposal_id,
+            snapshot.block_height,
+            payload,
+            snapshot.category
+        )
+        .execute(tx.as_mut())
+        .await
+        .map_err(|e| {
+            eprintln!("Failed to enqueue webhook deliveries: {:?}", e);
+            anyhow::anyhow!("Failed to enqueue webhook deliveries")
+        })?;
+        Ok(())
+    }
+
+    pub async fn create_webhook_subscription(
+        &self,
+        url: &str,
+        secret: &str,
+        event_types: &[String],
+        category: Option<String>,
+        author_id: Option<String>,
+    ) -> Result<WebhookSubscription, sqlx::Error> {
+        let rec = query!(
+            r#"
+          INSERT INTO webhook_subscriptions (url, secret, event_types, category, author_id)
+          VALUES ($1, $2, $3, $4, $5)
+          RETURNING id
+          "#,
+            url,
+            secret,
+            event_types,
+            category,
+            author_id
+        )
+        .fetch_one(&self.0)
+        .await?;
+
+        self.get_webhook_subscription(rec.id)
+            .await?
+            .ok_or(sqlx::Error::RowNotFound)
+    }
+
+    pub async fn get_webhook_subscription(
+        &self,
+        id: i32,
+    ) -> Result<Option<WebhookSubscription>, sqlx::Error> {
+        let sql = r#"
+          SELECT *
+          FROM webhook_subscriptions
+          WHERE id = $1
+        "#;
+        sqlx::query_as::<_, WebhookSubscription>(sql)
+            .bind(id)
+            .fetch_optional(&self.0)
+            .await
+    }
+
+    pub async fn get_webhook_subscriptions(&self) -> Result<Vec<WebhookSubscription>, sqlx::Error> {
+        let sql = r#"
+          SELECT *
+          FROM webhook_subscriptions
+          WHERE active
+          ORDER BY id
+        "#;
+        sqlx::query_as::<_, WebhookSubscription>(sql)
+            .fetch_all(&self.0)
+            .await
+    }
+
+    // Subscriptions are deactivated rather than deleted so their delivery log stays around
+    pub async fn deactivate_webhook_subscription(&self, id: i32) -> Result<bool, sqlx::Error> {
+        let result = query!(
+            r#"
+          UPDATE webhook_subscriptions SET active = false WHERE id = $1 AND active
+          "#,
+            id
+        )
+        .execute(&self.0)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn get_webhook_deliveries(
+        &self,
+        subscription_id: i32,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<WebhookDelivery>, sqlx::Error> {
+        let sql = r#"
+          SELECT *
+          FROM webhook_deliveries
+          WHERE subscription_id = $1
+          ORDER BY id DESC
+          LIMIT $2 OFFSET $3
+        "#;
+        sqlx::query_as::<_, WebhookDelivery>(sql)
+            .bind(subscription_id)
+            .bind(limit)
+            .bind(offset)
+            .fetch_all(&self.0)
+            .await
+    }
+
+    pub async fn get_webhook_delivery_attempts(
+        &self,
+        delivery_id: i64,
+    ) -> Result<Vec<WebhookDeliveryAttempt>, sqlx::Error> {
+        let sql = r#"
+          SELECT *
+          FROM webhook_delivery_attempts
+          WHERE delivery_id = $1
+          ORDER BY id
+        "#;
+        sqlx::query_as::<_, WebhookDeliveryAttempt>(sql)
+            .bind(delivery_id)
+            .fetch_all(&self.0)
+            .await
+    }
+
+    // Pushes `next_attempt_at` past the lease so concurrent workers skip claimed rows
+    pub async fn claim_due_webhook_deliveries(
+        &self,
+        limit: i64,
+        lease: std::time::Duration,
+    ) -> Result<Vec<DueWebhookDelivery>, sqlx::Error> {
+        let sql = r#"
+          WITH due AS (
+            SELECT d.id
+            FROM webhook_deliveries d
+            INNER JOIN webhook_subscriptions s ON s.id = d.subscription_id
+            WHERE d.status = 'pending' AND d.next_attempt_at <= now() AND s.active
+            ORDER BY d.next_attempt_at
+            LIMIT $1
+            FOR UPDATE OF d SKIP LOCKED
+          )
+          UPDATE webhook_deliveries d
+          SET next_attempt_at = now() + make_interval(secs => $2)
+          FROM due, webhook_subscriptions s
+          WHERE d.id = due.id AND s.id = d.subscription_id
+          RETURNING d.id, d.event_type, d.payload, d.attempts, s.url, s.secret
+        "#;
+        sqlx::query_as::<_, DueWebhookDelivery>(sql)
+            .bind(limit)
+            .bind(lease.as_secs_f64())
+            .fetch_all(&self.0)
+            .await
+    }
+
+    pub async fn record_webhook_delivery_attempt(
+        &self,
+        delivery_id: i64,
+        status: &str,
+        status_code: Option<i32>,
+        error: Option<String>,
+        duration_ms: i64,
+        retry_in: std::time::Duration,
+    ) -> Result<(), sqlx::Error> {
+        let mut tx = self.begin().await?;
+        query!(
+            r#"
+          INSERT INTO webhook_delivery_attempts (delivery_id, status_code, error, duration_ms)
+          VALUES ($1, $2, $3, $4)
+          "#,
+            delivery_id,
+            status_code,
+            error,
+            duration_ms
+        )
+        .execute(tx.as_mut())
+        .await?;
+        query!(
+            r#"
+          UPDATE webhook_deliveries SET
+              status = $2::varchar,
+              attempts = attempts + 1,
+              last_status_code = $3,
+              last_error = $4,
+              next_attempt_at = now() + make_interval(secs => $5),
+              delivered_at = CASE WHEN $2::varchar = 'delivered' THEN now() END
+          WHERE id = $1
+          "#,
+            delivery_id,
+            status,
+            status_code,
+            error,
+            retry_in.as_secs_f64()
+        )
+        .execute(tx.as_mut())
+        .await?;
+        tx.commit().await
+    }
+
+    // Feed entries: the first snapshot of each matching entity plus every snapshot
+    // whose timeline status differs from the one before it
+
+    pub async fn get_proposal_feed_entries(
+        &self,
+        filters: Option<GetProposalFilters>,
+        limit: i64,
+    ) -> Result<Vec<FeedEntryRecord>, sqlx::Error> {
+        let sql = r#"
+          WITH matching AS (
+            SELECT ps.proposal_id, ps.author_id
+            FROM proposals_with_latest_snapshot ps
+            WHERE
+                ($2 IS NULL OR ps.author_id = $2)
+                AND ($3 IS NULL OR ps.ts > $3)
+                AND ($4 IS NULL OR ps.timeline::text ~ $4)
+                AND ($5 IS NULL OR ps.category = $5)
+                AND ($6 IS NULL OR ps.labels::jsonb ?| $6)
+          ),
+          history AS (
+            SELECT
+              s.proposal_id AS id,
+              m.author_id,
+              s.block_height,
+              s.ts,
+              s.editor_id,
+              s.name,
+              s.category,
+              s.summary,
+              (s.timeline #>> '{}')::jsonb ->> 'status' AS stage,
+              LAG((s.timeline #>> '{}')::jsonb ->> 'status')
+                OVER (PARTITION BY s.proposal_id ORDER BY s.ts) AS previous_stage,
+              ROW_NUMBER() OVER (PARTITION BY s.proposal_id ORDER BY s.ts) = 1 AS created
+            FROM proposal_snapshots s
+            INNER JOIN matching m ON m.proposal_id = s.proposal_id
+          )
+          SELECT *
+          FROM history
+          WHERE created OR stage IS DISTINCT FROM previous_stage
+          ORDER BY ts DESC
+          LIMIT $1
+        "#;
+        sqlx::query_as::<_, FeedEntryRecord>(sql)
+            .bind(limit)
+            .bind(filters.as_ref().and_then(|f| f.author_id.as_ref()))
+            .bind(filters.as_ref().and_then(|f| f.block_timestamp))
+            .bind(proposal_stage_clause(&filters))
+            .bind(filters.as_ref().and_then(|f| f.category.as_ref()))
+            .bind(filters.as_ref().and_then(|f| f.labels.as_ref()))
+            .fetch_all(&self.0)
+            .await
+    }
+
+    pub async fn get_rfp_feed_entries(
+        &self,
+        filters: Option<GetRfpFilters>,
+        limit: i64,
+    ) -> Result<Vec<FeedEntryRecord>, sqlx::Error> {
+        let sql = r#"
+          WITH matching AS (
+            SELECT ps.rfp_id, ps.author_id
+            FROM rfps_with_latest_snapshot ps
+            WHERE
+                ($2 IS NULL OR ps.author_id = $2)
+                AND ($3 IS NULL OR ps.ts > $3)
+                AND ($4 IS NULL OR ps.timeline::text ~ $4)
+                AND ($5 IS NULL OR ps.category = $5)
+                AND ($6 IS NULL OR ps.labels::jsonb ?| $6)
+          ),
+          history AS (
+            SELECT
+              s.rfp_id AS id,
+              m.author_id,
+              s.block_height,
+              s.ts,
+              s.editor_id,
+              s.name,
+              s.category,
+              s.summary,
+              (s.timeline #>> '{}')::jsonb ->> 'status' AS stage,
+              LAG((s.timeline #>> '{}')::jsonb ->> 'status')
+                OVER (PARTITION BY s.rfp_id ORDER BY s.ts) AS previous_stage,
+              ROW_NUMBER() OVER (PARTITION BY s.rfp_id ORDER BY s.ts) = 1 AS created
+            FROM rfp_snapshots s
+            INNER JOIN matching m ON m.rfp_id = s.rfp_id
+          )
+          SELECT *
+          FROM history
+          WHERE created OR stage IS DISTINCT FROM previous_stage
+          ORDER BY ts DESC
+          LIMIT $1
+        "#;
+        sqlx::query_as::<_, FeedEntryRecord>(sql)
+            .bind(limit)
+            .bind(filters.as_ref().and_then(|f| f.author_id.as_ref()))
+            .bind(filters.as_ref().and_then(|f| f.block_timestamp))
+            .bind(rfp_stage_clause(&filters))
+            .bind(filters.as_ref().and_then(|f| f.category.as_ref()))
+            .bind(filters.as_ref().and_then(|f| f.labels.as_ref()))
+            .fetch_all(&self.0)
+            .await
+    }
+    /// Counts a view unless the same client was already counted within `window_secs`.
+    /// Returns whether it counted and the new total, or `None` for unknown proposals.
+    pub async fn record_proposal_view(
+        &self,
+        proposal_id: i32,
+        client_key: &str,
+        window_secs: i64,
+    ) -> Result<Option<(bool, i32)>, sqlx::Error> {
+        let counted = sqlx::query!(
+            r#"
+            WITH counted AS (
+              INSERT INTO proposal_view_clients (proposal_id, client_key, last_counted_at)
+              SELECT id, $2::varchar, now() FROM proposals WHERE id = $1
+              ON CONFLICT (proposal_id, client_key) DO UPDATE
+                SET last_counted_at = EXCLUDED.last_counted_at
+                WHERE proposal_view_clients.last_counted_at
+                  <= now() - make_interval(secs => $3::bigint)
+              RETURNING proposal_id
+            )
+            INSERT INTO proposal_view_counts (proposal_id, views)
+            SELECT proposal_id, 1 FROM counted
+            ON CONFLICT (proposal_id) DO UPDATE
+              SET views = proposal_view_counts.views + 1
+            RETURNING views
+            "#,
+            proposal_id,
+            client_key,
+            window_secs,
+        )
+        .fetch_optional(&self.0)
+        .await?;
+        if let Some(rec) = counted {
+            return Ok(Some((true, rec.views)));
+        }
+
+        let rec = sqlx::query!(
+            r#"
+            SELECT COALESCE(vc.views, 0) AS "views!"
+            FROM proposals p
+            LEFT JOIN proposal_view_counts vc ON vc.proposal_id = p.id
+            WHERE p.id = $1
+            "#,
+            proposal_id
+        )
+        .fetch_optional(&self.0)
+        .await?;
+        Ok(rec.map(|rec| (false, rec.views)))
+    }
+
+    /// Forgets clients last counted more than `window_secs` ago; they would be counted
+    /// again anyway. Returns how many were removed.
+    pub async fn prune_proposal_view_clients(&self, window_secs: i64) -> Result<u64, sqlx::Error> {
+        let sql = r#"
+          DELETE FROM proposal_view_clients
+          WHERE last_counted_at <= now() - make_interval(secs => $1::bigint)
+        "#;
+        let result = sqlx::query(sql).bind(window_secs).execute(&self.0).await?;
+        Ok(result.rows_affected())
+    }
+
+    pub async fn get_account_activity(
+        &self,
+        account_id: &str,
+    ) -> Result<AccountActivityRecord, sqlx::Error> {
+        let sql = r#"
+          SELECT
+            (SELECT COUNT(*) FROM proposals WHERE author_id = $1) AS proposals_authored,
+            (SELECT COUNT(DISTINCT proposal_id) FROM proposal_snapshots WHERE editor_id = $1)
+              AS proposals_edited,
+            (SELECT COUNT(*) FROM proposals_with_latest_snapshot WHERE supervisor = $1)
+              AS proposals_supervised,
+            (SELECT COUNT(*) FROM proposals_with_latest_snapshot WHERE requested_sponsor = $1)
+              AS proposals_sponsor_requested,
+            (SELECT COUNT(*) FROM proposals_with_latest_snapshot WHERE receiver_account = $1)
+              AS proposals_receiver,
+            (SELECT COUNT(*) FROM rfps WHERE author_id = $1) AS rfps_authored,
+            (SELECT COUNT(DISTINCT rfp_id) FROM rfp_snapshots WHERE editor_id = $1) AS rfps_edited
+        "#;
+        sqlx::query_as::<_, AccountActivityRecord>(sql)
+            .bind(account_id)
+            .fetch_one(&self.0)
+            .await
+    }
+
+    pub async fn get_account_proposals(
+        &self,
+        account_id: &str,
+        relation: ProposalRelation,
+        limit: i64,
+        offset: i64,
+    ) -> Result<(Vec<ProposalWithLatestSnapshotView>, i64), sqlx::Error> {
+        let relation_clause = match relation {
+            ProposalRelation::Authored => "ps.author_id = $1",
+            ProposalRelation::Edited => {
+                "ps.proposal_id IN (SELECT proposal_id FROM proposal_snapshots WHERE editor_id = $1)"
+            }
+            ProposalRelation::Supervised => "ps.supervisor = $1",
+            ProposalRelation::SponsorRequested => "ps.requested_sponsor = $1",
+            ProposalRelation::Receiver => "ps.receiver_account = $1",
+        };
+        let data_sql = format!(
+            r#"
+          SELECT *
+          FROM proposals_with_latest_snapshot ps
+          WHERE {}
+          ORDER BY ps.proposal_id DESC
+          LIMIT $2 OFFSET $3
+          "#,
+            relation_clause
+        );
+        let count_sql = format!(
+            "SELECT COUNT(*) FROM proposals_with_latest_snapshot ps WHERE {}",
+            relation_clause
+        );
+
+        let recs = sqlx::query_as::<_, ProposalWithLatestSnapshotView>(&data_sql)
+            .bind(account_id)
+            .bind(limit)
+            .bind(offset)
+            .fetch_all(&self.0)
+            .await?;
+        let total_count: i64 = sqlx::query_scalar(&count_sql)
+            .bind(account_id)
+            .fetch_one(&self.0)
+            .await?;
+
+        Ok((recs, total_count))
+    }
+
+    pub async fn get_account_rfps(
+        &self,
+        account_id: &str,
+        relation: RfpRelation,
+        limit: i64,
+        offset: i64,
+    ) -> Result<(Vec<RfpWithLatestSnapshotView>, i64), sqlx::Error> {
+        let relation_clause = match relation {
+            RfpRelation::Authored => "ps.author_id = $1",
+            RfpRelation::Edited => {
+                "ps.rfp_id IN (SELECT rfp_id FROM rfp_snapshots WHERE editor_id = $1)"
+            }
+        };
+        let data_sql = format!(
+            r#"
+          SELECT *
+          FROM rfps_with_latest_snapshot ps
+          WHERE {}
+          ORDER BY ps.rfp_id DESC
+          LIMIT $2 OFFSET $3
+          "#,
+            relation_clause
+        );
+        let count_sql = format!(
+            "SELECT COUNT(*) FROM rfps_with_latest_snapshot ps WHERE {}",
+            relation_clause
+        );
+
+        let recs = sqlx::query_as::<_, RfpWithLatestSnapshotView>(&data_sql)
+            .bind(account_id)
+            .bind(limit)
+            .bind(offset)
+            .fetch_all(&self.0)
+            .await?;
+        let total_count: i64 = sqlx::query_scalar(&count_sql)
+            .bind(account_id)
+            .fetch_one(&self.0)
+            .await?;
+
+        Ok((recs, total_count))
+    }
+
+    pub async fn get_proposal_taxonomy(
+        &self,
+        kind: TaxonomyKind,
+        stage: Option<&str>,
+        from: Option<Timestamp>,
+        to: Option<Timestamp>,
+    ) -> Result<Vec<TaxonomyCount>, sqlx::Error> {
+        self.get_taxonomy(
+            "proposals_with_latest_snapshot",
+            kind,
+            stage.and_then(proposal_stage_pattern),
+            from,
+            to,
+        )
+        .await
+    }
+
+    pub async fn get_rfp_taxonomy(
+        &self,
+        kind: TaxonomyKind,
+        stage: Option<&str>,
+        from: Option<Timestamp>,
+        to: Option<Timestamp>,
+    ) -> Result<Vec<TaxonomyCount>, sqlx::Error> {
+        self.get_taxonomy(
+            "rfps_with_latest_snapshot",
+            kind,
+            stage.and_then(rfp_stage_pattern),
+            from,
+            to,
+        )
+        .await
+    }
+
+    // Distinct labels or categories of the latest snapshots, most used first
+    async fn get_taxonomy(
+        &self,
+        view: &'static str,
+        kind: TaxonomyKind,
+        stage_pattern: Option<String>,
+        from: Option<Timestamp>,
+        to: Option<Timestamp>,
+    ) -> Result<Vec<TaxonomyCount>, sqlx::Error> {
+        let value_source = match kind {
+            // Anything but an array counts as no labels
+            TaxonomyKind::Labels => {
+                r#"CROSS JOIN jsonb_array_elements_text(
+                    CASE WHEN jsonb_typeof(ps.labels::jsonb) = 'array'
+                    THEN ps.labels::jsonb ELSE '[]'::jsonb END
+                  ) AS value"#
+            }
+            TaxonomyKind::Categories => "CROSS JOIN LATERAL (SELECT ps.category AS value) c",
+        };
+        let sql = format!(
+            r#"
+          SELECT value, COUNT(*) AS count
+          FROM {} ps
+          {}
+          WHERE
+              value IS NOT NULL
+              AND ($1 IS NULL OR ps.timeline::text ~ $1)
+              AND ($2::bigint IS NULL OR ps.ts >= $2)
+              AND ($3::bigint IS NULL OR ps.ts < $3)
+          GROUP BY value
+          ORDER BY count DESC, value ASC
+          "#,
+            view, value_source
+        );
+        sqlx::query_as::<_, TaxonomyCount>(&sql)
+            .bind(stage_pattern)
+            .bind(from)
+            .bind(to)
+            .fetch_all(&self.0)
+            .await
+    }
+
+    // Funding is grouped by when a proposal was created (its first snapshot), while
+    // amounts, stage and supervisor come from the latest snapshot. Approved covers
+    // every stage from approval onwards, paid only FUNDED.
+    pub async fn get_funding_stats(
+        &self,
+        from: Option<Timestamp>,
+        to: Option<Timestamp>,
+    ) -> Result<Vec<FundingStatsRecord>, sqlx::Error> {
+        let sql = r#"
+          WITH latest AS (
+            SELECT
+              ps.category,
+              ps.requested_sponsorship_paid_in_currency AS currency,
+              to_char(to_timestamp(created.ts / 1e9) AT TIME ZONE 'UTC', 'YYYY-MM') AS month,
+              (ps.timeline #>> '{}')::jsonb ->> 'status' AS stage,
+              ps.supervisor,
+              ps.requested_sponsorship_usd_amount AS usd
+            FROM proposals_with_latest_snapshot ps
+            INNER JOIN (
+              SELECT proposal_id, MIN(ts) AS ts
+              FROM proposal_snapshots
+              GROUP BY proposal_id
+            ) created ON created.proposal_id = ps.proposal_id
+            WHERE
+                ($1::bigint IS NULL OR created.ts >= $1)
+                AND ($2::bigint IS NULL OR created.ts < $2)
+          )
+          SELECT
+            CASE
+              WHEN GROUPING(category) = 0 THEN 'category'
+              WHEN GROUPING(currency) = 0 THEN 'currency'
+              WHEN GROUPING(month) = 0 THEN 'month'
+              WHEN GROUPING(stage) = 0 THEN 'stage'
+              WHEN GROUPING(supervisor) = 0 THEN 'supervisor'
+              ELSE 'total'
+            END AS dimension,
+            CASE
+              WHEN GROUPING(category) = 0 THEN category
+              WHEN GROUPING(currency) = 0 THEN currency
+              WHEN GROUPING(month) = 0 THEN month
+              WHEN GROUPING(stage) = 0 THEN stage
+              WHEN GROUPING(supervisor) = 0 THEN supervisor
+            END AS key,
+            COUNT(*) AS proposals,
+            COALESCE(SUM(usd), 0)::bigint AS requested_usd,
+            COALESCE(SUM(usd) FILTER (
+              WHERE stage IN ('APPROVED', 'APPROVED_CONDITIONALLY', 'PAYMENT_PROCESSING', 'FUNDED')
+            ), 0)::bigint AS approved_usd,
+            COALESCE(SUM(usd) FILTER (WHERE stage = 'FUNDED'), 0)::bigint AS paid_usd
+          FROM latest
+          GROUP BY GROUPING SETS ((), (category), (currency), (month), (stage), (supervisor))
+          ORDER BY dimension, key NULLS LAST
+        "#;
+        sqlx::query_as::<_, FundingStatsRecord>(sql)
+            .bind(from)
+            .bind(to)
+            .fetch_all(&self.0)
+            .await
+    }
+
+    pub async fn get_stage_durations(
+        &self,
+        from: Option<Timestamp>,
+        to: Option<Timestamp>,
+    ) -> Result<Vec<StageDurationRecord>, sqlx::Error> {
+        let sql = format!(
+            r#"
+          {}
+          SELECT
+            stage,
+            COUNT(*) AS completed,
+            percentile_cont(0.5) WITHIN GROUP (ORDER BY (left_ts - entered_ts) / 1e9::float8)
+              AS median_seconds,
+            percentile_cont(0.9) WITHIN GROUP (ORDER BY (left_ts - entered_ts) / 1e9::float8)
+              AS p90_seconds
+          FROM intervals
+          WHERE
+              stage IS NOT NULL
+              AND left_ts IS NOT NULL
+              AND ($1::bigint IS NULL OR entered_ts >= $1)
+              AND ($2::bigint IS NULL OR entered_ts < $2)
+          GROUP BY stage
+          ORDER BY stage
+          "#,
+            STAGE_INTERVALS
+        );
+        sqlx::query_as::<_, StageDurationRecord>(&sql)
+            .bind(from)
+            .bind(to)
+            .fetch_all(&self.0)
+            .await
+    }
+
+    pub async fn get_weekly_stage_counts(
+        &self,
+        from: Option<Timestamp>,
+        to: Option<Timestamp>,
+    ) -> Result<Vec<StageWeekRecord>, sqlx::Error> {
+        let sql = format!(
+            r#"
+          {},
+          transitions AS (
+            SELECT stage, entered_ts AS ts, TRUE AS entering FROM intervals
+            UNION ALL
+            SELECT stage, left_ts AS ts, FALSE AS entering FROM intervals WHERE left_ts IS NOT NULL
+          )
+          SELECT
+            to_char(date_trunc('week', to_timestamp(ts / 1e9) AT TIME ZONE 'UTC'), 'YYYY-MM-DD')
+              AS week,
+            stage,
+            COUNT(*) FILTER (WHERE entering) AS entered,
+            COUNT(*) FILTER (WHERE NOT entering) AS "left"
+          FROM transitions
+          WHERE
+              stage IS NOT NULL
+              AND ($1::bigint IS NULL OR ts >= $1)
+              AND ($2::bigint IS NULL OR ts < $2)
+          GROUP BY week, stage
+          ORDER BY week, stage
+          "#,
+            STAGE_INTERVALS
+        );
+        sqlx::query_as::<_, StageWeekRecord>(&sql)
+            .bind(from)
+            .bind(to)
+            .fetch_all(&self.0)
+            .await
+    }
+
+    pub async fn get_sla_breaches(
+        &self,
+        stage: &str,
+        entered_before: Timestamp,
+        now: Timestamp,
+    ) -> Result<Vec<SlaBreachRecord>, sqlx::Error> {
+        let sql = format!(
+            r#"
+          {}
+          SELECT
+            i.proposal_id,
+            ps.name,
+            ps.author_id,
+            i.stage,
+            i.entered_ts,
+            (($3 - i.entered_ts) / 1000000000)::bigint AS elapsed_seconds
+          FROM intervals i
+          INNER JOIN proposals_with_latest_snapshot ps ON ps.proposal_id = i.proposal_id
+          WHERE i.left_ts IS NULL AND i.stage = $1 AND i.entered_ts < $2
+          ORDER BY i.entered_ts ASC
+          "#,
+            STAGE_INTERVALS
+        );
+        sqlx::query_as::<_, SlaBreachRecord>(&sql)
+            .bind(stage)
+            .bind(entered_before)
+            .bind(now)
+            .fetch_all(&self.0)
+            .await
+    }
+
+    pub async fn get_all_proposals_with_latest_snapshot(
+        &self,
+    ) -> Result<Vec<ProposalWithLatestSnapshotView>, sqlx::Error> {
+        sqlx::query_as::<_, ProposalWithLatestSnapshotView>(
+            "SELECT * FROM proposals_with_latest_snapshot ORDER BY proposal_id",
+        )
+        .fetch_all(&self.0)
+        .await
+    }
+
+    pub async fn get_all_rfps_with_latest_snapshot(
+        &self,
+    ) -> Result<Vec<RfpWithLatestSnapshotView>, sqlx::Error> {
+        sqlx::query_as::<_, RfpWithLatestSnapshotView>(
+            "SELECT * FROM rfps_with_latest_snapshot ORDER BY rfp_id",
+        )
+        .fetch_all(&self.0)
+        .await
+    }
+
+    /// Starts a run, or returns `None` while another one is still running.
+    pub async fn create_reconciliation_run(
+        &self,
+        trigger: &str,
+        repair: bool,
+    ) -> Result<Option<ReconciliationRun>, sqlx::Error> {
+        let sql = r#"
+          INSERT INTO reconciliation_runs (trigger, repair)
+          VALUES ($1, $2)
+          ON CONFLICT DO NOTHING
+          RETURNING *
+        "#;
+        sqlx::query_as::<_, ReconciliationRun>(sql)
+            .bind(trigger)
+            .bind(repair)
+            .fetch_optional(&self.0)
+            .await
+    }
+
+    pub async fn finish_reconciliation_run(
+        &self,
+        id: i32,
+        checked_proposals: i32,
+        checked_rfps: i32,
+        issues: serde_json::Value,
+        error: Option<String>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            r#"
+            UPDATE reconciliation_runs
+            SET
+              status = CASE WHEN $5::varchar IS NULL THEN 'completed' ELSE 'failed' END,
+              checked_proposals = $2,
+              checked_rfps = $3,
+              issues = $4,
+              error = $5,
+              finished_at = now()
+            WHERE id = $1
+            "#,
+            id,
+            checked_proposals,
+            checked_rfps,
+            issues,
+            error
+        )
+        .execute(&self.0)
+        .await?;
+        Ok(())
+    }
+
+    /// Closes runs left `running` by the previous process; `create_reconciliation_run`
+    /// refuses to start a run while one is.
+    pub async fn fail_interrupted_reconciliation_runs(&self) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            r#"
+            UPDATE reconciliation_runs
+            SET status = 'failed', error = 'interrupted', finished_at = now()
+            WHERE status = 'running'
+            "#
+        )
+        .execute(&self.0)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn get_reconciliation_runs(
+        &self,
+        limit: i64,
+    ) -> Result<Vec<ReconciliationRun>, sqlx::Error> {
+        sqlx::query_as::<_, ReconciliationRun>(
+            "SELECT * FROM reconciliation_runs ORDER BY id DESC LIMIT $1",
+        )
+        .bind(limit)
+        .fetch_all(&self.0)
+        .await
+    }
+
+    pub async fn get_reconciliation_run(
+        &self,
+        id: i32,
+    ) -> Result<Option<ReconciliationRun>, sqlx::Error> {
+        sqlx::query_as::<_, ReconciliationRun>("SELECT * FROM reconciliation_runs WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.0)
+            .await
+    }
+
+    /// Queues a job, or returns `None` while another job for the contract is queued or running.
+    pub async fn create_job(
+        &self,
+        kind: &str,
+        contract: &str,
+        max_transactions: Option<i64>,
+    ) -> Result<Option<Job>, sqlx::Error> {
+        let sql = r#"
+          INSERT INTO jobs (kind, contract, max_transactions)
+          VALUES ($1, $2, $3)
+          ON CONFLICT DO NOTHING
+          RETURNING *
+        "#;
+        sqlx::query_as::<_, Job>(sql)
+            .bind(kind)
+            .bind(contract)
+            .bind(max_transactions)
+            .fetch_optional(&self.0)
+            .await
+    }
+
+    /// Marks a queued job as running; false if it was cancelled before it started.
+    pub async fn start_job(&self, id: i32) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE jobs SET status = 'running', started_at = COALESCE(started_at, now())
+            WHERE id = $1 AND status = 'queued'
+            "#,
+            id
+        )
+        .execute(&self.0)
+        .await?;
+        Ok(result.rows_affected() == 1)
+    }
+
+    /// Adds a processed page to the job's progress and returns whether cancellation was
+    /// requested.
+    pub async fn update_job_progress(
+        &self,
+        id: i32,
+        page_transactions: i64,
+        last_block: Option<BlockHeight>,
+    ) -> Result<bool, sqlx::Error> {
+        let rec = sqlx::query!(
+            r#"
+            UPDATE jobs
+            SET
+              pages_fetched = pages_fetched + 1,
+              transactions_fetched = transactions_fetched + $2,
+              last_block = COALESCE($3, last_block)
+            WHERE id = $1
+            RETURNING cancel_requested
+            "#,
+            id,
+            page_transactions,
+            last_block
+        )
+        .fetch_one(&self.0)
+        .await?;
+        Ok(rec.cancel_requested)
+    }
+
+    pub async fn finish_job(
+        &self,
+        id: i32,
+        status: &str,
+        error: Option<String>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            r#"
+            UPDATE jobs SET status = $2, error = $3, finished_at = now()
+            WHERE id = $1
+            "#,
+            id,
+            status,
+            error
+        )
+        .execute(&self.0)
+        .await?;
+        Ok(())
+    }
+
+    /// Asks a running job to stop and cancels a queued one right away. `None` if the job
+    /// doesn't exist or has already finished.
+    pub async fn request_job_cancel(&self, id: i32) -> Result<Option<Job>, sqlx::Error> {
+        let sql = r#"
+          UPDATE jobs
+          SET
+            cancel_requested = true,
+            status = CASE WHEN status = 'queued' THEN 'cancelled' ELSE status END,
+            finished_at = CASE WHEN status = 'queued' THEN now() ELSE finished_at END
+          WHERE id = $1 AND status IN ('queued', 'running')
+          RETURNING *
+        "#;
+        sqlx::query_as::<_, Job>(sql)
+            .bind(id)
+            .fetch_optional(&self.0)
+            .await
+    }
+
+    /// Puts the contract's job that was queued or running when the server stopped back in
+    /// the queue, keeping its progress.
+    pub async fn requeue_interrupted_job(
+        &self,
+        contract: &str,
+    ) -> Result<Option<Job>, sqlx::Error> {
+        let sql = r#"
+          UPDATE jobs SET status = 'queued'
+          WHERE contract = $1 AND status IN ('queued', 'running')
+          RETURNING *
+        "#;
+        sqlx::query_as::<_, Job>(sql)
+            .bind(contract)
+            .fetch_optional(&self.0)
+            .await
+    }
+
+    pub async fn get_jobs(&self, limit: i64) -> Result<Vec<Job>, sqlx::Error> {
+        sqlx::query_as::<_, Job>("SELECT * FROM jobs ORDER BY id DESC LIMIT $1")
+            .bind(limit)
+            .fetch_all(&self.0)
+            .await
+    }
+
+    pub async fn get_job(&self, id: i32) -> Result<Option<Job>, sqlx::Error> {
+        sqlx::query_as::<_, Job>("SELECT * FROM jobs WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.0)
+            .await
+    }
+
+    /// Versions of the embedded migrations that are not applied to the database.
+    pub async fn get_pending_migrations(&self) -> Result<Vec<i64>, sqlx::Error> {
+        let applied: Vec<i64> =
+            sqlx::query_scalar("SELECT version FROM _sqlx_migrations WHERE success")
+                .fetch_all(&self.0)
+                .await?;
+        Ok(migrate!("./migrations")
+            .iter()
+            .map(|migration| migration.version)
+            .filter(|version| !applied.contains(version))
+            .collect())
+    }
+
+    /// The given on-chain proposal ids that have no snapshot in the cache.
+    pub async fn get_missing_proposal_ids(&self, ids: &[i32]) -> Result<Vec<i32>, sqlx::Error> {
+        self.get_missing_ids("proposal_snapshots", "proposal_id", ids)
+            .await
+    }
+
+    /// The given on-chain RFP ids that have no snapshot in the cache.
+    pub async fn get_missing_rfp_ids(&self, ids: &[i32]) -> Result<Vec<i32>, sqlx::Error> {
+        self.get_missing_ids("rfp_snapshots", "rfp_id", ids).await
+    }
+
+    async fn get_missing_ids(
+        &self,
+        table: &'static str,
+        column: &'static str,
+        ids: &[i32],
+    ) -> Result<Vec<i32>, sqlx::Error> {
+        let sql = format!(
+            r#"
+          SELECT id
+          FROM unnest($1::int[]) AS id
+          WHERE NOT EXISTS (SELECT 1 FROM {table} s WHERE s.{column} = id)
+          ORDER BY id
+          "#,
+        );
+        sqlx::query_scalar::<_, i32>(&sql)
+            .bind(ids)
+            .fetch_all(&self.0)
+            .await
+    }
+
+    // Exports stream rows instead of collecting them; with `all_snapshots` every
+    // snapshot of each matching entity is returned, not only the latest one
+
+    pub fn stream_proposals_for_export(
+        &self,
+        filters: Option<GetProposalFilters>,
+        all_snapshots: bool,
+    ) -> BoxStream<'_, Result<ProposalWithLatestSnapshotView, sqlx::Error>> {
+        let sql = if all_snapshots {
+            r#"
+          SELECT
+            s.proposal_id,
+            p.author_id,
+            s.block_height,
+            s.ts,
+            s.editor_id,
+            s.social_db_post_block_height,
+            s.labels,
+            s.proposal_version,
+            s.proposal_body_version,
+            s.name,
+            s.category,
+            s.summary,
+            s.description,
+            s.linked_proposals,
+            s.linked_rfp,
+            s.requested_sponsorship_usd_amount,
+            s.requested_sponsorship_paid_in_currency,
+            s.requested_sponsor,
+            s.receiver_account,
+            s.supervisor,
+            s.timeline,
+            -- Views are counted per proposal, the snapshot column is never updated
+            COALESCE(vc.views, 0) AS views
+          FROM proposal_snapshots s
+          INNER JOIN proposals p ON p.id = s.proposal_id
+          LEFT JOIN proposal_view_counts vc ON vc.proposal_id = s.proposal_id
+          WHERE s.proposal_id IN (
+            SELECT ps.proposal_id
+            FROM proposals_with_latest_snapshot ps
+            WHERE
+                ($1 IS NULL OR ps.author_id = $1)
+                AND ($2 IS NULL OR ps.ts > $2)
+                AND ($3 IS NULL OR ps.timeline::text ~ $3)
+                AND ($4 IS NULL OR ps.category = $4)
+                AND ($5 IS NULL OR ps.labels::jsonb ?| $5)
+          )
+          ORDER BY s.proposal_id ASC, s.ts ASC
+          "#
+        } else {
+            r#"
+          SELECT *
+          FROM proposals_with_latest_snapshot ps
+          WHERE
+              ($1 IS NULL OR ps.author_id = $1)
+              AND ($2 IS NULL OR ps.ts > $2)
+              AND ($3 IS NULL OR ps.timeline::text ~ $3)
+              AND ($4 IS NULL OR ps.category = $4)
+              AND ($5 IS NULL OR ps.labels::jsonb ?| $5)
+          ORDER BY ps.proposal_id ASC
+          "#
+        };
+        let stage_clause = proposal_stage_clause(&filters);
+        sqlx::query_as::<_, ProposalWithLatestSnapshotView>(sql)
+            .bind(filters.as_ref().and_then(|f| f.author_id.clone()))
+            .bind(filters.as_ref().and_then(|f| f.block_timestamp))
+            .bind(stage_clause)
+            .bind(filters.as_ref().and_then(|f| f.category.clone()))
+            .bind(filters.as_ref().and_then(|f| f.labels.clone()))
+            .fetch(&self.0)
+    }
+
+    pub fn stream_rfps_for_export(
+        &self,
+        filters: Option<GetRfpFilters>,
+        all_snapshots: bool,
+    ) -> BoxStream<'_, Result<RfpWithLatestSnapshotView, sqlx::Error>> {
+        let sql = if all_snapshots {
+            r#"
+          SELECT s.*, p.author_id
+          FROM rfp_snapshots s
+          INNER JOIN rfps p ON p.id = s.rfp_id
+          WHERE s.rfp_id IN (
+            SELECT ps.rfp_id
+            FROM rfps_with_latest_snapshot ps
+            WHERE
+                ($1 IS NULL OR ps.author_id = $1)
+                AND ($2 IS NULL OR ps.ts > $2)
+                AND ($3 IS NULL OR ps.timeline::text ~ $3)
+                AND ($4 IS NULL OR ps.category = $4)
+                AND ($5 IS NULL OR ps.labels::jsonb ?| $5)
+          )
+          ORDER BY s.rfp_id ASC, s.ts ASC
+          "#
+        } else {
+            r#"
+          SELECT *
+          FROM rfps_with_latest_snapshot ps
+          WHERE
+              ($1 IS NULL OR ps.author_id = $1)
+              AND ($2 IS NULL OR ps.ts > $2)
+              AND ($3 IS NULL OR ps.timeline::text ~ $3)
+              AND ($4 IS NULL OR ps.category = $4)
+              AND ($5 IS NULL OR ps.labels::jsonb ?| $5)
+          ORDER BY ps.rfp_id ASC
+          "#
+        };
+        let stage_clause = rfp_stage_clause(&filters);
+        sqlx::query_as::<_, RfpWithLatestSnapshotView>(sql)
+            .bind(filters.as_ref().and_then(|f| f.author_id.clone()))
+            .bind(filters.as_ref().and_then(|f| f.block_timestamp))
+            .bind(stage_clause)
+            .bind(filters.as_ref().and_then(|f| f.category.clone()))
+            .bind(filters.as_ref().and_then(|f| f.labels.clone()))
+            .fetch(&self.0)
+    }
+}
+
+// Every stretch a proposal spent in one timeline status: consecutive snapshots with
+// the same status are merged and `left_ts` is null while it is still in that status
+const STAGE_INTERVALS: &str = r#"
+  WITH statuses AS (
+    SELECT
+      proposal_id,
+      ts,
+      (timeline #>> '{}')::jsonb ->> 'status' AS stage,
+      LAG((timeline #>> '{}')::jsonb ->> 'status')
+        OVER (PARTITION BY proposal_id ORDER BY ts) AS previous_stage,
+      ROW_NUMBER() OVER (PARTITION BY proposal_id ORDER BY ts) = 1 AS first
+    FROM proposal_snapshots
+  ),
+  changes AS (
+    SELECT proposal_id, ts, stage
+    FROM statuses
+    WHERE first OR stage IS DISTINCT FROM previous_stage
+  ),
+  intervals AS (
+    SELECT
+      proposal_id,
+      stage,
+      ts AS entered_ts,
+      LEAD(ts) OVER (PARTITION BY proposal_id ORDER BY ts) AS left_ts
+    FROM changes
+  )
+"#;
+
+// Validated stage filters, matched as a regex against the timeline
+fn proposal_stage_clause(filters: &Option<GetProposalFilters>) -> Option<String> {
+    let stage = filters.as_ref().and_then(|f| f.stage.as_deref());
+    // Set 'stage_clause' to None if 'stage' is None
+    stage.and_then(proposal_stage_pattern)
+}
+
+fn proposal_stage_pattern(stage: &str) -> Option<String> {
+    match stage.to_uppercase().as_str() {
+        "DRAFT" => Some("DRAFT".to_string()),
+        "REVIEW" => Some("REVIEW".to_string()),
+        "APPROVED" => Some("APPROVED".to_string()),
+        "REJECTED" => Some("REJECTED".to_string()),
+        "CANCELLED" => Some("CANCELLED".to_string()),
+        "CONDITIONAL" => Some("CONDITIONALLY".to_string()),
+        "PAYMENT" => Some("PAYMENT".to_string()),
+        "FUNDED" => Some("FUNDED".to_string()),
+        _ => None,
+    }
+}
+
+fn rfp_stage_clause(filters: &Option<GetRfpFilters>) -> Option<String> {
+    let stage = filters.as_ref().and_then(|f| f.stage.as_deref());
+    stage.and_then(rfp_stage_pattern)
+}
+
+fn rfp_stage_pattern(stage: &str) -> Option<String> {
+    match stage.to_uppercase().as_str() {
+        "ACCEPTING_SUBMISSIONS" => Some("ACCEPTING_SUBMISSIONS".to_string()),
+        "EVALUATION" => Some("EVALUATION".to_string()),
+        "PROPOSAL_SELECTED" => Some("PROPOSAL_SELECTED".to_string()),
+        "CANCELLED" => Some("CANCELLED".to_string()),
+        _ => None,
+    }
 }
 
+// The error hands the whole Rocket back, the fairing API leaves no way around its size
+#[allow(clippy::result_large_err)]
 async fn run_migrations(rocket: Rocket<Build>) -> fairing::Result {
     match DB::fetch(&rocket) {
         Some(db) => match migrate!("./migrations").run(&**db).await {
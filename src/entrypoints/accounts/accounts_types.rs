@@ -0,0 +1,71 @@
+use crate::db::db_types::AccountActivityRecord;
+use rocket::serde::{Deserialize, Serialize};
+use rocket::FromFormField;
+use utoipa::ToSchema;
+
+/// How an account is involved in a proposal.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, FromFormField, ToSchema)]
+pub enum ProposalRelation {
+    /// `proposals.author_id`
+    #[default]
+    Authored,
+    /// `editor_id` of any snapshot
+    Edited,
+    /// `supervisor` of the latest snapshot
+    Supervised,
+    /// `requested_sponsor` of the latest snapshot
+    #[field(value = "sponsor_requested")]
+    SponsorRequested,
+    /// `receiver_account` of the latest snapshot
+    Receiver,
+}
+
+/// How an account is involved in an RFP.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, FromFormField, ToSchema)]
+pub enum RfpRelation {
+    #[default]
+    Authored,
+    Edited,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct ProposalActivity {
+    pub authored: i64,
+    pub edited: i64,
+    pub supervised: i64,
+    pub sponsor_requested: i64,
+    pub receiver: i64,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct RfpActivity {
+    pub authored: i64,
+    pub edited: i64,
+}
+
+/// Number of proposals and RFPs an account is involved in, per relation.
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct AccountActivity {
+    pub account_id: String,
+    pub proposals: ProposalActivity,
+    pub rfps: RfpActivity,
+}
+
+impl AccountActivity {
+    pub fn new(account_id: String, record: AccountActivityRecord) -> Self {
+        Self {
+            account_id,
+            proposals: ProposalActivity {
+                authored: record.proposals_authored,
+                edited: record.proposals_edited,
+                supervised: record.proposals_supervised,
+                sponsor_requested: record.proposals_sponsor_requested,
+                receiver: record.proposals_receiver,
+            },
+            rfps: RfpActivity {
+                authored: record.rfps_authored,
+                edited: record.rfps_edited,
+            },
+        }
+    }
+}
@@ -0,0 +1,128 @@
+use self::accounts_types::*;
+use crate::changelog::fetch_changelog_from_rpc;
+use crate::db::db_types::{ProposalWithLatestSnapshotView, RfpWithLatestSnapshotView};
+use crate::db::DB;
+use crate::rpc_service::RpcService;
+use crate::types::PaginatedResponse;
+use rocket::serde::json::Json;
+use rocket::{get, http::Status, State};
+pub mod accounts_types;
+
+async fn refresh(db: &State<DB>, rpc_service: &State<RpcService>) {
+    let last_updated_info = db.get_last_updated_info().await.unwrap();
+
+    let _ = fetch_changelog_from_rpc(
+        db.inner(),
+        rpc_service.inner(),
+        Some(last_updated_info.after_block),
+    )
+    .await;
+}
+
+fn page<T: rocket::serde::Serialize>(
+    records: Vec<T>,
+    limit: i64,
+    offset: i64,
+    total: i64,
+) -> PaginatedResponse<T> {
+    PaginatedResponse::new(
+        records,
+        (offset / limit + 1).try_into().unwrap(),
+        limit.try_into().unwrap(),
+        total.try_into().unwrap(),
+        None,
+    )
+}
+
+#[utoipa::path(get, path = "/accounts/{account_id}")]
+#[get("/<account_id>")]
+async fn get_account_activity(
+    account_id: &str,
+    db: &State<DB>,
+    rpc_service: &State<RpcService>,
+) -> Result<Json<AccountActivity>, Status> {
+    refresh(db, rpc_service).await;
+
+    match db.get_account_activity(account_id).await {
+        Ok(record) => Ok(Json(AccountActivity::new(account_id.to_string(), record))),
+        Err(e) => {
+            eprintln!("Failed to get account activity: {:?}", e);
+            Err(Status::InternalServerError)
+        }
+    }
+}
+
+#[utoipa::path(get, path = "/accounts/{account_id}/proposals?<relation>&<limit>&<offset>", params(
+  ("relation"= &str, Path, description = "authored (default), edited, supervised, sponsor_requested or receiver"),
+  ("limit"= i64, Path, description = "default limit 10"),
+  ("offset"= i64, Path, description = "offset"),
+))]
+#[get("/<account_id>/proposals?<relation>&<limit>&<offset>")]
+async fn get_account_proposals(
+    account_id: &str,
+    relation: Option<ProposalRelation>,
+    limit: Option<i64>,
+    offset: Option<i64>,
+    db: &State<DB>,
+    rpc_service: &State<RpcService>,
+) -> Result<Json<PaginatedResponse<ProposalWithLatestSnapshotView>>, Status> {
+    let limit = limit.unwrap_or(10).max(1);
+    let offset = offset.unwrap_or(0).max(0);
+    refresh(db, rpc_service).await;
+
+    match db
+        .get_account_proposals(account_id, relation.unwrap_or_default(), limit, offset)
+        .await
+    {
+        Ok((proposals, total)) => Ok(Json(page(proposals, limit, offset, total))),
+        Err(e) => {
+            eprintln!("Failed to get account proposals: {:?}", e);
+            Err(Status::InternalServerError)
+        }
+    }
+}
+
+#[utoipa::path(get, path = "/accounts/{account_id}/rfps?<relation>&<limit>&<offset>", params(
+  ("relation"= &str, Path, description = "authored (default) or edited"),
+  ("limit"= i64, Path, description = "default limit 10"),
+  ("offset"= i64, Path, description = "offset"),
+))]
+#[get("/<account_id>/rfps?<relation>&<limit>&<offset>")]
+async fn get_account_rfps(
+    account_id: &str,
+    relation: Option<RfpRelation>,
+    limit: Option<i64>,
+    offset: Option<i64>,
+    db: &State<DB>,
+    rpc_service: &State<RpcService>,
+) -> Result<Json<PaginatedResponse<RfpWithLatestSnapshotView>>, Status> {
+    let limit = limit.unwrap_or(10).max(1);
+    let offset = offset.unwrap_or(0).max(0);
+    refresh(db, rpc_service).await;
+
+    match db
+        .get_account_rfps(account_id, relation.unwrap_or_default(), limit, offset)
+        .await
+    {
+        Ok((rfps, total)) => Ok(Json(page(rfps, limit, offset, total))),
+        Err(e) => {
+            eprintln!("Failed to get account rfps: {:?}", e);
+            Err(Status::InternalServerError)
+        }
+    }
+}
+
+pub fn stage() -> rocket::fairing::AdHoc {
+    rocket::fairing::AdHoc::on_ignite("Accounts Stage", |rocket| async {
+        println!("Accounts stage on ignite!");
+
+        rocket.mount(
+            "/accounts",
+            rocket::routes![
+                get_account_activity,
+                get_account_proposals,
+                get_account_rfps
+            ],
+        )
+    })
+}
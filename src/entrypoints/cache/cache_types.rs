@@ -0,0 +1,79 @@
+use rocket::http::{ContentType, Header, Status};
+use rocket::request::{self, FromRequest, Request};
+use rocket::response::{self, Responder, Response};
+use rocket::serde::Deserialize;
+use std::io::Cursor;
+use std::sync::Arc;
+
+/// Freshness and size limits of the in-memory `/proposals` and `/rfps` response cache.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct CacheConfig {
+    /// How long a cached list response is served without running the query again; 0
+    /// disables the cache. Also sent as `Cache-Control: max-age`.
+    pub ttl_secs: u64,
+    pub max_entries: usize,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            ttl_secs: 5,
+            max_entries: 256,
+        }
+    }
+}
+
+/// Entity tags from the `If-None-Match` header, without the weak prefix.
+pub struct IfNoneMatch(pub Vec<String>);
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for IfNoneMatch {
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        let tags = request
+            .headers()
+            .get("If-None-Match")
+            .flat_map(|value| value.split(','))
+            .map(|tag| tag.trim().trim_start_matches("W/").to_string())
+            .filter(|tag| !tag.is_empty())
+            .collect();
+        request::Outcome::Success(IfNoneMatch(tags))
+    }
+}
+
+impl IfNoneMatch {
+    pub fn matches(&self, etag: &str) -> bool {
+        self.0.iter().any(|tag| tag == "*" || tag == etag)
+    }
+}
+
+/// A JSON body with its `ETag` and `Cache-Control`, or a bodyless 304 when the client
+/// already has it.
+pub struct CachedJson {
+    pub body: Arc<[u8]>,
+    pub etag: String,
+    pub max_age: u64,
+    pub not_modified: bool,
+}
+
+impl<'r> Responder<'r, 'static> for CachedJson {
+    fn respond_to(self, _request: &'r Request<'_>) -> response::Result<'static> {
+        let mut response = Response::build();
+        response
+            .header(Header::new("ETag", self.etag))
+            .header(Header::new(
+                "Cache-Control",
+                format!("public, max-age={}", self.max_age),
+            ));
+        if self.not_modified {
+            response.status(Status::NotModified);
+        } else {
+            response
+                .header(ContentType::JSON)
+                .sized_body(self.body.len(), Cursor::new(self.body));
+        }
+        response.ok()
+    }
+}
@@ -0,0 +1,160 @@
+use self::cache_types::*;
+use rocket::fairing::AdHoc;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+pub mod cache_types;
+
+// Bumped whenever ingestion commits snapshots; entries from an older generation are stale
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// Drops every cached response. Called after anything a list response shows changes:
+/// committed or removed snapshots and counted views.
+pub fn invalidate() {
+    GENERATION.fetch_add(1, Ordering::SeqCst);
+}
+
+/// Cache key of a route and its parameters. Parameters are added in a fixed order and
+/// lists are sorted, so requests that only order them differently share an entry.
+pub struct CacheKey(String);
+
+impl CacheKey {
+    pub fn new(route: &str) -> Self {
+        Self(route.to_string())
+    }
+
+    pub fn param(mut self, name: &str, value: impl std::fmt::Debug) -> Self {
+        // Debug quotes and escapes strings, so values can't run into the next parameter
+        self.0.push_str(&format!("&{}={:?}", name, value));
+        self
+    }
+
+    pub fn list(self, name: &str, values: Option<&[String]>) -> Self {
+        let values = values.map(|values| {
+            let mut values = values.to_vec();
+            values.sort();
+            values.dedup();
+            values
+        });
+        self.param(name, values)
+    }
+
+    pub fn build(self) -> String {
+        self.0
+    }
+}
+
+struct Entry {
+    body: Arc<[u8]>,
+    etag: String,
+    generation: u64,
+    created_at: Instant,
+}
+
+/// Short-lived cache of serialized list responses, keyed by route and normalized
+/// parameters.
+pub struct ResponseCache {
+    config: CacheConfig,
+    entries: Mutex<HashMap<String, Entry>>,
+}
+
+impl ResponseCache {
+    pub fn new(config: CacheConfig) -> Self {
+        Self {
+            config,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn ttl(&self) -> Duration {
+        Duration::from_secs(self.config.ttl_secs)
+    }
+
+    fn fresh(&self, entry: &Entry, generation: u64) -> bool {
+        entry.generation == generation && entry.created_at.elapsed() < self.ttl()
+    }
+
+    fn respond(&self, body: Arc<[u8]>, etag: String, if_none_match: &IfNoneMatch) -> CachedJson {
+        CachedJson {
+            not_modified: if_none_match.matches(&etag),
+            body,
+            etag,
+            max_age: self.config.ttl_secs,
+        }
+    }
+
+    /// Serves `key` from the cache while it is fresh, and otherwise runs `compute` and
+    /// caches its result. `compute` returning `None` is passed through uncached.
+    pub async fn get_or_compute<T, Fut>(
+        &self,
+        key: String,
+        if_none_match: &IfNoneMatch,
+        compute: impl FnOnce() -> Fut,
+    ) -> Option<CachedJson>
+    where
+        T: Serialize,
+        Fut: Future<Output = Option<T>>,
+    {
+        // Read before computing, so an invalidation during the query marks the result stale
+        let generation = GENERATION.load(Ordering::SeqCst);
+        if let Some(entry) = self.entries.lock().unwrap().get(&key) {
+            if self.fresh(entry, generation) {
+                return Some(self.respond(entry.body.clone(), entry.etag.clone(), if_none_match));
+            }
+        }
+
+        let value = compute().await?;
+        let body: Arc<[u8]> = match serde_json::to_vec(&value) {
+            Ok(body) => body.into(),
+            Err(e) => {
+                eprintln!("Failed to serialize response for {}: {:?}", key, e);
+                return None;
+            }
+        };
+        let etag = format!("\"{}\"", &hex::encode(Sha256::digest(&body))[..32]);
+
+        if self.config.ttl_secs > 0 {
+            let mut entries = self.entries.lock().unwrap();
+            if entries.len() >= self.config.max_entries && !entries.contains_key(&key) {
+                let current = GENERATION.load(Ordering::SeqCst);
+                entries.retain(|_, entry| self.fresh(entry, current));
+                if entries.len() >= self.config.max_entries {
+                    let oldest = entries
+                        .iter()
+                        .min_by_key(|(_, entry)| entry.created_at)
+                        .map(|(key, _)| key.clone());
+                    if let Some(oldest) = oldest {
+                        entries.remove(&oldest);
+                    }
+                }
+            }
+            if self.config.max_entries > 0 {
+                entries.insert(
+                    key,
+                    Entry {
+                        body: body.clone(),
+                        etag: etag.clone(),
+                        generation,
+                        created_at: Instant::now(),
+                    },
+                );
+            }
+        }
+
+        Some(self.respond(body, etag, if_none_match))
+    }
+}
+
+pub fn stage() -> AdHoc {
+    AdHoc::on_ignite("Cache Stage", |rocket| async {
+        println!("Cache stage on ignite!");
+
+        let config: CacheConfig = rocket.figment().extract_inner("cache").unwrap_or_default();
+
+        rocket.manage(ResponseCache::new(config))
+    })
+}
@@ -0,0 +1,47 @@
+use rocket::request::{self, FromRequest, Request};
+use rocket::serde::Deserialize;
+use rocket::FromForm;
+use utoipa::ToSchema;
+
+#[derive(Clone, Debug, Default, FromForm, ToSchema)]
+pub struct EventFilters {
+    /// `proposal` or `rfp`
+    pub kind: Option<String>,
+    pub id: Option<i32>,
+    pub author_id: Option<String>,
+    pub category: Option<String>,
+}
+
+/// Id of the last event a reconnecting client received, from the
+/// `Last-Event-ID` header that `EventSource` sends automatically.
+pub struct LastEventId(pub Option<i64>);
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for LastEventId {
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        let last_event_id = request
+            .headers()
+            .get_one("Last-Event-ID")
+            .and_then(|id| id.trim().parse::<i64>().ok());
+        request::Outcome::Success(LastEventId(last_event_id))
+    }
+}
+
+/// How long `/events` can be resumed from an old event id.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct EventsConfig {
+    /// Events logged longer ago than this are pruned. A client resuming from a pruned
+    /// id gets the events that are still kept.
+    pub retention_secs: i64,
+}
+
+impl Default for EventsConfig {
+    fn default() -> Self {
+        Self {
+            retention_secs: 7 * 24 * 3600,
+        }
+    }
+}
@@ -0,0 +1,188 @@
+use self::events_types::*;
+use crate::db::{DB, SNAPSHOT_EVENTS_CHANNEL};
+use rocket::response::stream::{Event, EventStream};
+use rocket::{get, http::Status, Shutdown, State};
+use rocket_db_pools::Database;
+use sqlx::postgres::PgListener;
+use std::time::Duration;
+use tokio::sync::broadcast;
+pub mod events_types;
+
+const REPLAY_BATCH_SIZE: i64 = 500;
+const LISTENER_RETRY: Duration = Duration::from_secs(1);
+
+/// Fans the one Postgres listener out to every `/events` subscriber.
+#[derive(Clone)]
+pub struct SnapshotNotifier(broadcast::Sender<()>);
+
+impl Default for SnapshotNotifier {
+    fn default() -> Self {
+        // Subscribers re-read from their cursor on any wake-up, so a small buffer is enough
+        Self(broadcast::channel(16).0)
+    }
+}
+
+impl SnapshotNotifier {
+    pub fn subscribe(&self) -> broadcast::Receiver<()> {
+        self.0.subscribe()
+    }
+
+    fn wake(&self) {
+        // No receivers just means nobody is streaming right now
+        let _ = self.0.send(());
+    }
+}
+
+async fn run_snapshot_listener(db: DB, notifier: SnapshotNotifier) {
+    loop {
+        let mut listener = match PgListener::connect_with(&db).await {
+            Ok(listener) => listener,
+            Err(e) => {
+                eprintln!("Failed to connect snapshot event listener: {:?}", e);
+                tokio::time::sleep(LISTENER_RETRY).await;
+                continue;
+            }
+        };
+        if let Err(e) = listener.listen(SNAPSHOT_EVENTS_CHANNEL).await {
+            eprintln!("Failed to listen for snapshot events: {:?}", e);
+            tokio::time::sleep(LISTENER_RETRY).await;
+            continue;
+        }
+        // Notifications sent while disconnected are lost, number what they were for
+        // and have everyone re-read
+        assign_event_ids(&db, &notifier).await;
+
+        loop {
+            match listener.recv().await {
+                Ok(_) => assign_event_ids(&db, &notifier).await,
+                Err(e) => {
+                    eprintln!("Snapshot event listener failed: {:?}", e);
+                    break;
+                }
+            }
+        }
+        tokio::time::sleep(LISTENER_RETRY).await;
+    }
+}
+
+// Committed snapshots only reach subscribers once they have an event id. Another
+// instance may have numbered them already, so subscribers re-read either way.
+async fn assign_event_ids(db: &DB, notifier: &SnapshotNotifier) {
+    if let Err(e) = db.assign_snapshot_event_ids().await {
+        eprintln!("Failed to assign snapshot event ids: {:?}", e);
+    }
+    notifier.wake();
+}
+
+// Old events are only needed by clients resuming from far back, keep them for a while
+async fn prune_event_log(db: DB, config: EventsConfig) {
+    let interval = Duration::from_secs(3600);
+    loop {
+        if let Err(e) = db.prune_snapshot_events(config.retention_secs).await {
+            eprintln!("Failed to prune snapshot events: {:?}", e);
+        }
+        tokio::time::sleep(interval).await;
+    }
+}
+
+#[utoipa::path(get, path = "/events?<last_event_id>&<filters>", params(
+  ("last_event_id"= i64, Path, description = "resume after this event id; the Last-Event-ID header takes precedence"),
+  ("filters"= EventFilters, Path, description = "kind (proposal or rfp), id, author_id, category"),
+))]
+#[get("/?<last_event_id>&<filters..>")]
+async fn events(
+    last_event_id: Option<i64>,
+    filters: EventFilters,
+    resume: LastEventId,
+    db: &State<DB>,
+    notifier: &State<SnapshotNotifier>,
+    mut shutdown: Shutdown,
+) -> Result<EventStream![], Status> {
+    let db = db.inner().clone();
+
+    // Subscribe before reading the cursor so nothing committed in between is missed
+    let mut wake_ups = notifier.subscribe();
+
+    // Without a resume point only events committed from now on are sent
+    let mut cursor = match resume.0.or(last_event_id) {
+        Some(event_id) => event_id,
+        None => db.get_latest_snapshot_event_id().await.map_err(|e| {
+            eprintln!("Failed to get latest snapshot event id: {:?}", e);
+            Status::InternalServerError
+        })?,
+    };
+
+    Ok(EventStream! {
+        'stream: loop {
+            // Catch up from the cursor; on a notification this picks up the new snapshots
+            loop {
+                let (batch, scanned_to) =
+                    match db.get_snapshot_events(cursor, &filters, REPLAY_BATCH_SIZE).await {
+                        Ok(page) => page,
+                        Err(e) => {
+                            eprintln!("Failed to get snapshot events: {:?}", e);
+                            break 'stream;
+                        }
+                    };
+                for event in batch {
+                    yield Event::json(&event).id(event.event_id.to_string());
+                }
+                // Events the filters skipped are not read again on the next wake-up
+                match scanned_to {
+                    Some(event_id) => cursor = event_id,
+                    None => break,
+                }
+            }
+
+            tokio::select! {
+                wake_up = wake_ups.recv() => {
+                    // Lagging behind only means several wake-ups were merged into one
+                    if let Err(broadcast::error::RecvError::Closed) = wake_up {
+                        break;
+                    }
+                }
+                _ = &mut shutdown => break,
+            }
+        }
+    })
+}
+
+pub fn stage() -> rocket::fairing::AdHoc {
+    rocket::fairing::AdHoc::on_ignite("Events Stage", |rocket| async {
+        println!("Events stage on ignite!");
+
+        let events_config: EventsConfig =
+            rocket.figment().extract_inner("events").unwrap_or_default();
+
+        rocket
+            .manage(SnapshotNotifier::default())
+            .mount("/events", rocket::routes![events])
+            .attach(rocket::fairing::AdHoc::on_liftoff(
+                "Snapshot event listener",
+                |rocket| {
+                    Box::pin(async move {
+                        let notifier = rocket.state::<SnapshotNotifier>().cloned();
+                        match (DB::fetch(rocket), notifier) {
+                            (Some(db), Some(notifier)) => {
+                                tokio::spawn(run_snapshot_listener(db.clone(), notifier));
+                            }
+                            _ => eprintln!("Snapshot event listener needs the database"),
+                        }
+                    })
+                },
+            ))
+            .attach(rocket::fairing::AdHoc::on_liftoff(
+                "Prune snapshot events",
+                move |rocket| {
+                    Box::pin(async move {
+                        match DB::fetch(rocket) {
+                            Some(db) => {
+                                tokio::spawn(prune_event_log(db.clone(), events_config));
+                            }
+                            None => eprintln!("Pruning snapshot events needs the database"),
+                        }
+                    })
+                },
+            ))
+    })
+}
@@ -0,0 +1,120 @@
+use rocket::http::{Accept, ContentType};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExportFormat {
+    Csv,
+    Ndjson,
+    Parquet,
+}
+
+impl ExportFormat {
+    pub fn from_param(format: &str) -> Option<Self> {
+        match format.to_lowercase().as_str() {
+            "csv" => Some(ExportFormat::Csv),
+            "ndjson" | "jsonl" => Some(ExportFormat::Ndjson),
+            "parquet" => Some(ExportFormat::Parquet),
+            _ => None,
+        }
+    }
+
+    /// First supported media type in the `Accept` header, in the client's preference order.
+    pub fn from_accept(accept: &Accept) -> Option<Self> {
+        let mut media_types: Vec<_> = accept.iter().collect();
+        media_types.sort_by(|a, b| b.weight_or(1.0).total_cmp(&a.weight_or(1.0)));
+        media_types.into_iter().find_map(|media_type| {
+            match (media_type.top().as_str(), media_type.sub().as_str()) {
+                ("text", "csv") => Some(ExportFormat::Csv),
+                ("application", "x-ndjson" | "ndjson" | "jsonl") => Some(ExportFormat::Ndjson),
+                ("application", "vnd.apache.parquet" | "x-parquet") => Some(ExportFormat::Parquet),
+                _ => None,
+            }
+        })
+    }
+
+    pub fn content_type(&self) -> ContentType {
+        match self {
+            ExportFormat::Csv => ContentType::CSV,
+            ExportFormat::Ndjson => ContentType::new("application", "x-ndjson"),
+            ExportFormat::Parquet => ContentType::new("application", "vnd.apache.parquet"),
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Ndjson => "ndjson",
+            ExportFormat::Parquet => "parquet",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColumnType {
+    Int,
+    Bool,
+    Text,
+    /// Arrays and objects, written as JSON text
+    Json,
+}
+
+#[derive(Debug)]
+pub struct ExportColumn {
+    pub name: &'static str,
+    pub column_type: ColumnType,
+}
+
+const fn column(name: &'static str, column_type: ColumnType) -> ExportColumn {
+    ExportColumn { name, column_type }
+}
+
+pub const PROPOSAL_COLUMNS: &[ExportColumn] = &[
+    column("proposal_id", ColumnType::Int),
+    column("author_id", ColumnType::Text),
+    column("block_height", ColumnType::Int),
+    column("ts", ColumnType::Int),
+    column("editor_id", ColumnType::Text),
+    column("social_db_post_block_height", ColumnType::Int),
+    column("labels", ColumnType::Json),
+    column("proposal_version", ColumnType::Text),
+    column("proposal_body_version", ColumnType::Text),
+    column("name", ColumnType::Text),
+    column("category", ColumnType::Text),
+    column("summary", ColumnType::Text),
+    column("description", ColumnType::Text),
+    column("linked_proposals", ColumnType::Json),
+    column("linked_rfp", ColumnType::Int),
+    column("requested_sponsorship_usd_amount", ColumnType::Int),
+    column("requested_sponsorship_paid_in_currency", ColumnType::Text),
+    column("requested_sponsor", ColumnType::Text),
+    column("receiver_account", ColumnType::Text),
+    column("supervisor", ColumnType::Text),
+    column("views", ColumnType::Int),
+    column("timeline_status", ColumnType::Text),
+    column("timeline_sponsor_requested_review", ColumnType::Bool),
+    column("timeline_reviewer_completed_attestation", ColumnType::Bool),
+    column("timeline_kyc_verified", ColumnType::Bool),
+    column("timeline_test_transaction_sent", ColumnType::Bool),
+    column("timeline_request_for_trustees_created", ColumnType::Bool),
+    column("timeline_trustees_released_payment", ColumnType::Bool),
+    column("timeline_payouts", ColumnType::Json),
+];
+
+pub const RFP_COLUMNS: &[ExportColumn] = &[
+    column("rfp_id", ColumnType::Int),
+    column("author_id", ColumnType::Text),
+    column("block_height", ColumnType::Int),
+    column("ts", ColumnType::Int),
+    column("editor_id", ColumnType::Text),
+    column("social_db_post_block_height", ColumnType::Int),
+    column("labels", ColumnType::Json),
+    column("linked_proposals", ColumnType::Json),
+    column("rfp_version", ColumnType::Text),
+    column("rfp_body_version", ColumnType::Text),
+    column("name", ColumnType::Text),
+    column("category", ColumnType::Text),
+    column("summary", ColumnType::Text),
+    column("description", ColumnType::Text),
+    column("views", ColumnType::Int),
+    column("submission_deadline", ColumnType::Int),
+    column("timeline_status", ColumnType::Text),
+];
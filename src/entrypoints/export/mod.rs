@@ -0,0 +1,306 @@
+use self::export_types::*;
+use arrow_array::builder::{BooleanBuilder, Int64Builder, StringBuilder};
+use arrow_array::{ArrayRef, RecordBatch};
+use arrow_schema::{DataType, Field, Schema};
+use parquet::arrow::ArrowWriter;
+use rocket::futures::Stream;
+use rocket::http::{Accept, Header};
+use rocket::response::stream::ByteStream;
+use rocket::response::{self, Responder};
+use rocket::Request;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+pub mod export_types;
+
+// Rows per parquet row group, and bytes buffered before a text chunk is sent
+const ROW_GROUP_SIZE: usize = 1024;
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// `format` wins over `Accept`; CSV when neither names a supported format.
+pub fn negotiate_format(
+    format: Option<&str>,
+    accept: Option<&Accept>,
+) -> Result<ExportFormat, String> {
+    match format {
+        Some(format) => {
+            ExportFormat::from_param(format).ok_or(format!("Unknown export format: {}", format))
+        }
+        None => Ok(accept
+            .and_then(ExportFormat::from_accept)
+            .unwrap_or(ExportFormat::Csv)),
+    }
+}
+
+/// Comma separated column names, or every column when none are given.
+pub fn select_columns(
+    available: &'static [ExportColumn],
+    columns: Option<&str>,
+) -> Result<Vec<&'static ExportColumn>, String> {
+    let Some(columns) = columns.filter(|c| !c.trim().is_empty()) else {
+        return Ok(available.iter().collect());
+    };
+    columns
+        .split(',')
+        .map(|name| {
+            let name = name.trim();
+            available
+                .iter()
+                .find(|column| column.name == name)
+                .ok_or(format!("Unknown export column: {}", name))
+        })
+        .collect()
+}
+
+/// Serializes a row and adds `timeline_<field>` for every field of the stored timeline.
+pub fn flatten_row<R: Serialize>(row: &R) -> Map<String, Value> {
+    let Ok(Value::Object(mut map)) = serde_json::to_value(row) else {
+        return Map::new();
+    };
+    // Timelines are stored as the JSON string the contract returns
+    let timeline = match map.get("timeline") {
+        Some(Value::String(s)) => serde_json::from_str::<Value>(s).ok(),
+        Some(value) => Some(value.clone()),
+        None => None,
+    };
+    if let Some(timeline) = timeline {
+        flatten_timeline(&timeline, &mut map);
+    }
+    map
+}
+
+// Review and payment statuses nest inside each other; the outermost value of a field wins
+fn flatten_timeline(value: &Value, row: &mut Map<String, Value>) {
+    let Value::Object(fields) = value else {
+        return;
+    };
+    for (key, value) in fields {
+        match value {
+            Value::Object(_) => flatten_timeline(value, row),
+            _ => {
+                row.entry(format!("timeline_{}", key))
+                    .or_insert_with(|| value.clone());
+            }
+        }
+    }
+}
+
+fn text_value(value: Option<&Value>, column_type: ColumnType) -> Option<String> {
+    match value? {
+        Value::Null => None,
+        Value::String(s) if column_type != ColumnType::Json => Some(s.clone()),
+        value => Some(value.to_string()),
+    }
+}
+
+// std::io::Write handle the csv and parquet writers write through while the stream drains it
+#[derive(Clone, Default)]
+struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+impl SharedBuffer {
+    fn len(&self) -> usize {
+        self.0.lock().unwrap().len()
+    }
+
+    fn take(&self) -> Vec<u8> {
+        std::mem::take(&mut *self.0.lock().unwrap())
+    }
+}
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+enum Sink {
+    Csv {
+        writer: csv::Writer<SharedBuffer>,
+        buffer: SharedBuffer,
+    },
+    Ndjson(Vec<u8>),
+    Parquet {
+        writer: ArrowWriter<SharedBuffer>,
+        buffer: SharedBuffer,
+        schema: Arc<Schema>,
+        rows: Vec<Map<String, Value>>,
+    },
+}
+
+/// Turns flattened rows into chunks of the export format, holding at most one chunk
+/// (or one parquet row group) in memory.
+pub struct ExportEncoder {
+    columns: Vec<&'static ExportColumn>,
+    sink: Sink,
+}
+
+impl ExportEncoder {
+    pub fn new(format: ExportFormat, columns: Vec<&'static ExportColumn>) -> anyhow::Result<Self> {
+        let sink = match format {
+            ExportFormat::Csv => {
+                let buffer = SharedBuffer::default();
+                let mut writer = csv::Writer::from_writer(buffer.clone());
+                writer.write_record(columns.iter().map(|c| c.name))?;
+                Sink::Csv { writer, buffer }
+            }
+            ExportFormat::Ndjson => Sink::Ndjson(vec![]),
+            ExportFormat::Parquet => {
+                let schema = Arc::new(Schema::new(
+                    columns
+                        .iter()
+                        .map(|c| {
+                            let data_type = match c.column_type {
+                                ColumnType::Int => DataType::Int64,
+                                ColumnType::Bool => DataType::Boolean,
+                                ColumnType::Text | ColumnType::Json => DataType::Utf8,
+                            };
+                            Field::new(c.name, data_type, true)
+                        })
+                        .collect::<Vec<_>>(),
+                ));
+                let buffer = SharedBuffer::default();
+                let writer = ArrowWriter::try_new(buffer.clone(), schema.clone(), None)?;
+                Sink::Parquet {
+                    writer,
+                    buffer,
+                    schema,
+                    rows: Vec::with_capacity(ROW_GROUP_SIZE),
+                }
+            }
+        };
+        Ok(Self { columns, sink })
+    }
+
+    /// Adds a row; returns a chunk once enough output has accumulated.
+    pub fn push(&mut self, row: Map<String, Value>) -> anyhow::Result<Option<Vec<u8>>> {
+        match &mut self.sink {
+            Sink::Csv { writer, buffer } => {
+                writer.write_record(
+                    self.columns
+                        .iter()
+                        .map(|c| text_value(row.get(c.name), c.column_type).unwrap_or_default()),
+                )?;
+                // The csv writer flushes its own buffer into ours as it fills
+                if buffer.len() >= CHUNK_SIZE {
+                    writer.flush()?;
+                    return Ok(Some(buffer.take()));
+                }
+            }
+            Sink::Ndjson(buffer) => {
+                let selected: Map<String, Value> = self
+                    .columns
+                    .iter()
+                    .map(|c| {
+                        let value = row.get(c.name).cloned().unwrap_or(Value::Null);
+                        (c.name.to_string(), value)
+                    })
+                    .collect();
+                serde_json::to_writer(&mut *buffer, &selected)?;
+                buffer.push(b'\n');
+                if buffer.len() >= CHUNK_SIZE {
+                    return Ok(Some(std::mem::take(buffer)));
+                }
+            }
+            Sink::Parquet {
+                writer,
+                buffer,
+                schema,
+                rows,
+            } => {
+                rows.push(row);
+                if rows.len() >= ROW_GROUP_SIZE {
+                    let batch = record_batch(&self.columns, schema.clone(), rows)?;
+                    rows.clear();
+                    writer.write(&batch)?;
+                    // Closes the row group so its bytes can be sent
+                    writer.flush()?;
+                    return Ok(Some(buffer.take()));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// Whatever is still buffered, including the parquet footer.
+    pub fn finish(self) -> anyhow::Result<Vec<u8>> {
+        match self.sink {
+            Sink::Csv { mut writer, buffer } => {
+                writer.flush()?;
+                Ok(buffer.take())
+            }
+            Sink::Ndjson(buffer) => Ok(buffer),
+            Sink::Parquet {
+                mut writer,
+                buffer,
+                schema,
+                rows,
+            } => {
+                if !rows.is_empty() {
+                    writer.write(&record_batch(&self.columns, schema, &rows)?)?;
+                }
+                writer.close()?;
+                Ok(buffer.take())
+            }
+        }
+    }
+}
+
+fn record_batch(
+    columns: &[&'static ExportColumn],
+    schema: Arc<Schema>,
+    rows: &[Map<String, Value>],
+) -> anyhow::Result<RecordBatch> {
+    let arrays: Vec<ArrayRef> = columns
+        .iter()
+        .map(|c| -> ArrayRef {
+            let values = rows.iter().map(|row| row.get(c.name));
+            match c.column_type {
+                ColumnType::Int => {
+                    let mut builder = Int64Builder::with_capacity(rows.len());
+                    values.for_each(|v| builder.append_option(v.and_then(Value::as_i64)));
+                    Arc::new(builder.finish())
+                }
+                ColumnType::Bool => {
+                    let mut builder = BooleanBuilder::with_capacity(rows.len());
+                    values.for_each(|v| builder.append_option(v.and_then(Value::as_bool)));
+                    Arc::new(builder.finish())
+                }
+                ColumnType::Text | ColumnType::Json => {
+                    let mut builder = StringBuilder::new();
+                    values.for_each(|v| builder.append_option(text_value(v, c.column_type)));
+                    Arc::new(builder.finish())
+                }
+            }
+        })
+        .collect();
+    Ok(RecordBatch::try_new(schema, arrays)?)
+}
+
+/// Streamed export download with its content type and file name.
+pub struct Export<S> {
+    pub format: ExportFormat,
+    pub name: &'static str,
+    pub stream: ByteStream<S>,
+}
+
+impl<'r, S: Stream<Item = Vec<u8>> + Send + 'r> Responder<'r, 'r> for Export<S> {
+    fn respond_to(self, request: &'r Request<'_>) -> response::Result<'r> {
+        let mut response = self.stream.respond_to(request)?;
+        response.set_header(self.format.content_type());
+        response.set_header(Header::new(
+            "Content-Disposition",
+            format!(
+                "attachment; filename=\"{}.{}\"",
+                self.name,
+                self.format.extension()
+            ),
+        ));
+        Ok(response)
+    }
+}
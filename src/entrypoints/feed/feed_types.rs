@@ -0,0 +1,39 @@
+use crate::db::db_types::Timestamp;
+
+pub struct Feed {
+    pub id: String,
+    pub title: String,
+    pub link: String,
+    pub description: String,
+    pub items: Vec<FeedItem>,
+}
+
+pub struct FeedItem {
+    pub id: String,
+    pub title: String,
+    pub link: String,
+    pub summary: Option<String>,
+    pub author: String,
+    pub category: Option<String>,
+    /// Snapshot timestamp in nanoseconds
+    pub updated: Timestamp,
+}
+
+/// Singular and plural names of what a feed lists, as used in links and titles.
+pub struct FeedKind {
+    pub page: &'static str,
+    pub list_page: &'static str,
+    pub label: &'static str,
+}
+
+pub const PROPOSAL_FEED: FeedKind = FeedKind {
+    page: "proposal",
+    list_page: "proposals",
+    label: "Proposal",
+};
+
+pub const RFP_FEED: FeedKind = FeedKind {
+    page: "rfp",
+    list_page: "rfps",
+    label: "RFP",
+};
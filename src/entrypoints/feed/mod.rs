@@ -0,0 +1,170 @@
+use self::feed_types::*;
+use crate::db::db_types::{FeedEntryRecord, Timestamp};
+use chrono::{DateTime, SecondsFormat, Utc};
+use near_account_id::AccountId;
+use rocket::http::ContentType;
+pub mod feed_types;
+
+pub const DEFAULT_FEED_LIMIT: i64 = 50;
+pub const MAX_FEED_LIMIT: i64 = 200;
+
+pub fn atom_content_type() -> ContentType {
+    ContentType::new("application", "atom+xml")
+}
+
+pub fn rss_content_type() -> ContentType {
+    ContentType::new("application", "rss+xml")
+}
+
+fn app_link(contract: &AccountId, query: &str) -> String {
+    format!("https://near.social/{}/widget/app?{}", contract, query)
+}
+
+pub fn feed_from_entries(
+    kind: &FeedKind,
+    contract: &AccountId,
+    entries: Vec<FeedEntryRecord>,
+) -> Feed {
+    let items = entries
+        .into_iter()
+        .map(|entry| {
+            let name = entry.name.unwrap_or_default();
+            let title = match (&entry.stage, entry.created) {
+                (_, true) => format!("New {} #{}: {}", kind.label, entry.id, name),
+                (Some(stage), false) => {
+                    format!("{} #{} moved to {}: {}", kind.label, entry.id, stage, name)
+                }
+                (None, false) => format!("{} #{} updated: {}", kind.label, entry.id, name),
+            };
+            FeedItem {
+                id: format!("urn:{}:{}:{}:{}", contract, kind.page, entry.id, entry.ts),
+                title,
+                link: app_link(contract, &format!("page={}&id={}", kind.page, entry.id)),
+                summary: entry.summary,
+                author: entry.author_id,
+                category: entry.category,
+                updated: entry.ts,
+            }
+        })
+        .collect();
+
+    Feed {
+        id: format!("urn:{}:{}", contract, kind.list_page),
+        title: format!("{} {}s", contract, kind.label),
+        link: app_link(contract, &format!("page={}", kind.list_page)),
+        description: format!("New {}s and stage changes on {}", kind.label, contract),
+        items,
+    }
+}
+
+fn datetime(ts: Timestamp) -> DateTime<Utc> {
+    DateTime::from_timestamp_nanos(ts)
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+// Newest entry, or now for an empty feed
+fn feed_updated(feed: &Feed) -> DateTime<Utc> {
+    feed.items
+        .iter()
+        .map(|item| item.updated)
+        .max()
+        .map(datetime)
+        .unwrap_or_else(Utc::now)
+}
+
+pub fn render_atom(feed: &Feed) -> String {
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+    xml.push_str("<feed xmlns=\"http://www.w3.org/2005/Atom\">\n");
+    xml.push_str(&format!("  <id>{}</id>\n", escape(&feed.id)));
+    xml.push_str(&format!("  <title>{}</title>\n", escape(&feed.title)));
+    xml.push_str(&format!(
+        "  <subtitle>{}</subtitle>\n",
+        escape(&feed.description)
+    ));
+    xml.push_str(&format!("  <link href=\"{}\"/>\n", escape(&feed.link)));
+    xml.push_str(&format!(
+        "  <updated>{}</updated>\n",
+        feed_updated(feed).to_rfc3339_opts(SecondsFormat::Secs, true)
+    ));
+    for item in &feed.items {
+        xml.push_str("  <entry>\n");
+        xml.push_str(&format!("    <id>{}</id>\n", escape(&item.id)));
+        xml.push_str(&format!("    <title>{}</title>\n", escape(&item.title)));
+        xml.push_str(&format!("    <link href=\"{}\"/>\n", escape(&item.link)));
+        xml.push_str(&format!(
+            "    <updated>{}</updated>\n",
+            datetime(item.updated).to_rfc3339_opts(SecondsFormat::Secs, true)
+        ));
+        xml.push_str(&format!(
+            "    <author><name>{}</name></author>\n",
+            escape(&item.author)
+        ));
+        if let Some(category) = &item.category {
+            xml.push_str(&format!("    <category term=\"{}\"/>\n", escape(category)));
+        }
+        if let Some(summary) = &item.summary {
+            xml.push_str(&format!("    <summary>{}</summary>\n", escape(summary)));
+        }
+        xml.push_str("  </entry>\n");
+    }
+    xml.push_str("</feed>\n");
+    xml
+}
+
+pub fn render_rss(feed: &Feed) -> String {
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+    xml.push_str(
+        "<rss version=\"2.0\" xmlns:dc=\"http://purl.org/dc/elements/1.1/\">\n  <channel>\n",
+    );
+    xml.push_str(&format!("    <title>{}</title>\n", escape(&feed.title)));
+    xml.push_str(&format!("    <link>{}</link>\n", escape(&feed.link)));
+    xml.push_str(&format!(
+        "    <description>{}</description>\n",
+        escape(&feed.description)
+    ));
+    xml.push_str(&format!(
+        "    <lastBuildDate>{}</lastBuildDate>\n",
+        feed_updated(feed).to_rfc2822()
+    ));
+    for item in &feed.items {
+        xml.push_str("    <item>\n");
+        xml.push_str(&format!(
+            "      <guid isPermaLink=\"false\">{}</guid>\n",
+            escape(&item.id)
+        ));
+        xml.push_str(&format!("      <title>{}</title>\n", escape(&item.title)));
+        xml.push_str(&format!("      <link>{}</link>\n", escape(&item.link)));
+        xml.push_str(&format!(
+            "      <pubDate>{}</pubDate>\n",
+            datetime(item.updated).to_rfc2822()
+        ));
+        // RSS <author> must be an email address, so the account id goes in dc:creator
+        xml.push_str(&format!(
+            "      <dc:creator>{}</dc:creator>\n",
+            escape(&item.author)
+        ));
+        if let Some(category) = &item.category {
+            xml.push_str(&format!(
+                "      <category>{}</category>\n",
+                escape(category)
+            ));
+        }
+        if let Some(summary) = &item.summary {
+            xml.push_str(&format!(
+                "      <description>{}</description>\n",
+                escape(summary)
+            ));
+        }
+        xml.push_str("    </item>\n");
+    }
+    xml.push_str("  </channel>\n</rss>\n");
+    xml
+}
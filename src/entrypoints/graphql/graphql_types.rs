@@ -0,0 +1,45 @@
+use crate::db::db_types::{ProposalWithLatestSnapshotView, RfpWithLatestSnapshotView};
+use async_graphql::{Enum, SimpleObject};
+
+pub const DEFAULT_PAGE_LIMIT: i64 = 10;
+/// Larger `limit` arguments of the list queries are lowered to this.
+pub const MAX_PAGE_LIMIT: i64 = 100;
+
+/// Same orders as the `order` query parameter of the REST list endpoints.
+#[derive(Enum, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Order {
+    TsAsc,
+    TsDesc,
+    IdAsc,
+    IdDesc,
+    /// Proposals only; RFPs fall back to `IdDesc`
+    ViewsDesc,
+}
+
+impl Order {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Order::TsAsc => "ts_asc",
+            Order::TsDesc => "ts_desc",
+            Order::IdAsc => "id_asc",
+            Order::IdDesc => "id_desc",
+            Order::ViewsDesc => "views_desc",
+        }
+    }
+}
+
+#[derive(SimpleObject)]
+pub struct ProposalPage {
+    pub records: Vec<ProposalWithLatestSnapshotView>,
+    pub total_records: i64,
+    pub limit: i64,
+    pub offset: i64,
+}
+
+#[derive(SimpleObject)]
+pub struct RfpPage {
+    pub records: Vec<RfpWithLatestSnapshotView>,
+    pub total_records: i64,
+    pub limit: i64,
+    pub offset: i64,
+}
@@ -0,0 +1,105 @@
+use crate::db::db_types::{
+    ProposalSnapshotRecord, ProposalWithLatestSnapshotView, RfpSnapshotRecord,
+    RfpWithLatestSnapshotView,
+};
+use crate::db::DB;
+use async_graphql::dataloader::Loader;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+// One loader serves every entity; the key type selects the batch query
+pub struct DbLoader(pub DB);
+
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub struct ProposalKey(pub i32);
+
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub struct RfpKey(pub i32);
+
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub struct ProposalSnapshotsKey(pub i32);
+
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub struct RfpSnapshotsKey(pub i32);
+
+fn ids<K: Copy>(keys: &[K], id: impl Fn(K) -> i32) -> Vec<i32> {
+    keys.iter().map(|key| id(*key)).collect()
+}
+
+impl Loader<ProposalKey> for DbLoader {
+    type Value = ProposalWithLatestSnapshotView;
+    type Error = Arc<sqlx::Error>;
+
+    async fn load(
+        &self,
+        keys: &[ProposalKey],
+    ) -> Result<HashMap<ProposalKey, Self::Value>, Self::Error> {
+        let proposals = self
+            .0
+            .get_proposals_with_latest_snapshot_by_ids(&ids(keys, |k| k.0))
+            .await?;
+        Ok(proposals
+            .into_iter()
+            .map(|proposal| (ProposalKey(proposal.proposal_id), proposal))
+            .collect())
+    }
+}
+
+impl Loader<RfpKey> for DbLoader {
+    type Value = RfpWithLatestSnapshotView;
+    type Error = Arc<sqlx::Error>;
+
+    async fn load(&self, keys: &[RfpKey]) -> Result<HashMap<RfpKey, Self::Value>, Self::Error> {
+        let rfps = self
+            .0
+            .get_rfps_with_latest_snapshot_by_ids(&ids(keys, |k| k.0))
+            .await?;
+        Ok(rfps
+            .into_iter()
+            .map(|rfp| (RfpKey(rfp.rfp_id), rfp))
+            .collect())
+    }
+}
+
+impl Loader<ProposalSnapshotsKey> for DbLoader {
+    type Value = Vec<ProposalSnapshotRecord>;
+    type Error = Arc<sqlx::Error>;
+
+    async fn load(
+        &self,
+        keys: &[ProposalSnapshotsKey],
+    ) -> Result<HashMap<ProposalSnapshotsKey, Self::Value>, Self::Error> {
+        let snapshots = self
+            .0
+            .get_proposal_snapshots_by_ids(&ids(keys, |k| k.0))
+            .await?;
+        let mut grouped: HashMap<ProposalSnapshotsKey, Self::Value> = HashMap::new();
+        for snapshot in snapshots {
+            grouped
+                .entry(ProposalSnapshotsKey(snapshot.proposal_id))
+                .or_default()
+                .push(snapshot);
+        }
+        Ok(grouped)
+    }
+}
+
+impl Loader<RfpSnapshotsKey> for DbLoader {
+    type Value = Vec<RfpSnapshotRecord>;
+    type Error = Arc<sqlx::Error>;
+
+    async fn load(
+        &self,
+        keys: &[RfpSnapshotsKey],
+    ) -> Result<HashMap<RfpSnapshotsKey, Self::Value>, Self::Error> {
+        let snapshots = self.0.get_rfp_snapshots_by_ids(&ids(keys, |k| k.0)).await?;
+        let mut grouped: HashMap<RfpSnapshotsKey, Self::Value> = HashMap::new();
+        for snapshot in snapshots {
+            grouped
+                .entry(RfpSnapshotsKey(snapshot.rfp_id))
+                .or_default()
+                .push(snapshot);
+        }
+        Ok(grouped)
+    }
+}
@@ -0,0 +1,242 @@
+use self::graphql_types::*;
+use self::loaders::*;
+use crate::changelog::fetch_changelog_from_rpc;
+use crate::db::db_types::{
+    ProposalSnapshotRecord, ProposalWithLatestSnapshotView, RfpSnapshotRecord,
+    RfpWithLatestSnapshotView,
+};
+use crate::db::DB;
+use crate::entrypoints::proposal::proposal_types::GetProposalFilters;
+use crate::entrypoints::rfp::rfp_types::GetRfpFilters;
+use crate::nearblocks_client::types::LinkedProposals;
+use crate::rpc_service::RpcService;
+use async_graphql::dataloader::DataLoader;
+use async_graphql::http::GraphiQLSource;
+use async_graphql::{ComplexObject, Context, EmptyMutation, EmptySubscription, Object, Schema};
+use async_graphql_rocket::{GraphQLQuery, GraphQLRequest, GraphQLResponse};
+use rocket::response::content::RawHtml;
+use rocket::{get, post, State};
+pub mod graphql_types;
+pub mod loaders;
+
+pub type DevhubSchema = Schema<QueryRoot, EmptyMutation, EmptySubscription>;
+
+// Proposals link to RFPs and back, so cap how deep a query can follow them
+const MAX_QUERY_DEPTH: usize = 12;
+
+pub struct QueryRoot;
+
+#[Object]
+impl QueryRoot {
+    /// Proposals with their latest snapshot, filtered like `GET /proposals`.
+    async fn proposals(
+        &self,
+        ctx: &Context<'_>,
+        order: Option<Order>,
+        limit: Option<i64>,
+        offset: Option<i64>,
+        filters: Option<GetProposalFilters>,
+    ) -> async_graphql::Result<ProposalPage> {
+        let db = ctx.data::<DB>()?;
+        refresh_from_changelog(ctx).await?;
+
+        let limit = limit.unwrap_or(DEFAULT_PAGE_LIMIT).clamp(1, MAX_PAGE_LIMIT);
+        let offset = offset.unwrap_or(0);
+        let order = order.unwrap_or(Order::IdDesc);
+        let (records, total_records) = db
+            .get_proposals_with_latest_snapshot(limit, order.as_str(), offset, filters)
+            .await?;
+
+        Ok(ProposalPage {
+            records,
+            total_records,
+            limit,
+            offset,
+        })
+    }
+
+    async fn proposal(
+        &self,
+        ctx: &Context<'_>,
+        id: i32,
+    ) -> async_graphql::Result<Option<ProposalWithLatestSnapshotView>> {
+        let loader = ctx.data::<DataLoader<DbLoader>>()?;
+        Ok(loader.load_one(ProposalKey(id)).await?)
+    }
+
+    /// RFPs with their latest snapshot, filtered like `GET /rfps`.
+    async fn rfps(
+        &self,
+        ctx: &Context<'_>,
+        order: Option<Order>,
+        limit: Option<i64>,
+        offset: Option<i64>,
+        filters: Option<GetRfpFilters>,
+    ) -> async_graphql::Result<RfpPage> {
+        let db = ctx.data::<DB>()?;
+        refresh_from_changelog(ctx).await?;
+
+        let limit = limit.unwrap_or(DEFAULT_PAGE_LIMIT).clamp(1, MAX_PAGE_LIMIT);
+        let offset = offset.unwrap_or(0);
+        let order = order.unwrap_or(Order::IdDesc);
+        let (records, total_records) = db
+            .get_rfps_with_latest_snapshot(limit, order.as_str(), offset, filters)
+            .await?;
+
+        Ok(RfpPage {
+            records,
+            total_records,
+            limit,
+            offset,
+        })
+    }
+
+    async fn rfp(
+        &self,
+        ctx: &Context<'_>,
+        id: i32,
+    ) -> async_graphql::Result<Option<RfpWithLatestSnapshotView>> {
+        let loader = ctx.data::<DataLoader<DbLoader>>()?;
+        Ok(loader.load_one(RfpKey(id)).await?)
+    }
+}
+
+#[ComplexObject]
+impl ProposalWithLatestSnapshotView {
+    /// The RFP this proposal was submitted to.
+    #[graphql(name = "linkedRfp")]
+    async fn resolve_linked_rfp(
+        &self,
+        ctx: &Context<'_>,
+    ) -> async_graphql::Result<Option<RfpWithLatestSnapshotView>> {
+        let Some(rfp_id) = self.linked_rfp else {
+            return Ok(None);
+        };
+        let loader = ctx.data::<DataLoader<DbLoader>>()?;
+        Ok(loader.load_one(RfpKey(rfp_id)).await?)
+    }
+
+    #[graphql(name = "linkedProposals")]
+    async fn resolve_linked_proposals(
+        &self,
+        ctx: &Context<'_>,
+    ) -> async_graphql::Result<Vec<ProposalWithLatestSnapshotView>> {
+        load_proposals(ctx, self.linked_proposals.clone()).await
+    }
+
+    /// Snapshot history, newest first.
+    async fn snapshots(
+        &self,
+        ctx: &Context<'_>,
+    ) -> async_graphql::Result<Vec<ProposalSnapshotRecord>> {
+        let loader = ctx.data::<DataLoader<DbLoader>>()?;
+        Ok(loader
+            .load_one(ProposalSnapshotsKey(self.proposal_id))
+            .await?
+            .unwrap_or_default())
+    }
+}
+
+#[ComplexObject]
+impl RfpWithLatestSnapshotView {
+    #[graphql(name = "linkedProposals")]
+    async fn resolve_linked_proposals(
+        &self,
+        ctx: &Context<'_>,
+    ) -> async_graphql::Result<Vec<ProposalWithLatestSnapshotView>> {
+        load_proposals(ctx, self.linked_proposals.clone()).await
+    }
+
+    /// Snapshot history, newest first.
+    async fn snapshots(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<RfpSnapshotRecord>> {
+        let loader = ctx.data::<DataLoader<DbLoader>>()?;
+        Ok(loader
+            .load_one(RfpSnapshotsKey(self.rfp_id))
+            .await?
+            .unwrap_or_default())
+    }
+}
+
+async fn load_proposals(
+    ctx: &Context<'_>,
+    linked_proposals: Option<serde_json::Value>,
+) -> async_graphql::Result<Vec<ProposalWithLatestSnapshotView>> {
+    let loader = ctx.data::<DataLoader<DbLoader>>()?;
+    let LinkedProposals(ids) = LinkedProposals::from(linked_proposals);
+    let mut proposals = loader
+        .load_many(ids.iter().copied().map(ProposalKey))
+        .await?;
+    // Keep the order the links were stored in, skipping ids that aren't cached
+    Ok(ids
+        .iter()
+        .filter_map(|id| proposals.remove(&ProposalKey(*id)))
+        .collect())
+}
+
+// Same freshness guarantee as the REST list endpoints
+async fn refresh_from_changelog(ctx: &Context<'_>) -> async_graphql::Result<()> {
+    let db = ctx.data::<DB>()?;
+    let rpc_service = ctx.data::<RpcService>()?;
+    let last_updated_info = db.get_last_updated_info().await?;
+    let _ = fetch_changelog_from_rpc(db, rpc_service, Some(last_updated_info.after_block)).await;
+    Ok(())
+}
+
+pub fn schema() -> DevhubSchema {
+    Schema::build(QueryRoot, EmptyMutation, EmptySubscription)
+        .limit_depth(MAX_QUERY_DEPTH)
+        .finish()
+}
+
+// Loaders cache per request, so a fresh one is attached to every query
+fn with_context(request: GraphQLRequest, db: &DB, rpc_service: &RpcService) -> GraphQLRequest {
+    request
+        .data(db.clone())
+        .data(rpc_service.clone())
+        .data(DataLoader::new(DbLoader(db.clone()), tokio::spawn))
+}
+
+#[get("/?<query..>")]
+async fn graphql_query(
+    query: GraphQLQuery,
+    schema: &State<DevhubSchema>,
+    db: &State<DB>,
+    rpc_service: &State<RpcService>,
+) -> GraphQLResponse {
+    with_context(query.into(), db, rpc_service)
+        .execute(schema.inner())
+        .await
+}
+
+#[post("/", data = "<request>", format = "application/json")]
+async fn graphql_request(
+    request: GraphQLRequest,
+    schema: &State<DevhubSchema>,
+    db: &State<DB>,
+    rpc_service: &State<RpcService>,
+) -> GraphQLResponse {
+    with_context(request, db, rpc_service)
+        .execute(schema.inner())
+        .await
+}
+
+#[get("/graphiql")]
+fn graphiql() -> RawHtml<String> {
+    RawHtml(GraphiQLSource::build().endpoint("/graphql").finish())
+}
+
+#[get("/schema.graphql")]
+fn sdl(schema: &State<DevhubSchema>) -> String {
+    schema.sdl()
+}
+
+pub fn stage() -> rocket::fairing::AdHoc {
+    rocket::fairing::AdHoc::on_ignite("GraphQL Stage", |rocket| async {
+        println!("GraphQL stage on ignite!");
+
+        rocket.manage(schema()).mount(
+            "/graphql",
+            rocket::routes![graphql_query, graphql_request, graphiql, sdl],
+        )
+    })
+}
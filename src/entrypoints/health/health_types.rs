@@ -0,0 +1,45 @@
+use crate::changelog::IngestionStatus;
+use rocket::serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Checks `/health/ready` makes beyond the database and migrations.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct HealthConfig {
+    /// `/health/ready` fails once the oldest unprocessed change is older than this; 0 disables
+    /// the check. Changes are only ingested while requests come in, so it is off by default:
+    /// an idle instance taken out of rotation would never catch up.
+    pub max_lag_secs: u64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct Liveness {
+    pub status: String,
+}
+
+#[derive(Clone, Debug, Serialize, ToSchema)]
+pub struct IndexerHealth {
+    /// `last_updated_info.after_block`
+    pub after_block: i64,
+    pub chain_head: Option<u64>,
+    /// Contract changes after `after_block` that are not cached yet
+    pub pending_changes: Option<usize>,
+    /// Blocks and seconds between the oldest pending change and the chain head, 0 when
+    /// nothing is pending
+    pub lag_blocks: Option<u64>,
+    pub lag_seconds: Option<u64>,
+    #[serde(flatten)]
+    pub ingestion: IngestionStatus,
+    /// Set when the chain head or change log could not be read
+    pub rpc_error: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, ToSchema)]
+pub struct Readiness {
+    pub ready: bool,
+    pub database: bool,
+    pub pending_migrations: Vec<i64>,
+    /// `None` when the lag check is disabled or the lag could not be measured
+    pub lag_ok: Option<bool>,
+    pub errors: Vec<String>,
+}
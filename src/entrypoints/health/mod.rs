@@ -0,0 +1,226 @@
+use self::health_types::*;
+use crate::auth::Admin;
+use crate::changelog::ingestion_status;
+use crate::db::DB;
+use crate::reconciliation::gaps::{detect_gaps, run_gap_backfill, run_gap_monitor};
+use crate::reconciliation::types::{BackfillStatus, GapBackfill, GapConfig, GapReport};
+use crate::rpc_service::pool::EndpointStatus;
+use crate::rpc_service::RpcService;
+use rocket::response::status::Accepted;
+use rocket::serde::json::Json;
+use rocket::{get, http::Status, post, State};
+use rocket_db_pools::Database;
+pub mod health_types;
+
+#[utoipa::path(get, path = "/health/live")]
+#[get("/live")]
+fn live() -> Json<Liveness> {
+    Json(Liveness {
+        status: "ok".to_string(),
+    })
+}
+
+async fn indexer_health(db: &DB, rpc_service: &RpcService) -> Result<IndexerHealth, String> {
+    let after_block = db
+        .get_last_updated_info()
+        .await
+        .map_err(|e| format!("Failed to read last updated info: {}", e))?
+        .after_block;
+    let mut health = IndexerHealth {
+        after_block,
+        chain_head: None,
+        pending_changes: None,
+        lag_blocks: None,
+        lag_seconds: None,
+        ingestion: ingestion_status(),
+        rpc_error: None,
+    };
+
+    let head = rpc_service.get_chain_head().await;
+    let pending = rpc_service.get_change_log_since(after_block).await;
+    match (head, pending) {
+        (Ok((height, timestamp)), Ok(pending)) => {
+            health.chain_head = Some(height);
+            health.pending_changes = Some(pending.len());
+            let oldest = pending.iter().min_by_key(|change| change.block_id);
+            health.lag_blocks = Some(oldest.map_or(0, |c| height.saturating_sub(c.block_id)));
+            health.lag_seconds = Some(oldest.map_or(0, |c| {
+                timestamp.saturating_sub(c.block_timestamp) / 1_000_000_000
+            }));
+        }
+        (head, pending) => {
+            health.chain_head = head.as_ref().ok().map(|(height, _)| *height);
+            health.rpc_error = head.err().or(pending.err()).map(|e| e.to_string());
+        }
+    }
+    Ok(health)
+}
+
+#[utoipa::path(get, path = "/health/indexer")]
+#[get("/indexer")]
+async fn indexer(
+    db: &State<DB>,
+    rpc_service: &State<RpcService>,
+) -> Result<Json<IndexerHealth>, (Status, String)> {
+    indexer_health(db.inner(), rpc_service.inner())
+        .await
+        .map(Json)
+        .map_err(|e| (Status::ServiceUnavailable, e))
+}
+
+#[utoipa::path(get, path = "/health/rpc")]
+#[get("/rpc")]
+fn rpc(rpc_service: &State<RpcService>) -> Json<Vec<EndpointStatus>> {
+    Json(rpc_service.endpoint_status())
+}
+
+#[utoipa::path(get, path = "/health/ready")]
+#[get("/ready")]
+async fn ready(
+    db: &State<DB>,
+    rpc_service: &State<RpcService>,
+    config: &State<HealthConfig>,
+) -> (Status, Json<Readiness>) {
+    let mut readiness = Readiness {
+        ready: true,
+        database: true,
+        pending_migrations: vec![],
+        lag_ok: None,
+        errors: vec![],
+    };
+
+    match db.get_pending_migrations().await {
+        Ok(pending) if pending.is_empty() => {}
+        Ok(pending) => {
+            readiness
+                .errors
+                .push(format!("{} migrations are not applied", pending.len()));
+            readiness.pending_migrations = pending;
+        }
+        Err(e) => {
+            readiness.database = false;
+            readiness
+                .errors
+                .push(format!("Database check failed: {}", e));
+        }
+    }
+
+    if config.max_lag_secs > 0 && readiness.database {
+        match indexer_health(db.inner(), rpc_service.inner()).await {
+            Ok(IndexerHealth {
+                lag_seconds: Some(lag),
+                ..
+            }) => {
+                let lag_ok = lag <= config.max_lag_secs;
+                if !lag_ok {
+                    readiness
+                        .errors
+                        .push(format!("Indexer is {} seconds behind", lag));
+                }
+                readiness.lag_ok = Some(lag_ok);
+            }
+            // An RPC outage doesn't make the cached data unservable
+            Ok(_) => {}
+            Err(e) => readiness.errors.push(e),
+        }
+    }
+
+    readiness.ready = readiness.errors.is_empty();
+    let status = if readiness.ready {
+        Status::Ok
+    } else {
+        Status::ServiceUnavailable
+    };
+    (status, Json(readiness))
+}
+
+// Two full id lists from RPC per call, so only admins can trigger it
+#[utoipa::path(get, path = "/health/gaps")]
+#[get("/gaps")]
+async fn get_gaps(
+    _admin: Admin,
+    db: &State<DB>,
+    rpc_service: &State<RpcService>,
+) -> Result<Json<GapReport>, Status> {
+    match detect_gaps(db.inner(), rpc_service.inner()).await {
+        Ok(report) => Ok(Json(report)),
+        Err(e) => {
+            eprintln!("Failed to check for id gaps: {:?}", e);
+            Err(Status::ServiceUnavailable)
+        }
+    }
+}
+
+#[utoipa::path(post, path = "/health/gaps/backfill")]
+#[post("/gaps/backfill")]
+async fn backfill(
+    _admin: Admin,
+    db: &State<DB>,
+    rpc_service: &State<RpcService>,
+    config: &State<GapConfig>,
+    gap_backfill: &State<GapBackfill>,
+) -> Result<Accepted<Json<BackfillStatus>>, (Status, String)> {
+    if !gap_backfill.try_start() {
+        return Err((
+            Status::Conflict,
+            "A gap backfill is already running".to_string(),
+        ));
+    }
+    tokio::spawn(run_gap_backfill(
+        db.inner().clone(),
+        rpc_service.inner().clone(),
+        config.concurrency,
+        gap_backfill.inner().clone(),
+    ));
+    Ok(Accepted(Json(gap_backfill.status())))
+}
+
+#[utoipa::path(get, path = "/health/gaps/backfill")]
+#[get("/gaps/backfill")]
+fn backfill_status(_admin: Admin, gap_backfill: &State<GapBackfill>) -> Json<BackfillStatus> {
+    Json(gap_backfill.status())
+}
+
+pub fn stage() -> rocket::fairing::AdHoc {
+    rocket::fairing::AdHoc::on_ignite("Health Stage", |rocket| async {
+        println!("Health stage on ignite!");
+
+        let config: GapConfig = rocket.figment().extract_inner("gaps").unwrap_or_default();
+        let health_config: HealthConfig =
+            rocket.figment().extract_inner("health").unwrap_or_default();
+
+        rocket
+            .manage(config)
+            .manage(health_config)
+            .manage(GapBackfill::default())
+            .mount(
+                "/health",
+                rocket::routes![
+                    live,
+                    ready,
+                    indexer,
+                    rpc,
+                    get_gaps,
+                    backfill,
+                    backfill_status
+                ],
+            )
+            .attach(rocket::fairing::AdHoc::on_liftoff(
+                "Id gap monitor",
+                |rocket| {
+                    Box::pin(async move {
+                        let config = rocket.state::<GapConfig>().cloned();
+                        let rpc_service = rocket.state::<RpcService>().cloned();
+                        match (DB::fetch(rocket), config, rpc_service) {
+                            (Some(db), Some(config), Some(rpc_service))
+                                if config.interval_secs > 0 =>
+                            {
+                                tokio::spawn(run_gap_monitor(db.clone(), rpc_service, config));
+                            }
+                            _ => {}
+                        }
+                    })
+                },
+            ))
+    })
+}
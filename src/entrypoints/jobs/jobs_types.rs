@@ -0,0 +1,13 @@
+use crate::jobs::types::JobKind;
+use rocket::serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+pub const DEFAULT_JOBS_LIMIT: i64 = 20;
+pub const MAX_JOBS_LIMIT: i64 = 100;
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct CreateJob {
+    pub kind: JobKind,
+    /// Stop after about this many transactions; the next `continue_sync` resumes from there
+    pub max_transactions: Option<i64>,
+}
@@ -0,0 +1,142 @@
+use self::jobs_types::*;
+use crate::auth::Admin;
+use crate::db::db_types::Job;
+use crate::db::DB;
+use crate::jobs::{enqueue_job, resume_interrupted_jobs};
+use crate::nearblocks_client::ApiClient;
+use crate::rpc_service::RpcService;
+use near_account_id::AccountId;
+use rocket::response::status::Accepted;
+use rocket::serde::json::Json;
+use rocket::{get, http::Status, post, State};
+use rocket_db_pools::Database;
+pub mod jobs_types;
+
+#[utoipa::path(post, path = "/admin/jobs", request_body = CreateJob)]
+#[post("/", data = "<job>", format = "application/json")]
+async fn create_job(
+    _admin: Admin,
+    job: Json<CreateJob>,
+    db: &State<DB>,
+    rpc_service: &State<RpcService>,
+    nearblocks_client: &State<ApiClient>,
+    contract: &State<AccountId>,
+) -> Result<Accepted<Json<Job>>, (Status, String)> {
+    match enqueue_job(
+        db,
+        rpc_service,
+        nearblocks_client,
+        job.kind,
+        contract.as_str(),
+        job.max_transactions,
+    )
+    .await
+    {
+        Ok(Some(job)) => Ok(Accepted(Json(job))),
+        Ok(None) => Err((
+            Status::Conflict,
+            format!(
+                "A job for {} is already queued or running",
+                contract.inner()
+            ),
+        )),
+        Err(e) => {
+            eprintln!("Failed to create job: {:?}", e);
+            Err((Status::InternalServerError, "".to_string()))
+        }
+    }
+}
+
+#[utoipa::path(get, path = "/admin/jobs?<limit>", params(
+  ("limit"= i64, Path, description = "default limit 20, at most 100"),
+))]
+#[get("/?<limit>")]
+async fn get_jobs(
+    _admin: Admin,
+    limit: Option<i64>,
+    db: &State<DB>,
+) -> Result<Json<Vec<Job>>, Status> {
+    let limit = limit.unwrap_or(DEFAULT_JOBS_LIMIT).clamp(1, MAX_JOBS_LIMIT);
+
+    match db.get_jobs(limit).await {
+        Ok(jobs) => Ok(Json(jobs)),
+        Err(e) => {
+            eprintln!("Failed to get jobs: {:?}", e);
+            Err(Status::InternalServerError)
+        }
+    }
+}
+
+#[utoipa::path(get, path = "/admin/jobs/{job_id}")]
+#[get("/<job_id>")]
+async fn get_job(_admin: Admin, job_id: i32, db: &State<DB>) -> Result<Json<Job>, Status> {
+    match db.get_job(job_id).await {
+        Ok(Some(job)) => Ok(Json(job)),
+        Ok(None) => Err(Status::NotFound),
+        Err(e) => {
+            eprintln!("Failed to get job: {:?}", e);
+            Err(Status::InternalServerError)
+        }
+    }
+}
+
+/// A running job stops after the page it is processing.
+#[utoipa::path(post, path = "/admin/jobs/{job_id}/cancel")]
+#[post("/<job_id>/cancel")]
+async fn cancel_job(
+    _admin: Admin,
+    job_id: i32,
+    db: &State<DB>,
+) -> Result<Accepted<Json<Job>>, (Status, String)> {
+    match db.request_job_cancel(job_id).await {
+        Ok(Some(job)) => Ok(Accepted(Json(job))),
+        Ok(None) => match db.get_job(job_id).await {
+            Ok(Some(job)) => Err((
+                Status::Conflict,
+                format!("Job {} is already {}", job.id, job.status),
+            )),
+            Ok(None) => Err((Status::NotFound, "".to_string())),
+            Err(e) => {
+                eprintln!("Failed to get job: {:?}", e);
+                Err((Status::InternalServerError, "".to_string()))
+            }
+        },
+        Err(e) => {
+            eprintln!("Failed to cancel job: {:?}", e);
+            Err((Status::InternalServerError, "".to_string()))
+        }
+    }
+}
+
+pub fn stage() -> rocket::fairing::AdHoc {
+    rocket::fairing::AdHoc::on_ignite("Jobs Stage", |rocket| async {
+        println!("Jobs stage on ignite!");
+
+        rocket
+            .mount(
+                "/admin/jobs",
+                rocket::routes![create_job, get_jobs, get_job, cancel_job],
+            )
+            .attach(rocket::fairing::AdHoc::on_liftoff(
+                "Resume interrupted jobs",
+                |rocket| {
+                    Box::pin(async move {
+                        let (Some(db), Some(rpc_service), Some(nearblocks_client), Some(contract)) = (
+                            DB::fetch(rocket),
+                            rocket.state::<RpcService>(),
+                            rocket.state::<ApiClient>(),
+                            rocket.state::<AccountId>(),
+                        ) else {
+                            return;
+                        };
+                        tokio::spawn(resume_interrupted_jobs(
+                            db.clone(),
+                            rpc_service.clone(),
+                            nearblocks_client.clone(),
+                            contract.to_string(),
+                        ));
+                    })
+                },
+            ))
+    })
+}
@@ -0,0 +1,53 @@
+use crate::db::db_types::{ProposalWithLatestSnapshotView, RfpWithLatestSnapshotView};
+use rocket::serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum EntityKind {
+    Proposal,
+    Rfp,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum LinkIssue {
+    /// The link points at an id that isn't in the cache.
+    MissingTarget,
+    /// The target exists but doesn't link back to the source.
+    MissingBacklink,
+}
+
+/// A link from `from_kind`/`from_id` to `to_kind`/`to_id` that the two sides disagree on.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct LinkInconsistency {
+    pub from_kind: EntityKind,
+    pub from_id: i32,
+    pub to_kind: EntityKind,
+    pub to_id: i32,
+    pub issue: LinkIssue,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct ProposalLinks {
+    pub proposal: ProposalWithLatestSnapshotView,
+    /// Proposals listed in this proposal's `linked_proposals`.
+    pub linked_proposals: Vec<ProposalWithLatestSnapshotView>,
+    /// The RFP in this proposal's `linked_rfp`.
+    pub linked_rfp: Option<RfpWithLatestSnapshotView>,
+    /// Proposals that list this proposal in their `linked_proposals`.
+    pub linked_from_proposals: Vec<ProposalWithLatestSnapshotView>,
+    /// RFPs that list this proposal in their `linked_proposals`.
+    pub linked_from_rfps: Vec<RfpWithLatestSnapshotView>,
+    pub inconsistencies: Vec<LinkInconsistency>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct RfpLinks {
+    pub rfp: RfpWithLatestSnapshotView,
+    /// Proposals listed in this RFP's `linked_proposals`.
+    pub linked_proposals: Vec<ProposalWithLatestSnapshotView>,
+    /// Proposals whose `linked_rfp` is this RFP.
+    pub linked_from_proposals: Vec<ProposalWithLatestSnapshotView>,
+    pub inconsistencies: Vec<LinkInconsistency>,
+}
@@ -0,0 +1,154 @@
+use self::links_types::*;
+use crate::db::db_types::{ProposalWithLatestSnapshotView, RfpWithLatestSnapshotView};
+use crate::db::DB;
+use crate::nearblocks_client::types::LinkedProposals;
+pub mod links_types;
+
+// Resolves both directions of a proposal's links. Proposal to proposal links are
+// one-sided by design, so only proposal <-> RFP links are checked for a backlink.
+pub async fn resolve_proposal_links(
+    db: &DB,
+    proposal_id: i32,
+) -> anyhow::Result<Option<ProposalLinks>> {
+    let Some(proposal) = db
+        .get_proposal_with_latest_snapshot_view(proposal_id)
+        .await?
+    else {
+        return Ok(None);
+    };
+
+    let LinkedProposals(linked_ids) = LinkedProposals::from(proposal.linked_proposals.clone());
+    let linked_proposals = proposals_in_order(
+        &linked_ids,
+        db.get_proposals_with_latest_snapshot_by_ids(&linked_ids)
+            .await?,
+    );
+    let linked_rfp = match proposal.linked_rfp {
+        Some(rfp_id) => db
+            .get_rfps_with_latest_snapshot_by_ids(&[rfp_id])
+            .await?
+            .pop(),
+        None => None,
+    };
+    let linked_from_proposals = db.get_proposals_linking_to_proposal(proposal_id).await?;
+    let linked_from_rfps = db.get_rfps_linking_to_proposal(proposal_id).await?;
+
+    let mut inconsistencies = vec![];
+    for id in &linked_ids {
+        if !linked_proposals.iter().any(|p| p.proposal_id == *id) {
+            inconsistencies.push(LinkInconsistency {
+                from_kind: EntityKind::Proposal,
+                from_id: proposal_id,
+                to_kind: EntityKind::Proposal,
+                to_id: *id,
+                issue: LinkIssue::MissingTarget,
+            });
+        }
+    }
+    if let Some(rfp_id) = proposal.linked_rfp {
+        let issue = match &linked_rfp {
+            None => Some(LinkIssue::MissingTarget),
+            Some(rfp) if !rfp_links_to(rfp, proposal_id) => Some(LinkIssue::MissingBacklink),
+            Some(_) => None,
+        };
+        if let Some(issue) = issue {
+            inconsistencies.push(LinkInconsistency {
+                from_kind: EntityKind::Proposal,
+                from_id: proposal_id,
+                to_kind: EntityKind::Rfp,
+                to_id: rfp_id,
+                issue,
+            });
+        }
+    }
+    for rfp in &linked_from_rfps {
+        if proposal.linked_rfp != Some(rfp.rfp_id) {
+            inconsistencies.push(LinkInconsistency {
+                from_kind: EntityKind::Rfp,
+                from_id: rfp.rfp_id,
+                to_kind: EntityKind::Proposal,
+                to_id: proposal_id,
+                issue: LinkIssue::MissingBacklink,
+            });
+        }
+    }
+
+    Ok(Some(ProposalLinks {
+        proposal,
+        linked_proposals,
+        linked_rfp,
+        linked_from_proposals,
+        linked_from_rfps,
+        inconsistencies,
+    }))
+}
+
+pub async fn resolve_rfp_links(db: &DB, rfp_id: i32) -> anyhow::Result<Option<RfpLinks>> {
+    let Some(rfp) = db
+        .get_rfps_with_latest_snapshot_by_ids(&[rfp_id])
+        .await?
+        .pop()
+    else {
+        return Ok(None);
+    };
+
+    let LinkedProposals(linked_ids) = LinkedProposals::from(rfp.linked_proposals.clone());
+    let linked_proposals = proposals_in_order(
+        &linked_ids,
+        db.get_proposals_with_latest_snapshot_by_ids(&linked_ids)
+            .await?,
+    );
+    let linked_from_proposals = db.get_proposals_linking_to_rfp(rfp_id).await?;
+
+    let mut inconsistencies = vec![];
+    for id in &linked_ids {
+        let issue = match linked_proposals.iter().find(|p| p.proposal_id == *id) {
+            None => Some(LinkIssue::MissingTarget),
+            Some(proposal) if proposal.linked_rfp != Some(rfp_id) => {
+                Some(LinkIssue::MissingBacklink)
+            }
+            Some(_) => None,
+        };
+        if let Some(issue) = issue {
+            inconsistencies.push(LinkInconsistency {
+                from_kind: EntityKind::Rfp,
+                from_id: rfp_id,
+                to_kind: EntityKind::Proposal,
+                to_id: *id,
+                issue,
+            });
+        }
+    }
+    for proposal in &linked_from_proposals {
+        if !linked_ids.contains(&proposal.proposal_id) {
+            inconsistencies.push(LinkInconsistency {
+                from_kind: EntityKind::Proposal,
+                from_id: proposal.proposal_id,
+                to_kind: EntityKind::Rfp,
+                to_id: rfp_id,
+                issue: LinkIssue::MissingBacklink,
+            });
+        }
+    }
+
+    Ok(Some(RfpLinks {
+        rfp,
+        linked_proposals,
+        linked_from_proposals,
+        inconsistencies,
+    }))
+}
+
+fn rfp_links_to(rfp: &RfpWithLatestSnapshotView, proposal_id: i32) -> bool {
+    let LinkedProposals(ids) = LinkedProposals::from(rfp.linked_proposals.clone());
+    ids.contains(&proposal_id)
+}
+
+// Keep the order the links were stored in; ids that aren't cached are dropped
+fn proposals_in_order(
+    ids: &[i32],
+    mut proposals: Vec<ProposalWithLatestSnapshotView>,
+) -> Vec<ProposalWithLatestSnapshotView> {
+    proposals.sort_by_key(|p| ids.iter().position(|id| *id == p.proposal_id));
+    proposals
+}
@@ -1,7 +1,19 @@
 use rocket::fairing::AdHoc;
 use utoipa::OpenApi;
+pub mod accounts;
+pub mod cache;
+pub mod events;
+pub mod export;
+pub mod feed;
+pub mod graphql;
+pub mod health;
+pub mod jobs;
+pub mod links;
 pub mod proposal;
+pub mod reconciliation;
 pub mod rfp;
+pub mod stats;
+pub mod webhooks;
 use crate::db::db_types::ProposalWithLatestSnapshotView;
 use crate::types::PaginatedResponse;
 #[derive(OpenApi)]
@@ -26,6 +38,17 @@ pub struct ApiDoc;
 
 pub fn stage() -> AdHoc {
     AdHoc::on_ignite("Installing entrypoints", |rocket| async {
-        rocket.attach(proposal::stage()).attach(rfp::stage())
+        rocket
+            .attach(proposal::stage())
+            .attach(rfp::stage())
+            .attach(graphql::stage())
+            .attach(events::stage())
+            .attach(webhooks::stage())
+            .attach(stats::stage())
+            .attach(accounts::stage())
+            .attach(reconciliation::stage())
+            .attach(health::stage())
+            .attach(jobs::stage())
+            .attach(cache::stage())
     })
 }
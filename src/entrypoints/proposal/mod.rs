@@ -1,17 +1,40 @@
 use self::proposal_types::*;
+use crate::auth::Admin;
 use crate::changelog::fetch_changelog_from_rpc;
 use crate::db::db_types::{
-    LastUpdatedInfo, ProposalSnapshotRecord, ProposalWithLatestSnapshotView,
+    Job, LastUpdatedInfo, ProposalSnapshotRecord, ProposalWithLatestSnapshotView, TaxonomyCount,
+    Timestamp,
 };
 use crate::db::DB;
-use crate::nearblocks_client::transactions::update_nearblocks_data;
+use crate::entrypoints::cache::cache_types::{CachedJson, IfNoneMatch};
+use crate::entrypoints::cache::{invalidate, CacheKey, ResponseCache};
+use crate::entrypoints::export::export_types::PROPOSAL_COLUMNS;
+use crate::entrypoints::export::{
+    flatten_row, negotiate_format, select_columns, Export, ExportEncoder,
+};
+use crate::entrypoints::feed::feed_types::{Feed, PROPOSAL_FEED};
+use crate::entrypoints::feed::{
+    atom_content_type, feed_from_entries, render_atom, render_rss, rss_content_type,
+    DEFAULT_FEED_LIMIT, MAX_FEED_LIMIT,
+};
+use crate::entrypoints::links::links_types::ProposalLinks;
+use crate::entrypoints::links::resolve_proposal_links;
+use crate::jobs::enqueue_job;
+use crate::jobs::types::JobKind;
+use crate::nearblocks_client::ApiClient;
 use crate::rpc_service::RpcService;
 use crate::separate_number_and_text;
-use crate::types::PaginatedResponse;
+use crate::types::{PaginatedResponse, TaxonomyKind};
 use devhub_shared::proposal::VersionedProposal;
-use rocket::delete;
+use near_account_id::AccountId;
+use rocket::futures::{Stream, StreamExt};
+use rocket::http::{Accept, ContentType};
+use rocket::response::status::Accepted;
+use rocket::response::stream::ByteStream;
 use rocket::serde::json::Json;
+use rocket::{delete, post};
 use rocket::{get, http::Status, State};
+use rocket_db_pools::Database;
 use std::convert::TryInto;
 pub mod proposal_types;
 
@@ -72,42 +95,66 @@ async fn fetch_proposals(
 }
 
 #[utoipa::path(get, path = "/proposals?<order>&<limit>&<offset>&<filters>", params(
-  ("order"= &str, Path, description ="default order id_desc (ts_asc)"),
+  ("order"= &str, Path, description ="default order id_desc (ts_asc, ts_desc, id_asc, views_desc)"),
   ("limit"= i64, Path, description = "default limit 10"),
   ("offset"= i64, Path, description = "offset"),
   ("filters"= GetProposalFilters, Path, description = "filters struct that contains stuff like category, labels (vec), author_id, stage, block_timestamp (i64)"),
 ))]
-#[get("/?<order>&<limit>&<offset>&<filters>")]
+#[get("/?<query..>")]
 async fn get_proposals(
-    order: Option<&str>,
-    limit: Option<i64>,
-    offset: Option<i64>,
-    filters: Option<GetProposalFilters>,
+    query: ProposalListQuery,
     db: &State<DB>,
     rpc_service: &State<RpcService>,
-) -> Option<Json<PaginatedResponse<ProposalWithLatestSnapshotView>>> {
-    let order = order.unwrap_or("id_desc");
-    let limit = limit.unwrap_or(10);
-    let offset = offset.unwrap_or(0);
-
+    cache: &State<ResponseCache>,
+    if_none_match: IfNoneMatch,
+) -> Option<CachedJson> {
+    // Requests drive ingestion, so cache hits must not skip it
     let last_updated_info = db.get_last_updated_info().await.unwrap();
-
     let change_log_count = fetch_changelog_from_rpc(
         db.inner(),
         rpc_service.inner(),
         Some(last_updated_info.after_block),
     )
-    .await;
+    .await
+    .unwrap_or(0);
 
-    let (proposals, total) = fetch_proposals(db.inner(), limit, order, offset, filters).await;
+    let order = query.order.as_deref().unwrap_or("id_desc");
+    let limit = query.limit.unwrap_or(10);
+    let offset = query.offset.unwrap_or(0);
+    let filters = query.filters;
+
+    // The change count is part of the body, so it is part of the key as well
+    let key = CacheKey::new("/proposals")
+        .param("order", order)
+        .param("limit", limit)
+        .param("offset", offset)
+        .param("changes", change_log_count);
+    let key = match &filters {
+        Some(filters) => key
+            .param("category", &filters.category)
+            .list("labels", filters.labels.as_deref())
+            .param("input", &filters.input)
+            .param("author_id", &filters.author_id)
+            .param("stage", &filters.stage)
+            .param("block_timestamp", filters.block_timestamp),
+        None => key,
+    };
+    cache
+        .get_or_compute(key.build(), &if_none_match, || async {
+            let (proposals, total) =
+                fetch_proposals(db.inner(), limit, order, offset, filters).await;
 
-    Some(Json(PaginatedResponse::new(
-        proposals.into_iter().collect(),
-        1,
-        limit.try_into().unwrap(),
-        total.try_into().unwrap(),
-        Some(change_log_count.unwrap_or(0)),
-    )))
+            Some(PaginatedResponse::new(
+                proposals
+                    .into_iter()
+                    .collect::<Vec<ProposalWithLatestSnapshotView>>(),
+                1,
+                limit.try_into().unwrap(),
+                total.try_into().unwrap(),
+                Some(change_log_count),
+            ))
+        })
+        .await
 }
 
 #[utoipa::path(get, path = "/proposal/{proposal_id}/snapshots")]
@@ -136,6 +183,32 @@ async fn get_proposal_with_all_snapshots(
     }
 }
 
+#[utoipa::path(get, path = "/proposal/{proposal_id}/links")]
+#[get("/<proposal_id>/links")]
+async fn get_proposal_links(
+    proposal_id: i32,
+    db: &State<DB>,
+    rpc_service: &State<RpcService>,
+) -> Result<Json<ProposalLinks>, Status> {
+    let last_updated_info = db.get_last_updated_info().await.unwrap();
+
+    let _ = fetch_changelog_from_rpc(
+        db.inner(),
+        rpc_service.inner(),
+        Some(last_updated_info.after_block),
+    )
+    .await;
+
+    match resolve_proposal_links(db.inner(), proposal_id).await {
+        Ok(Some(links)) => Ok(Json(links)),
+        Ok(None) => Err(Status::NotFound),
+        Err(e) => {
+            eprintln!("Failed to resolve proposal links: {:?}", e);
+            Err(Status::InternalServerError)
+        }
+    }
+}
+
 #[get("/info/cursor/<cursor>")]
 async fn set_cursor(cursor: &str, db: &State<DB>) -> Result<(), Status> {
     match db.set_last_updated_cursor(cursor.to_string()).await {
@@ -172,7 +245,10 @@ async fn set_block(block: i64, db: &State<DB>) -> Result<(), Status> {
 #[get("/info/reset")]
 async fn reset(db: &State<DB>) -> Result<(), Status> {
     match db.set_last_updated_info(0, 0, "".to_string()).await {
-        Ok(()) => Ok(()),
+        Ok(()) => {
+            invalidate();
+            Ok(())
+        }
         Err(e) => {
             eprintln!("Error updating timestamp: {:?}", e);
             Err(Status::InternalServerError)
@@ -183,43 +259,91 @@ async fn reset(db: &State<DB>) -> Result<(), Status> {
 #[utoipa::path(get, path = "/proposals/sync_from_start")]
 #[get("/sync_from_start/<max_transactions>")]
 async fn sync_from_start(
-    max_transactions: Option<usize>,
+    _admin: Admin,
+    max_transactions: Option<i64>,
     db: &State<DB>,
     rpc_service: &State<RpcService>,
-) -> Result<String, Status> {
-    let result = update_nearblocks_data(db, rpc_service, Some(0), max_transactions).await;
-
-    match result {
-        Ok(_) => Ok("Success".to_string()),
-        Err(e) => {
-            eprintln!("Error syncing from start: {:?}", e);
-            Err(Status::InternalServerError)
-        }
-    }
+    nearblocks_client: &State<ApiClient>,
+    contract: &State<AccountId>,
+) -> Result<Accepted<Json<Job>>, (Status, String)> {
+    queue_sync(
+        JobKind::SyncFromStart,
+        max_transactions,
+        db,
+        rpc_service,
+        nearblocks_client,
+        contract,
+    )
+    .await
 }
 
 #[utoipa::path(get, path = "/proposals/continue_sync")]
 #[get("/continue_sync/<max_transactions>")]
 async fn continue_sync(
-    max_transactions: Option<usize>,
+    _admin: Admin,
+    max_transactions: Option<i64>,
     db: &State<DB>,
     rpc_service: &State<RpcService>,
-) -> Result<String, Status> {
-    let last_updated_info = db.get_last_updated_info().await.unwrap();
-    let result = update_nearblocks_data(
+    nearblocks_client: &State<ApiClient>,
+    contract: &State<AccountId>,
+) -> Result<Accepted<Json<Job>>, (Status, String)> {
+    // Resumes an unfinished sync from its cursor, otherwise starts after the last updated block
+    queue_sync(
+        JobKind::ContinueSync,
+        max_transactions,
         db,
         rpc_service,
-        Some(last_updated_info.after_block),
-        max_transactions,
+        nearblocks_client,
+        contract,
     )
-    .await;
+    .await
+}
 
-    match result {
-        Ok(_) => Ok("Success".to_string()),
+// Syncs run as jobs, so they outlive the request and only one runs per contract
+async fn queue_sync(
+    kind: JobKind,
+    max_transactions: Option<i64>,
+    db: &State<DB>,
+    rpc_service: &State<RpcService>,
+    nearblocks_client: &State<ApiClient>,
+    contract: &State<AccountId>,
+) -> Result<Accepted<Json<Job>>, (Status, String)> {
+    match enqueue_job(
+        db,
+        rpc_service,
+        nearblocks_client,
+        kind,
+        contract.as_str(),
+        max_transactions,
+    )
+    .await
+    {
+        Ok(Some(job)) => Ok(Accepted(Json(job))),
+        Ok(None) => Err((
+            Status::Conflict,
+            format!(
+                "A job for {} is already queued or running",
+                contract.inner()
+            ),
+        )),
         Err(e) => {
-            eprintln!("Error syncing from start: {:?}", e);
-            Err(Status::InternalServerError)
+            eprintln!("Failed to queue {} job: {:?}", kind.as_str(), e);
+            Err((Status::InternalServerError, "".to_string()))
+        }
+    }
+}
+
+// Clients only matter within the dedup window, drop them once it has passed
+async fn prune_view_clients(db: DB, config: ViewsConfig) {
+    let interval = std::time::Duration::from_secs(config.dedup_window_secs.max(60) as u64);
+    loop {
+        if let Err(e) = db
+            .prune_proposal_view_clients(config.dedup_window_secs)
+            .await
+        {
+            eprintln!("Failed to prune proposal view clients: {:?}", e);
         }
+        tokio::time::sleep(interval).await;
     }
 }
 
@@ -234,13 +358,15 @@ async fn clean(db: &State<DB>) -> Result<(), Status> {
         }
     };
 
-    match db.remove_all_data().await {
+    let result = match db.remove_all_data().await {
         Ok(()) => Ok(()),
         Err(e) => {
             eprintln!("Error cleaning data: {:?}", e);
             Err(Status::InternalServerError)
         }
-    }
+    };
+    invalidate();
+    result
 }
 
 #[get("/info")]
@@ -249,6 +375,189 @@ async fn get_timestamp(db: &State<DB>) -> Result<Json<LastUpdatedInfo>, Status>
     Ok(Json(info))
 }
 
+async fn proposal_feed(
+    db: &State<DB>,
+    rpc_service: &State<RpcService>,
+    contract: &State<AccountId>,
+    limit: Option<i64>,
+    filters: Option<GetProposalFilters>,
+) -> Result<Feed, Status> {
+    let last_updated_info = db.get_last_updated_info().await.unwrap();
+
+    let _ = fetch_changelog_from_rpc(
+        db.inner(),
+        rpc_service.inner(),
+        Some(last_updated_info.after_block),
+    )
+    .await;
+
+    let limit = limit.unwrap_or(DEFAULT_FEED_LIMIT).clamp(1, MAX_FEED_LIMIT);
+    match db.get_proposal_feed_entries(filters, limit).await {
+        Ok(entries) => Ok(feed_from_entries(&PROPOSAL_FEED, contract.inner(), entries)),
+        Err(e) => {
+            eprintln!("Failed to get proposal feed entries: {:?}", e);
+            Err(Status::InternalServerError)
+        }
+    }
+}
+
+#[utoipa::path(get, path = "/proposals/feed.atom?<limit>&<filters>", params(
+  ("limit"= i64, Path, description = "default limit 50, max 200"),
+  ("filters"= GetProposalFilters, Path, description = "same filters as /proposals"),
+))]
+#[get("/feed.atom?<limit>&<filters>")]
+async fn get_proposals_atom_feed(
+    limit: Option<i64>,
+    filters: Option<GetProposalFilters>,
+    db: &State<DB>,
+    rpc_service: &State<RpcService>,
+    contract: &State<AccountId>,
+) -> Result<(ContentType, String), Status> {
+    let feed = proposal_feed(db, rpc_service, contract, limit, filters).await?;
+    Ok((atom_content_type(), render_atom(&feed)))
+}
+
+#[utoipa::path(get, path = "/proposals/feed.rss?<limit>&<filters>", params(
+  ("limit"= i64, Path, description = "default limit 50, max 200"),
+  ("filters"= GetProposalFilters, Path, description = "same filters as /proposals"),
+))]
+#[get("/feed.rss?<limit>&<filters>")]
+async fn get_proposals_rss_feed(
+    limit: Option<i64>,
+    filters: Option<GetProposalFilters>,
+    db: &State<DB>,
+    rpc_service: &State<RpcService>,
+    contract: &State<AccountId>,
+) -> Result<(ContentType, String), Status> {
+    let feed = proposal_feed(db, rpc_service, contract, limit, filters).await?;
+    Ok((rss_content_type(), render_rss(&feed)))
+}
+
+async fn proposal_taxonomy(
+    db: &State<DB>,
+    rpc_service: &State<RpcService>,
+    kind: TaxonomyKind,
+    stage: Option<&str>,
+    from: Option<Timestamp>,
+    to: Option<Timestamp>,
+) -> Result<Json<Vec<TaxonomyCount>>, Status> {
+    let last_updated_info = db.get_last_updated_info().await.unwrap();
+
+    let _ = fetch_changelog_from_rpc(
+        db.inner(),
+        rpc_service.inner(),
+        Some(last_updated_info.after_block),
+    )
+    .await;
+
+    match db.get_proposal_taxonomy(kind, stage, from, to).await {
+        Ok(counts) => Ok(Json(counts)),
+        Err(e) => {
+            eprintln!("Failed to get proposal {:?}: {:?}", kind, e);
+            Err(Status::InternalServerError)
+        }
+    }
+}
+
+#[utoipa::path(get, path = "/proposals/labels?<stage>&<from>&<to>", params(
+  ("stage"= &str, Path, description = "only proposals in this stage"),
+  ("from"= i64, Path, description = "only proposals last updated at or after this timestamp (nanoseconds)"),
+  ("to"= i64, Path, description = "only proposals last updated before this timestamp (nanoseconds)"),
+))]
+#[get("/labels?<stage>&<from>&<to>")]
+async fn get_proposals_labels(
+    stage: Option<&str>,
+    from: Option<Timestamp>,
+    to: Option<Timestamp>,
+    db: &State<DB>,
+    rpc_service: &State<RpcService>,
+) -> Result<Json<Vec<TaxonomyCount>>, Status> {
+    proposal_taxonomy(db, rpc_service, TaxonomyKind::Labels, stage, from, to).await
+}
+
+#[utoipa::path(get, path = "/proposals/categories?<stage>&<from>&<to>", params(
+  ("stage"= &str, Path, description = "only proposals in this stage"),
+  ("from"= i64, Path, description = "only proposals last updated at or after this timestamp (nanoseconds)"),
+  ("to"= i64, Path, description = "only proposals last updated before this timestamp (nanoseconds)"),
+))]
+#[get("/categories?<stage>&<from>&<to>")]
+async fn get_proposals_categories(
+    stage: Option<&str>,
+    from: Option<Timestamp>,
+    to: Option<Timestamp>,
+    db: &State<DB>,
+    rpc_service: &State<RpcService>,
+) -> Result<Json<Vec<TaxonomyCount>>, Status> {
+    proposal_taxonomy(db, rpc_service, TaxonomyKind::Categories, stage, from, to).await
+}
+
+#[utoipa::path(get, path = "/proposals/export?<format>&<columns>&<all_snapshots>&<filters>", params(
+  ("format"= &str, Path, description = "csv, ndjson or parquet; falls back to the Accept header, then csv"),
+  ("columns"= &str, Path, description = "comma separated columns, default all"),
+  ("all_snapshots"= bool, Path, description = "export every snapshot instead of only the latest"),
+  ("filters"= GetProposalFilters, Path, description = "same filters as /proposals"),
+))]
+#[get("/export?<format>&<columns>&<all_snapshots>&<filters>")]
+#[allow(clippy::too_many_arguments)]
+async fn export_proposals(
+    format: Option<&str>,
+    columns: Option<&str>,
+    all_snapshots: Option<bool>,
+    filters: Option<GetProposalFilters>,
+    accept: Option<&Accept>,
+    db: &State<DB>,
+    rpc_service: &State<RpcService>,
+) -> Result<Export<impl Stream<Item = Vec<u8>>>, (Status, String)> {
+    let format = negotiate_format(format, accept).map_err(|e| (Status::BadRequest, e))?;
+    let columns = select_columns(PROPOSAL_COLUMNS, columns).map_err(|e| (Status::BadRequest, e))?;
+    let mut encoder = ExportEncoder::new(format, columns).map_err(|e| {
+        eprintln!("Failed to create proposal export encoder: {:?}", e);
+        (
+            Status::InternalServerError,
+            "Failed to start export".to_string(),
+        )
+    })?;
+
+    let last_updated_info = db.get_last_updated_info().await.unwrap();
+
+    let _ = fetch_changelog_from_rpc(
+        db.inner(),
+        rpc_service.inner(),
+        Some(last_updated_info.after_block),
+    )
+    .await;
+
+    let db = db.inner().clone();
+    let stream = ByteStream! {
+        let mut rows = db.stream_proposals_for_export(filters, all_snapshots.unwrap_or(false));
+        while let Some(row) = rows.next().await {
+            let chunk = match row {
+                Ok(row) => encoder.push(flatten_row(&row)),
+                Err(e) => Err(e.into()),
+            };
+            match chunk {
+                Ok(Some(chunk)) => yield chunk,
+                Ok(None) => {}
+                Err(e) => {
+                    // Headers are already sent, so the download is cut short
+                    eprintln!("Failed to export proposals: {:?}", e);
+                    return;
+                }
+            }
+        }
+        match encoder.finish() {
+            Ok(chunk) => yield chunk,
+            Err(e) => eprintln!("Failed to finish proposal export: {:?}", e),
+        }
+    };
+
+    Ok(Export {
+        format,
+        name: "proposals",
+        stream,
+    })
+}
+
 #[utoipa::path(get, path = "/proposal/{proposal_id}")]
 #[get("/<proposal_id>")]
 async fn get_proposal(
@@ -265,11 +574,49 @@ async fn get_proposal(
     }
 }
 
+#[utoipa::path(post, path = "/proposal/{proposal_id}/view")]
+#[post("/<proposal_id>/view")]
+async fn record_proposal_view(
+    proposal_id: i32,
+    viewer: ViewerKey,
+    db: &State<DB>,
+    config: &State<ViewsConfig>,
+    rate_limiter: &State<ViewRateLimiter>,
+) -> Result<Json<ProposalViewCount>, Status> {
+    if !rate_limiter.allow(viewer.ip, config.max_views_per_ip_per_minute) {
+        return Err(Status::TooManyRequests);
+    }
+    match db
+        .record_proposal_view(proposal_id, &viewer.key, config.dedup_window_secs)
+        .await
+    {
+        Ok(Some((counted, views))) => {
+            // Lists show the count and can be ordered by it
+            if counted {
+                invalidate();
+            }
+            Ok(Json(ProposalViewCount {
+                proposal_id,
+                views,
+                counted,
+            }))
+        }
+        Ok(None) => Err(Status::NotFound),
+        Err(e) => {
+            eprintln!("Failed to record proposal view: {:?}", e);
+            Err(Status::InternalServerError)
+        }
+    }
+}
+
 // TODO Remove this once we go in production or put it behind authentication or a flag
 #[delete("/<proposal_id>/snapshots")]
 async fn remove_proposal_snapshots_by_id(proposal_id: i32, db: &State<DB>) -> Result<(), Status> {
     match db.remove_proposal_snapshots_by_id(proposal_id).await {
-        Ok(_) => Ok(()),
+        Ok(_) => {
+            invalidate();
+            Ok(())
+        }
         Err(e) => {
             eprintln!("Failed to remove proposal snapshots: {:?}", e);
             Err(Status::InternalServerError)
@@ -282,11 +629,20 @@ pub fn stage() -> rocket::fairing::AdHoc {
     rocket::fairing::AdHoc::on_ignite("Proposal Stage", |rocket| async {
         println!("Proposal stage on ignite!");
 
+        let views_config: ViewsConfig = rocket.figment().extract_inner("views").unwrap_or_default();
+
         rocket
+            .manage(views_config.clone())
+            .manage(ViewRateLimiter::default())
             .mount(
                 "/proposals/",
                 rocket::routes![
                     get_proposals,
+                    get_proposals_atom_feed,
+                    get_proposals_rss_feed,
+                    export_proposals,
+                    get_proposals_labels,
+                    get_proposals_categories,
                     set_timestamp,
                     get_timestamp,
                     search,
@@ -303,8 +659,23 @@ pub fn stage() -> rocket::fairing::AdHoc {
                 rocket::routes![
                     get_proposal,
                     get_proposal_with_all_snapshots,
+                    get_proposal_links,
+                    record_proposal_view,
                     remove_proposal_snapshots_by_id,
                 ],
             )
+            .attach(rocket::fairing::AdHoc::on_liftoff(
+                "Prune proposal view clients",
+                move |rocket| {
+                    Box::pin(async move {
+                        match DB::fetch(rocket) {
+                            Some(db) => {
+                                tokio::spawn(prune_view_clients(db.clone(), views_config));
+                            }
+                            None => eprintln!("Pruning proposal view clients needs the database"),
+                        }
+                    })
+                },
+            ))
     })
 }
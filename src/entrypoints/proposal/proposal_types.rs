@@ -1,16 +1,24 @@
 use crate::db::db_types::ProposalSnapshotRecord;
+use async_graphql::InputObject;
 use devhub_shared::proposal::{
     Proposal, ProposalFundingCurrency, ProposalId, VersionedProposalBody,
 };
 use near_sdk::near;
 use rocket::form::FromForm;
+use rocket::http::Status;
+use rocket::request::{self, FromRequest, Request};
 use rocket::serde::{Deserialize, Serialize};
-use std::collections::HashSet;
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::net::IpAddr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 use utoipa::ToSchema;
 // NOTE should this be VersionedProposal instead of Proposal?
 use devhub_shared::proposal::Proposal as ContractProposal;
 
-#[derive(Clone, Debug, FromForm, ToSchema)]
+#[derive(Clone, Debug, FromForm, ToSchema, InputObject)]
+#[graphql(name = "ProposalFilters")]
 pub struct GetProposalFilters {
     pub category: Option<String>,
     pub labels: Option<Vec<String>>,
@@ -20,6 +28,15 @@ pub struct GetProposalFilters {
     pub block_timestamp: Option<i64>,
 }
 
+/// Query parameters of `GET /proposals`.
+#[derive(Debug, FromForm)]
+pub struct ProposalListQuery {
+    pub order: Option<String>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+    pub filters: Option<GetProposalFilters>,
+}
+
 pub trait ProposalBodyFields {
     fn get_name(&self) -> &String;
     fn get_category(&self) -> &String;
@@ -213,6 +230,8 @@ impl FromContractProposal for ProposalSnapshotRecord {
                 proposal.snapshot.body.get_timeline().clone(),
             )),
             views: None,
+            state_block_height: None,
+            approximate: false,
         }
     }
 }
@@ -242,3 +261,82 @@ pub struct EditProposalArgs {
 pub struct PartialEditProposalArgs {
     pub id: i32,
 }
+
+/// Deduplication and per-IP limits of `POST /proposal/<id>/view`.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct ViewsConfig {
+    /// Repeat views from the same client within this window are not counted.
+    pub dedup_window_secs: i64,
+    /// Views accepted from one IP per minute, counted or not; more get a 429.
+    pub max_views_per_ip_per_minute: u32,
+}
+
+impl Default for ViewsConfig {
+    fn default() -> Self {
+        Self {
+            dedup_window_secs: 3600,
+            max_views_per_ip_per_minute: 30,
+        }
+    }
+}
+
+/// The client recording a view. Views are told apart by a hash of the client IP and
+/// user agent. The IP comes from `ip_header` in Rocket.toml, which names the header
+/// Fly's proxy sets (`Fly-Client-IP`), or from the connection when it's missing.
+/// Headers the client can set freely, like `X-Real-IP`, don't go into it.
+pub struct ViewerKey {
+    pub ip: IpAddr,
+    pub key: String,
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for ViewerKey {
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        let Some(ip) = request.client_ip() else {
+            return request::Outcome::Error((Status::BadRequest, ()));
+        };
+        let user_agent = request.headers().get_one("User-Agent").unwrap_or_default();
+        let key = hex::encode(Sha256::digest(format!("ip:{}|ua:{}", ip, user_agent)));
+        request::Outcome::Success(ViewerKey { ip, key })
+    }
+}
+
+const VIEW_RATE_WINDOW: Duration = Duration::from_secs(60);
+// Past this many tracked IPs, expired windows are dropped before adding another
+const VIEW_RATE_MAX_TRACKED: usize = 10_000;
+
+/// Views seen per IP in its current one-minute window.
+#[derive(Default)]
+pub struct ViewRateLimiter(Mutex<HashMap<IpAddr, (Instant, u32)>>);
+
+impl ViewRateLimiter {
+    /// Takes one view from `ip`'s allowance, or returns false when it's used up.
+    pub fn allow(&self, ip: IpAddr, per_minute: u32) -> bool {
+        let now = Instant::now();
+        let mut windows = self.0.lock().unwrap();
+        if windows.len() >= VIEW_RATE_MAX_TRACKED {
+            windows.retain(|_, (started, _)| now.duration_since(*started) < VIEW_RATE_WINDOW);
+        }
+        let (started, seen) = windows.entry(ip).or_insert((now, 0));
+        if now.duration_since(*started) >= VIEW_RATE_WINDOW {
+            *started = now;
+            *seen = 0;
+        }
+        if *seen >= per_minute {
+            return false;
+        }
+        *seen += 1;
+        true
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct ProposalViewCount {
+    pub proposal_id: i32,
+    pub views: i32,
+    /// False when this client was already counted within the dedup window
+    pub counted: bool,
+}
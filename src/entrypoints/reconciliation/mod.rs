@@ -0,0 +1,129 @@
+use self::reconciliation_types::*;
+use crate::auth::Admin;
+use crate::db::DB;
+use crate::reconciliation::types::ReconciliationConfig;
+use crate::reconciliation::{execute_reconciliation_run, run_reconciliation_scheduler};
+use crate::rpc_service::RpcService;
+use rocket::response::status::Accepted;
+use rocket::serde::json::Json;
+use rocket::{get, http::Status, post, State};
+use rocket_db_pools::Database;
+pub mod reconciliation_types;
+
+#[utoipa::path(post, path = "/reconciliation/runs?<repair>", params(
+  ("repair"= bool, Path, description = "insert fresh snapshots for missing or mismatched entries"),
+))]
+#[post("/runs?<repair>")]
+async fn start_run(
+    _admin: Admin,
+    repair: Option<bool>,
+    db: &State<DB>,
+    rpc_service: &State<RpcService>,
+    config: &State<ReconciliationConfig>,
+) -> Result<Accepted<Json<ReconciliationRunReport>>, (Status, String)> {
+    let repair = repair.unwrap_or(false);
+    let run = match db.create_reconciliation_run("admin", repair).await {
+        Ok(Some(run)) => run,
+        Ok(None) => {
+            return Err((
+                Status::Conflict,
+                "A reconciliation run is already in progress".to_string(),
+            ))
+        }
+        Err(e) => {
+            eprintln!("Failed to create reconciliation run: {:?}", e);
+            return Err((Status::InternalServerError, "".to_string()));
+        }
+    };
+
+    tokio::spawn(execute_reconciliation_run(
+        db.inner().clone(),
+        rpc_service.inner().clone(),
+        config.inner().clone(),
+        run.id,
+        repair,
+    ));
+    Ok(Accepted(Json(run.into())))
+}
+
+#[utoipa::path(get, path = "/reconciliation/runs?<limit>", params(
+  ("limit"= i64, Path, description = "default limit 20, at most 100"),
+))]
+#[get("/runs?<limit>")]
+async fn get_runs(
+    _admin: Admin,
+    limit: Option<i64>,
+    db: &State<DB>,
+) -> Result<Json<Vec<ReconciliationRunReport>>, Status> {
+    let limit = limit.unwrap_or(DEFAULT_RUNS_LIMIT).clamp(1, MAX_RUNS_LIMIT);
+
+    match db.get_reconciliation_runs(limit).await {
+        Ok(runs) => Ok(Json(runs.into_iter().map(Into::into).collect())),
+        Err(e) => {
+            eprintln!("Failed to get reconciliation runs: {:?}", e);
+            Err(Status::InternalServerError)
+        }
+    }
+}
+
+#[utoipa::path(get, path = "/reconciliation/runs/{run_id}")]
+#[get("/runs/<run_id>")]
+async fn get_run(
+    _admin: Admin,
+    run_id: i32,
+    db: &State<DB>,
+) -> Result<Json<ReconciliationRunReport>, Status> {
+    match db.get_reconciliation_run(run_id).await {
+        Ok(Some(run)) => Ok(Json(run.into())),
+        Ok(None) => Err(Status::NotFound),
+        Err(e) => {
+            eprintln!("Failed to get reconciliation run: {:?}", e);
+            Err(Status::InternalServerError)
+        }
+    }
+}
+
+pub fn stage() -> rocket::fairing::AdHoc {
+    rocket::fairing::AdHoc::on_ignite("Reconciliation Stage", |rocket| async {
+        println!("Reconciliation stage on ignite!");
+
+        let config: ReconciliationConfig = rocket
+            .figment()
+            .extract_inner("reconciliation")
+            .unwrap_or_default();
+
+        rocket
+            .manage(config)
+            .mount(
+                "/reconciliation",
+                rocket::routes![start_run, get_runs, get_run],
+            )
+            .attach(rocket::fairing::AdHoc::on_liftoff(
+                "Reconciliation scheduler",
+                |rocket| {
+                    Box::pin(async move {
+                        let Some(db) = DB::fetch(rocket) else {
+                            eprintln!("Reconciliation needs the database");
+                            return;
+                        };
+                        // A run still marked running was cut off by the previous shutdown
+                        if let Err(e) = db.fail_interrupted_reconciliation_runs().await {
+                            eprintln!("Failed to close interrupted reconciliation runs: {:?}", e);
+                        }
+
+                        let config = rocket.state::<ReconciliationConfig>().cloned();
+                        let rpc_service = rocket.state::<RpcService>().cloned();
+                        if let (Some(config), Some(rpc_service)) = (config, rpc_service) {
+                            if config.interval_secs > 0 {
+                                tokio::spawn(run_reconciliation_scheduler(
+                                    db.clone(),
+                                    rpc_service,
+                                    config,
+                                ));
+                            }
+                        }
+                    })
+                },
+            ))
+    })
+}
@@ -0,0 +1,40 @@
+use crate::db::db_types::ReconciliationRun;
+use crate::reconciliation::types::ReconciliationIssue;
+use rocket::serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+pub const DEFAULT_RUNS_LIMIT: i64 = 20;
+pub const MAX_RUNS_LIMIT: i64 = 100;
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct ReconciliationRunReport {
+    pub id: i32,
+    /// `admin` or `scheduled`
+    pub trigger: String,
+    pub repair: bool,
+    /// `running`, `completed` or `failed`
+    pub status: String,
+    pub checked_proposals: i32,
+    pub checked_rfps: i32,
+    pub issues: Vec<ReconciliationIssue>,
+    pub error: Option<String>,
+    pub started_at: chrono::DateTime<chrono::Utc>,
+    pub finished_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl From<ReconciliationRun> for ReconciliationRunReport {
+    fn from(run: ReconciliationRun) -> Self {
+        Self {
+            id: run.id,
+            trigger: run.trigger,
+            repair: run.repair,
+            status: run.status,
+            checked_proposals: run.checked_proposals,
+            checked_rfps: run.checked_rfps,
+            issues: serde_json::from_value(run.issues).unwrap_or_default(),
+            error: run.error,
+            started_at: run.started_at,
+            finished_at: run.finished_at,
+        }
+    }
+}
@@ -1,11 +1,28 @@
 use self::rfp_types::*;
 use crate::changelog::fetch_changelog_from_rpc;
-use crate::db::db_types::{RfpSnapshotRecord, RfpWithLatestSnapshotView};
+use crate::db::db_types::{RfpSnapshotRecord, RfpWithLatestSnapshotView, TaxonomyCount, Timestamp};
 use crate::db::DB;
+use crate::entrypoints::cache::cache_types::{CachedJson, IfNoneMatch};
+use crate::entrypoints::cache::{invalidate, CacheKey, ResponseCache};
+use crate::entrypoints::export::export_types::RFP_COLUMNS;
+use crate::entrypoints::export::{
+    flatten_row, negotiate_format, select_columns, Export, ExportEncoder,
+};
+use crate::entrypoints::feed::feed_types::{Feed, RFP_FEED};
+use crate::entrypoints::feed::{
+    atom_content_type, feed_from_entries, render_atom, render_rss, rss_content_type,
+    DEFAULT_FEED_LIMIT, MAX_FEED_LIMIT,
+};
+use crate::entrypoints::links::links_types::RfpLinks;
+use crate::entrypoints::links::resolve_rfp_links;
 use crate::rpc_service::RpcService;
 use crate::separate_number_and_text;
-use crate::types::PaginatedResponse;
+use crate::types::{PaginatedResponse, TaxonomyKind};
 use devhub_shared::rfp::VersionedRFP;
+use near_account_id::AccountId;
+use rocket::futures::{Stream, StreamExt};
+use rocket::http::{Accept, ContentType};
+use rocket::response::stream::ByteStream;
 use rocket::serde::json::Json;
 use rocket::{delete, get, http::Status, State};
 use std::convert::TryInto;
@@ -72,43 +89,250 @@ async fn fetch_rfps(
   ("offset"= i64, Path, description = "offset"),
   ("filters"= GetRfpFilters, Path, description = "filters struct that contains stuff like category, labels (vec), author_id, stage, block_timestamp (i64)"),
 ))]
-#[get("/?<order>&<limit>&<offset>&<filters>")]
+#[get("/?<query..>")]
 async fn get_rfps(
-    order: Option<&str>,
+    query: RfpListQuery,
+    db: &State<DB>,
+    rpc_service: &State<RpcService>,
+    cache: &State<ResponseCache>,
+    if_none_match: IfNoneMatch,
+) -> Option<CachedJson> {
+    // Requests drive ingestion, so cache hits must not skip it
+    let last_updated_info = db.get_last_updated_info().await.unwrap();
+    let change_log_count = fetch_changelog_from_rpc(
+        db.inner(),
+        rpc_service.inner(),
+        Some(last_updated_info.after_block),
+    )
+    .await
+    .unwrap_or(0);
+
+    let order = query.order.as_deref().unwrap_or("id_desc");
+    let limit = query.limit.unwrap_or(10);
+    let offset = query.offset.unwrap_or(0);
+    let filters = query.filters;
+
+    // The change count is part of the body, so it is part of the key as well
+    let key = CacheKey::new("/rfps")
+        .param("order", order)
+        .param("limit", limit)
+        .param("offset", offset)
+        .param("changes", change_log_count);
+    let key = match &filters {
+        Some(filters) => key
+            .param("category", &filters.category)
+            .list("labels", filters.labels.as_deref())
+            .param("input", &filters.input)
+            .param("author_id", &filters.author_id)
+            .param("stage", &filters.stage)
+            .param("block_timestamp", filters.block_timestamp),
+        None => key,
+    };
+    cache
+        .get_or_compute(key.build(), &if_none_match, || async {
+            let (rfps, total) = fetch_rfps(db, limit, order, offset, filters).await;
+
+            Some(PaginatedResponse::new(
+                rfps.into_iter().collect::<Vec<RfpWithLatestSnapshotView>>(),
+                1,
+                limit.try_into().unwrap(),
+                total.try_into().unwrap(),
+                Some(change_log_count),
+            ))
+        })
+        .await
+}
+
+async fn rfp_feed(
+    db: &State<DB>,
+    rpc_service: &State<RpcService>,
+    contract: &State<AccountId>,
+    limit: Option<i64>,
+    filters: Option<GetRfpFilters>,
+) -> Result<Feed, Status> {
+    let last_updated_info = db.get_last_updated_info().await.unwrap();
+
+    let _ = fetch_changelog_from_rpc(
+        db.inner(),
+        rpc_service.inner(),
+        Some(last_updated_info.after_block),
+    )
+    .await;
+
+    let limit = limit.unwrap_or(DEFAULT_FEED_LIMIT).clamp(1, MAX_FEED_LIMIT);
+    match db.get_rfp_feed_entries(filters, limit).await {
+        Ok(entries) => Ok(feed_from_entries(&RFP_FEED, contract.inner(), entries)),
+        Err(e) => {
+            eprintln!("Failed to get rfp feed entries: {:?}", e);
+            Err(Status::InternalServerError)
+        }
+    }
+}
+
+#[utoipa::path(get, path = "/rfps/feed.atom?<limit>&<filters>", params(
+  ("limit"= i64, Path, description = "default limit 50, max 200"),
+  ("filters"= GetRfpFilters, Path, description = "same filters as /rfps"),
+))]
+#[get("/feed.atom?<limit>&<filters>")]
+async fn get_rfps_atom_feed(
     limit: Option<i64>,
-    offset: Option<i64>,
     filters: Option<GetRfpFilters>,
     db: &State<DB>,
     rpc_service: &State<RpcService>,
-) -> Option<Json<PaginatedResponse<RfpWithLatestSnapshotView>>> {
-    let order = order.unwrap_or("id_desc");
-    let limit = limit.unwrap_or(10);
-    let offset = offset.unwrap_or(0);
+    contract: &State<AccountId>,
+) -> Result<(ContentType, String), Status> {
+    let feed = rfp_feed(db, rpc_service, contract, limit, filters).await?;
+    Ok((atom_content_type(), render_atom(&feed)))
+}
+
+#[utoipa::path(get, path = "/rfps/feed.rss?<limit>&<filters>", params(
+  ("limit"= i64, Path, description = "default limit 50, max 200"),
+  ("filters"= GetRfpFilters, Path, description = "same filters as /rfps"),
+))]
+#[get("/feed.rss?<limit>&<filters>")]
+async fn get_rfps_rss_feed(
+    limit: Option<i64>,
+    filters: Option<GetRfpFilters>,
+    db: &State<DB>,
+    rpc_service: &State<RpcService>,
+    contract: &State<AccountId>,
+) -> Result<(ContentType, String), Status> {
+    let feed = rfp_feed(db, rpc_service, contract, limit, filters).await?;
+    Ok((rss_content_type(), render_rss(&feed)))
+}
 
+async fn rfp_taxonomy(
+    db: &State<DB>,
+    rpc_service: &State<RpcService>,
+    kind: TaxonomyKind,
+    stage: Option<&str>,
+    from: Option<Timestamp>,
+    to: Option<Timestamp>,
+) -> Result<Json<Vec<TaxonomyCount>>, Status> {
     let last_updated_info = db.get_last_updated_info().await.unwrap();
 
-    let change_log_count = fetch_changelog_from_rpc(
+    let _ = fetch_changelog_from_rpc(
         db.inner(),
         rpc_service.inner(),
         Some(last_updated_info.after_block),
     )
     .await;
 
-    let (rfps, total) = fetch_rfps(db, limit, order, offset, filters).await;
+    match db.get_rfp_taxonomy(kind, stage, from, to).await {
+        Ok(counts) => Ok(Json(counts)),
+        Err(e) => {
+            eprintln!("Failed to get RFP {:?}: {:?}", kind, e);
+            Err(Status::InternalServerError)
+        }
+    }
+}
 
-    Some(Json(PaginatedResponse::new(
-        rfps.into_iter().collect(),
-        1,
-        limit.try_into().unwrap(),
-        total.try_into().unwrap(),
-        Some(change_log_count.unwrap_or(0)),
-    )))
+#[utoipa::path(get, path = "/rfps/labels?<stage>&<from>&<to>", params(
+  ("stage"= &str, Path, description = "only RFPs in this stage"),
+  ("from"= i64, Path, description = "only RFPs last updated at or after this timestamp (nanoseconds)"),
+  ("to"= i64, Path, description = "only RFPs last updated before this timestamp (nanoseconds)"),
+))]
+#[get("/labels?<stage>&<from>&<to>")]
+async fn get_rfps_labels(
+    stage: Option<&str>,
+    from: Option<Timestamp>,
+    to: Option<Timestamp>,
+    db: &State<DB>,
+    rpc_service: &State<RpcService>,
+) -> Result<Json<Vec<TaxonomyCount>>, Status> {
+    rfp_taxonomy(db, rpc_service, TaxonomyKind::Labels, stage, from, to).await
+}
+
+#[utoipa::path(get, path = "/rfps/categories?<stage>&<from>&<to>", params(
+  ("stage"= &str, Path, description = "only RFPs in this stage"),
+  ("from"= i64, Path, description = "only RFPs last updated at or after this timestamp (nanoseconds)"),
+  ("to"= i64, Path, description = "only RFPs last updated before this timestamp (nanoseconds)"),
+))]
+#[get("/categories?<stage>&<from>&<to>")]
+async fn get_rfps_categories(
+    stage: Option<&str>,
+    from: Option<Timestamp>,
+    to: Option<Timestamp>,
+    db: &State<DB>,
+    rpc_service: &State<RpcService>,
+) -> Result<Json<Vec<TaxonomyCount>>, Status> {
+    rfp_taxonomy(db, rpc_service, TaxonomyKind::Categories, stage, from, to).await
+}
+
+#[utoipa::path(get, path = "/rfps/export?<format>&<columns>&<all_snapshots>&<filters>", params(
+  ("format"= &str, Path, description = "csv, ndjson or parquet; falls back to the Accept header, then csv"),
+  ("columns"= &str, Path, description = "comma separated columns, default all"),
+  ("all_snapshots"= bool, Path, description = "export every snapshot instead of only the latest"),
+  ("filters"= GetRfpFilters, Path, description = "same filters as /rfps"),
+))]
+#[get("/export?<format>&<columns>&<all_snapshots>&<filters>")]
+#[allow(clippy::too_many_arguments)]
+async fn export_rfps(
+    format: Option<&str>,
+    columns: Option<&str>,
+    all_snapshots: Option<bool>,
+    filters: Option<GetRfpFilters>,
+    accept: Option<&Accept>,
+    db: &State<DB>,
+    rpc_service: &State<RpcService>,
+) -> Result<Export<impl Stream<Item = Vec<u8>>>, (Status, String)> {
+    let format = negotiate_format(format, accept).map_err(|e| (Status::BadRequest, e))?;
+    let columns = select_columns(RFP_COLUMNS, columns).map_err(|e| (Status::BadRequest, e))?;
+    let mut encoder = ExportEncoder::new(format, columns).map_err(|e| {
+        eprintln!("Failed to create RFP export encoder: {:?}", e);
+        (
+            Status::InternalServerError,
+            "Failed to start export".to_string(),
+        )
+    })?;
+
+    let last_updated_info = db.get_last_updated_info().await.unwrap();
+
+    let _ = fetch_changelog_from_rpc(
+        db.inner(),
+        rpc_service.inner(),
+        Some(last_updated_info.after_block),
+    )
+    .await;
+
+    let db = db.inner().clone();
+    let stream = ByteStream! {
+        let mut rows = db.stream_rfps_for_export(filters, all_snapshots.unwrap_or(false));
+        while let Some(row) = rows.next().await {
+            let chunk = match row {
+                Ok(row) => encoder.push(flatten_row(&row)),
+                Err(e) => Err(e.into()),
+            };
+            match chunk {
+                Ok(Some(chunk)) => yield chunk,
+                Ok(None) => {}
+                Err(e) => {
+                    // Headers are already sent, so the download is cut short
+                    eprintln!("Failed to export RFPs: {:?}", e);
+                    return;
+                }
+            }
+        }
+        match encoder.finish() {
+            Ok(chunk) => yield chunk,
+            Err(e) => eprintln!("Failed to finish RFP export: {:?}", e),
+        }
+    };
+
+    Ok(Export {
+        format,
+        name: "rfps",
+        stream,
+    })
 }
 
 #[utoipa::path(get, path = "/rfp/{rfp_id}")]
 #[get("/<rfp_id>")]
-async fn get_rfp(rfp_id: i32) -> Result<Json<VersionedRFP>, Status> {
-    match RpcService::new().get_rfp(rfp_id).await {
+async fn get_rfp(
+    rfp_id: i32,
+    rpc_service: &State<RpcService>,
+) -> Result<Json<VersionedRFP>, Status> {
+    match rpc_service.get_rfp(rfp_id).await {
         Ok(rfp) => Ok(Json(rfp.data)),
         Err(e) => {
             eprintln!("In /rfp/rfp_id; Failed to get rfp from RPC: {:?}", e);
@@ -142,11 +366,40 @@ async fn get_rfp_with_snapshots(
     }
 }
 
+#[utoipa::path(get, path = "/rfp/{rfp_id}/links")]
+#[get("/<rfp_id>/links")]
+async fn get_rfp_links(
+    rfp_id: i32,
+    db: &State<DB>,
+    rpc_service: &State<RpcService>,
+) -> Result<Json<RfpLinks>, Status> {
+    let last_updated_info = db.get_last_updated_info().await.unwrap();
+
+    let _ = fetch_changelog_from_rpc(
+        db.inner(),
+        rpc_service.inner(),
+        Some(last_updated_info.after_block),
+    )
+    .await;
+
+    match resolve_rfp_links(db.inner(), rfp_id).await {
+        Ok(Some(links)) => Ok(Json(links)),
+        Ok(None) => Err(Status::NotFound),
+        Err(e) => {
+            eprintln!("Failed to resolve rfp links: {:?}", e);
+            Err(Status::InternalServerError)
+        }
+    }
+}
+
 // TODO Remove this once we go in production or put it behind authentication or a flag
 #[delete("/<rfp_id>/snapshots")]
 async fn remove_rfp_snapshots_by_rfp_id(rfp_id: i32, db: &State<DB>) -> Result<(), Status> {
     match db.remove_rfp_snapshots_by_rfp_id(rfp_id).await {
-        Ok(_) => Ok(()),
+        Ok(_) => {
+            invalidate();
+            Ok(())
+        }
         Err(e) => {
             eprintln!("Failed to remove rfp snapshots: {:?}", e);
             Err(Status::InternalServerError)
@@ -159,12 +412,24 @@ pub fn stage() -> rocket::fairing::AdHoc {
         println!("Rfp stage on ignite!");
 
         rocket
-            .mount("/rfps/", rocket::routes![get_rfps, search])
+            .mount(
+                "/rfps/",
+                rocket::routes![
+                    get_rfps,
+                    get_rfps_atom_feed,
+                    get_rfps_rss_feed,
+                    export_rfps,
+                    get_rfps_labels,
+                    get_rfps_categories,
+                    search
+                ],
+            )
             .mount(
                 "/rfp/",
                 rocket::routes![
                     get_rfp,
                     get_rfp_with_snapshots,
+                    get_rfp_links,
                     remove_rfp_snapshots_by_rfp_id
                 ],
             )
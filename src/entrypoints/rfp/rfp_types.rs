@@ -1,4 +1,5 @@
 use crate::db::db_types::{BlockHeight, RfpSnapshotRecord};
+use async_graphql::InputObject;
 pub use devhub_shared::rfp::RFP as ContractRFP;
 use devhub_shared::rfp::{VersionedRFPBody, RFP};
 use rocket::serde::{Deserialize, Serialize};
@@ -16,7 +17,8 @@ pub struct PartialEditRFPTimelineArgs {
     pub timeline: String,
 }
 
-#[derive(Clone, Debug, FromForm, ToSchema)]
+#[derive(Clone, Debug, FromForm, ToSchema, InputObject)]
+#[graphql(name = "RfpFilters")]
 pub struct GetRfpFilters {
     pub category: Option<String>,
     pub labels: Option<Vec<String>>,
@@ -26,6 +28,15 @@ pub struct GetRfpFilters {
     pub block_timestamp: Option<i64>,
 }
 
+/// Query parameters of `GET /rfps`.
+#[derive(Debug, FromForm)]
+pub struct RfpListQuery {
+    pub order: Option<String>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+    pub filters: Option<GetRfpFilters>,
+}
+
 #[derive(Serialize, Deserialize, Clone)]
 pub struct SetRfpBlockHeightCallbackArgs {
     pub rfp: RFP,
@@ -68,6 +79,8 @@ impl FromContractRFP for RfpSnapshotRecord {
             )),
             submission_deadline: rfp.snapshot.body.get_submission_deadline(),
             views: Some(0),
+            state_block_height: None,
+            approximate: false,
         }
     }
 }
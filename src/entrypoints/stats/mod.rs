@@ -0,0 +1,112 @@
+use self::stats_types::*;
+use crate::changelog::fetch_changelog_from_rpc;
+use crate::db::db_types::Timestamp;
+use crate::db::DB;
+use crate::rpc_service::RpcService;
+use chrono::Utc;
+use rocket::serde::json::Json;
+use rocket::{get, http::Status, State};
+pub mod stats_types;
+
+#[utoipa::path(get, path = "/stats/funding?<from>&<to>", params(
+  ("from"= i64, Path, description = "only proposals created at or after this timestamp (nanoseconds)"),
+  ("to"= i64, Path, description = "only proposals created before this timestamp (nanoseconds)"),
+))]
+#[get("/funding?<from>&<to>")]
+async fn get_funding_stats(
+    from: Option<Timestamp>,
+    to: Option<Timestamp>,
+    db: &State<DB>,
+    rpc_service: &State<RpcService>,
+) -> Result<Json<FundingStats>, Status> {
+    let last_updated_info = db.get_last_updated_info().await.unwrap();
+
+    let _ = fetch_changelog_from_rpc(
+        db.inner(),
+        rpc_service.inner(),
+        Some(last_updated_info.after_block),
+    )
+    .await;
+
+    match db.get_funding_stats(from, to).await {
+        Ok(records) => Ok(Json(FundingStats::from_records(from, to, records))),
+        Err(e) => {
+            eprintln!("Failed to get funding stats: {:?}", e);
+            Err(Status::InternalServerError)
+        }
+    }
+}
+
+#[utoipa::path(get, path = "/stats/pipeline?<from>&<to>&<sla_stage>&<sla_hours>", params(
+  ("from"= i64, Path, description = "only stage transitions at or after this timestamp (nanoseconds)"),
+  ("to"= i64, Path, description = "only stage transitions before this timestamp (nanoseconds)"),
+  ("sla_stage"= &str, Path, description = "stage the SLA applies to, default REVIEW"),
+  ("sla_hours"= i64, Path, description = "hours a proposal may stay in sla_stage, default 336"),
+))]
+#[get("/pipeline?<from>&<to>&<sla_stage>&<sla_hours>")]
+#[allow(clippy::too_many_arguments)]
+async fn get_pipeline_stats(
+    from: Option<Timestamp>,
+    to: Option<Timestamp>,
+    sla_stage: Option<String>,
+    sla_hours: Option<i64>,
+    db: &State<DB>,
+    rpc_service: &State<RpcService>,
+    config: &State<PipelineConfig>,
+) -> Result<Json<PipelineStats>, Status> {
+    let sla_stage = sla_stage
+        .unwrap_or_else(|| config.sla_stage.clone())
+        .to_uppercase();
+    let sla_hours = sla_hours.unwrap_or(config.sla_hours).max(0);
+
+    let last_updated_info = db.get_last_updated_info().await.unwrap();
+
+    let _ = fetch_changelog_from_rpc(
+        db.inner(),
+        rpc_service.inner(),
+        Some(last_updated_info.after_block),
+    )
+    .await;
+
+    let now = Utc::now().timestamp_nanos_opt().unwrap_or(i64::MAX);
+    let entered_before = now.saturating_sub(sla_hours.saturating_mul(3_600_000_000_000));
+    let result = tokio::try_join!(
+        db.get_stage_durations(from, to),
+        db.get_weekly_stage_counts(from, to),
+        db.get_sla_breaches(&sla_stage, entered_before, now),
+    );
+
+    match result {
+        Ok((time_in_stage, weekly, breaches)) => Ok(Json(PipelineStats {
+            from,
+            to,
+            time_in_stage,
+            weekly,
+            sla: SlaReport {
+                stage: sla_stage,
+                threshold_hours: sla_hours,
+                breaches,
+            },
+        })),
+        Err(e) => {
+            eprintln!("Failed to get pipeline stats: {:?}", e);
+            Err(Status::InternalServerError)
+        }
+    }
+}
+
+pub fn stage() -> rocket::fairing::AdHoc {
+    rocket::fairing::AdHoc::on_ignite("Stats Stage", |rocket| async {
+        println!("Stats stage on ignite!");
+
+        let config: PipelineConfig = rocket
+            .figment()
+            .extract_inner("pipeline")
+            .unwrap_or_default();
+
+        rocket.manage(config).mount(
+            "/stats",
+            rocket::routes![get_funding_stats, get_pipeline_stats],
+        )
+    })
+}
@@ -0,0 +1,112 @@
+use crate::db::db_types::{
+    FundingStatsRecord, SlaBreachRecord, StageDurationRecord, StageWeekRecord, Timestamp,
+};
+use rocket::serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct FundingTotals {
+    pub proposals: i64,
+    pub requested_usd: i64,
+    /// Approved, approved conditionally, payment processing or funded
+    pub approved_usd: i64,
+    /// Funded
+    pub paid_usd: i64,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct FundingGroup {
+    /// Category, currency, `YYYY-MM` month, stage or supervisor; null when unset
+    pub key: Option<String>,
+    #[serde(flatten)]
+    pub totals: FundingTotals,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, ToSchema)]
+pub struct FundingStats {
+    /// Inclusive lower bound on proposal creation, in nanoseconds
+    pub from: Option<Timestamp>,
+    /// Exclusive upper bound on proposal creation, in nanoseconds
+    pub to: Option<Timestamp>,
+    pub totals: FundingTotals,
+    pub by_category: Vec<FundingGroup>,
+    pub by_currency: Vec<FundingGroup>,
+    pub by_month: Vec<FundingGroup>,
+    pub by_stage: Vec<FundingGroup>,
+    pub by_supervisor: Vec<FundingGroup>,
+}
+
+impl FundingStats {
+    pub fn from_records(
+        from: Option<Timestamp>,
+        to: Option<Timestamp>,
+        records: Vec<FundingStatsRecord>,
+    ) -> Self {
+        let mut stats = FundingStats {
+            from,
+            to,
+            ..Default::default()
+        };
+        for record in records {
+            let totals = FundingTotals {
+                proposals: record.proposals,
+                requested_usd: record.requested_usd,
+                approved_usd: record.approved_usd,
+                paid_usd: record.paid_usd,
+            };
+            let groups = match record.dimension.as_str() {
+                "category" => &mut stats.by_category,
+                "currency" => &mut stats.by_currency,
+                "month" => &mut stats.by_month,
+                "stage" => &mut stats.by_stage,
+                "supervisor" => &mut stats.by_supervisor,
+                _ => {
+                    stats.totals = totals;
+                    continue;
+                }
+            };
+            groups.push(FundingGroup {
+                key: record.key,
+                totals,
+            });
+        }
+        stats
+    }
+}
+
+/// The stage and time limit `/stats/pipeline` reports overdue proposals for when the
+/// request doesn't pass `sla_stage` and `sla_hours`.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct PipelineConfig {
+    pub sla_stage: String,
+    pub sla_hours: i64,
+}
+
+impl Default for PipelineConfig {
+    fn default() -> Self {
+        Self {
+            sla_stage: "REVIEW".to_string(),
+            sla_hours: 14 * 24,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct SlaReport {
+    pub stage: String,
+    pub threshold_hours: i64,
+    /// Proposals that have been in `stage` longer than the threshold, longest first
+    pub breaches: Vec<SlaBreachRecord>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct PipelineStats {
+    /// Inclusive lower bound on stage transitions, in nanoseconds
+    pub from: Option<Timestamp>,
+    /// Exclusive upper bound on stage transitions, in nanoseconds
+    pub to: Option<Timestamp>,
+    pub time_in_stage: Vec<StageDurationRecord>,
+    pub weekly: Vec<StageWeekRecord>,
+    pub sla: SlaReport,
+}
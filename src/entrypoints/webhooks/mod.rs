@@ -0,0 +1,154 @@
+use self::webhooks_types::*;
+use crate::auth::Admin;
+use crate::db::db_types::{WebhookDelivery, WebhookDeliveryAttempt, WebhookSubscription};
+use crate::db::DB;
+use crate::webhooks::run_delivery_worker;
+use crate::webhooks::types::WebhookConfig;
+use rocket::serde::json::Json;
+use rocket::{delete, get, http::Status, post, State};
+use rocket_db_pools::Database;
+pub mod webhooks_types;
+
+#[utoipa::path(post, path = "/webhooks", request_body = CreateWebhookSubscription)]
+#[post("/", data = "<subscription>", format = "application/json")]
+async fn create_subscription(
+    _admin: Admin,
+    subscription: Json<CreateWebhookSubscription>,
+    db: &State<DB>,
+) -> Result<Json<WebhookSubscription>, (Status, String)> {
+    subscription
+        .validate()
+        .map_err(|e| (Status::BadRequest, e))?;
+
+    let event_types: Vec<String> = subscription
+        .event_types
+        .iter()
+        .map(|t| t.as_str().to_string())
+        .collect();
+    match db
+        .create_webhook_subscription(
+            &subscription.url,
+            &subscription.secret,
+            &event_types,
+            subscription.category.clone(),
+            subscription.author_id.clone(),
+        )
+        .await
+    {
+        Ok(subscription) => Ok(Json(subscription)),
+        Err(e) => {
+            eprintln!("Failed to create webhook subscription: {:?}", e);
+            Err((Status::InternalServerError, "".to_string()))
+        }
+    }
+}
+
+#[utoipa::path(get, path = "/webhooks")]
+#[get("/")]
+async fn get_subscriptions(
+    _admin: Admin,
+    db: &State<DB>,
+) -> Result<Json<Vec<WebhookSubscription>>, Status> {
+    match db.get_webhook_subscriptions().await {
+        Ok(subscriptions) => Ok(Json(subscriptions)),
+        Err(e) => {
+            eprintln!("Failed to get webhook subscriptions: {:?}", e);
+            Err(Status::InternalServerError)
+        }
+    }
+}
+
+#[utoipa::path(delete, path = "/webhooks/{subscription_id}")]
+#[delete("/<subscription_id>")]
+async fn delete_subscription(
+    _admin: Admin,
+    subscription_id: i32,
+    db: &State<DB>,
+) -> Result<(), Status> {
+    match db.deactivate_webhook_subscription(subscription_id).await {
+        Ok(true) => Ok(()),
+        Ok(false) => Err(Status::NotFound),
+        Err(e) => {
+            eprintln!("Failed to deactivate webhook subscription: {:?}", e);
+            Err(Status::InternalServerError)
+        }
+    }
+}
+
+#[utoipa::path(get, path = "/webhooks/{subscription_id}/deliveries?<limit>&<offset>", params(
+  ("limit"= i64, Path, description = "default limit 50"),
+  ("offset"= i64, Path, description = "offset"),
+))]
+#[get("/<subscription_id>/deliveries?<limit>&<offset>")]
+async fn get_deliveries(
+    _admin: Admin,
+    subscription_id: i32,
+    limit: Option<i64>,
+    offset: Option<i64>,
+    db: &State<DB>,
+) -> Result<Json<Vec<WebhookDelivery>>, Status> {
+    let limit = limit.unwrap_or(50);
+    let offset = offset.unwrap_or(0);
+
+    match db
+        .get_webhook_deliveries(subscription_id, limit, offset)
+        .await
+    {
+        Ok(deliveries) => Ok(Json(deliveries)),
+        Err(e) => {
+            eprintln!("Failed to get webhook deliveries: {:?}", e);
+            Err(Status::InternalServerError)
+        }
+    }
+}
+
+#[utoipa::path(get, path = "/webhooks/deliveries/{delivery_id}/attempts")]
+#[get("/deliveries/<delivery_id>/attempts")]
+async fn get_delivery_attempts(
+    _admin: Admin,
+    delivery_id: i64,
+    db: &State<DB>,
+) -> Result<Json<Vec<WebhookDeliveryAttempt>>, Status> {
+    match db.get_webhook_delivery_attempts(delivery_id).await {
+        Ok(attempts) => Ok(Json(attempts)),
+        Err(e) => {
+            eprintln!("Failed to get webhook delivery attempts: {:?}", e);
+            Err(Status::InternalServerError)
+        }
+    }
+}
+
+pub fn stage() -> rocket::fairing::AdHoc {
+    rocket::fairing::AdHoc::on_ignite("Webhooks Stage", |rocket| async {
+        println!("Webhooks stage on ignite!");
+
+        rocket
+            .mount(
+                "/webhooks",
+                rocket::routes![
+                    create_subscription,
+                    get_subscriptions,
+                    delete_subscription,
+                    get_deliveries,
+                    get_delivery_attempts,
+                ],
+            )
+            .attach(rocket::fairing::AdHoc::on_liftoff(
+                "Webhook delivery worker",
+                |rocket| {
+                    Box::pin(async move {
+                        let config: WebhookConfig = rocket
+                            .figment()
+                            .extract_inner("webhooks")
+                            .unwrap_or_default();
+                        match DB::fetch(rocket) {
+                            Some(db) => {
+                                tokio::spawn(run_delivery_worker(db.clone(), config));
+                            }
+                            None => eprintln!("Webhook delivery worker needs the database"),
+                        }
+                    })
+                },
+            ))
+    })
+}
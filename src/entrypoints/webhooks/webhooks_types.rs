@@ -0,0 +1,32 @@
+use crate::webhooks::types::WebhookEventType;
+use rocket::serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct CreateWebhookSubscription {
+    /// http(s) endpoint that receives a JSON POST per event
+    pub url: String,
+    /// Key for the `X-Devhub-Signature` HMAC; never returned by the API
+    pub secret: String,
+    pub event_types: Vec<WebhookEventType>,
+    /// Only deliver events for proposals in this category
+    pub category: Option<String>,
+    /// Only deliver events for proposals by this author
+    pub author_id: Option<String>,
+}
+
+impl CreateWebhookSubscription {
+    pub fn validate(&self) -> Result<(), String> {
+        match reqwest::Url::parse(&self.url) {
+            Ok(url) if url.scheme() == "http" || url.scheme() == "https" => {}
+            _ => return Err(format!("Invalid webhook url: {}", self.url)),
+        }
+        if self.secret.is_empty() {
+            return Err("Webhook secret must not be empty".to_string());
+        }
+        if self.event_types.is_empty() {
+            return Err("At least one event type is required".to_string());
+        }
+        Ok(())
+    }
+}
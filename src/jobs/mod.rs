@@ -0,0 +1,122 @@
+use self::types::*;
+use crate::db::db_types::Job;
+use crate::db::DB;
+use crate::nearblocks_client::transactions::{update_nearblocks_data, SyncOutcome};
+use crate::nearblocks_client::ApiClient;
+use crate::rpc_service::RpcService;
+pub mod types;
+
+/// Runs a queued job to completion and records how it ended.
+pub async fn run_job(db: DB, rpc_service: RpcService, nearblocks_client: ApiClient, job: Job) {
+    match db.start_job(job.id).await {
+        Ok(true) => {}
+        // Cancelled before it started
+        Ok(false) => return,
+        Err(e) => {
+            eprintln!("Failed to start job {}: {:?}", job.id, e);
+            return;
+        }
+    }
+
+    let (status, error) = match JobKind::parse(&job.kind) {
+        Some(kind) => {
+            // A job picked up again after a restart continues its own sync from the cursor
+            let after_block = match kind {
+                JobKind::SyncFromStart if job.pages_fetched == 0 => Some(0),
+                JobKind::SyncFromStart | JobKind::ContinueSync => None,
+            };
+            // Transactions from before a restart count toward the limit
+            let max_transactions = job
+                .max_transactions
+                .map(|max| (max - job.transactions_fetched).max(0) as usize);
+            let result = update_nearblocks_data(
+                (&db).into(),
+                (&rpc_service).into(),
+                &nearblocks_client,
+                after_block,
+                max_transactions,
+                Some(job.id),
+            )
+            .await;
+            match result {
+                Ok(SyncOutcome::Finished | SyncOutcome::Paused) => (JobStatus::Completed, None),
+                Ok(SyncOutcome::Cancelled) => (JobStatus::Cancelled, None),
+                Err(e) => {
+                    eprintln!("Job {} failed: {:?}", job.id, e);
+                    (JobStatus::Failed, Some(e.to_string()))
+                }
+            }
+        }
+        None => (
+            JobStatus::Failed,
+            Some(format!("Unknown job kind {}", job.kind)),
+        ),
+    };
+
+    if let Err(e) = db.finish_job(job.id, status.as_str(), error).await {
+        eprintln!("Failed to store job {} status: {:?}", job.id, e);
+    }
+}
+
+/// Queues a job and runs it in the background. `None` when the contract already has a job
+/// queued or running.
+pub async fn enqueue_job(
+    db: &DB,
+    rpc_service: &RpcService,
+    nearblocks_client: &ApiClient,
+    kind: JobKind,
+    contract: &str,
+    max_transactions: Option<i64>,
+) -> Result<Option<Job>, sqlx::Error> {
+    let job = db
+        .create_job(kind.as_str(), contract, max_transactions)
+        .await?;
+    if let Some(job) = &job {
+        tokio::spawn(run_job(
+            db.clone(),
+            rpc_service.clone(),
+            nearblocks_client.clone(),
+            job.clone(),
+        ));
+    }
+    Ok(job)
+}
+
+/// Runs the job a restart cut short again, or gives an unfinished sync that was started
+/// without a job one, so it is tracked and still blocks a second sync for the contract.
+pub async fn resume_interrupted_jobs(
+    db: DB,
+    rpc_service: RpcService,
+    nearblocks_client: ApiClient,
+    contract: String,
+) {
+    let job = match db.requeue_interrupted_job(&contract).await {
+        Ok(Some(job)) => job,
+        Ok(None) => match db.get_last_updated_info().await {
+            Ok(info) if !info.cursor.is_empty() => {
+                match db
+                    .create_job(JobKind::ContinueSync.as_str(), &contract, None)
+                    .await
+                {
+                    Ok(Some(job)) => job,
+                    Ok(None) => return,
+                    Err(e) => {
+                        eprintln!("Failed to create job for the unfinished sync: {:?}", e);
+                        return;
+                    }
+                }
+            }
+            Ok(_) => return,
+            Err(e) => {
+                eprintln!("Failed to read NearBlocks sync progress: {:?}", e);
+                return;
+            }
+        },
+        Err(e) => {
+            eprintln!("Failed to requeue interrupted jobs: {:?}", e);
+            return;
+        }
+    };
+    println!("Resuming job {} ({})", job.id, job.kind);
+    run_job(db, rpc_service, nearblocks_client, job).await;
+}
@@ -1,17 +1,23 @@
+pub mod auth;
 pub mod changelog;
 pub mod db;
 pub mod entrypoints;
+pub mod jobs;
 pub mod nearblocks_client;
+pub mod reconciliation;
 pub mod rpc_service;
 pub mod types;
+pub mod webhooks;
 
 // Re-export commonly used items
 pub use rpc_service::RpcService;
 pub use types::PaginatedResponse;
 
 use crate::entrypoints::ApiDoc;
+use crate::rpc_service::network::Network;
 use crate::rpc_service::Env;
 use near_account_id::AccountId;
+use rocket::fairing::AdHoc;
 use rocket::{catch, catchers, get, routes};
 use rocket_cors::{AllOrSome, AllowedOrigins, Origins};
 use utoipa::OpenApi;
@@ -87,26 +93,26 @@ fn bad_request() -> &'static str {
     "Custom 400 Error: Bad Request"
 }
 
-pub fn rocket(rpc_service: Option<RpcService>) -> rocket::Rocket<rocket::Build> {
-    dotenvy::dotenv().ok();
+struct Settings {
+    cors: rocket_cors::Cors,
+    contract: AccountId,
+    nearblocks_api_key: String,
+    nearblocks_client: nearblocks_client::ApiClient,
+    rpc_service: RpcService,
+}
+
+fn load_settings(rpc_service: Option<RpcService>) -> anyhow::Result<Settings> {
+    let env: Env = envy::from_env::<Env>()
+        .map_err(|e| anyhow::anyhow!("Failed to load environment variables: {}", e))?;
 
-    let env: Env = envy::from_env::<Env>().expect("Failed to load environment variables");
-
-    let exact_origins = AllowedOrigins::some_exact(&[
-        "http://localhost:3000",
-        "http://localhost:8080", // Playwright
-        "http://127.0.0.1:8080", // Local development
-        "https://dev.near.org",
-        "https://near.social",
-        "https://neardevhub.org",
-        "https://devhub.near.page",
-        "https://events-committee.near.page/",
-        "https://infrastructure-committee.near.page/",
-        "https://devhub-cache-api-rs-2.fly.dev",
-        "https://infra-cache-api-rs-2.fly.dev",
-        "https://events-cache-api-rs-2.fly.dev",
-        // TODO Add prod urls here
-    ]);
+    let network = Network::from_env()?;
+    // Fails early for a custom network without a NearBlocks API to sync from
+    let nearblocks_client = nearblocks_client::ApiClient::with_base_url(
+        &nearblocks_client::base_url_for(&network)?,
+        env.nearblocks_api_key.clone(),
+    );
+
+    let exact_origins = AllowedOrigins::some_exact(&network.cors_origins());
     let allowed_origins = Origins {
         allow_null: true, // Iframe simpleMDE mentioning proposals
         exact: exact_origins.unwrap().exact,
@@ -118,24 +124,53 @@ pub fn rocket(rpc_service: Option<RpcService>) -> rocket::Rocket<rocket::Build>
         ..Default::default()
     }
     .to_cors()
-    .expect("Failed to create cors config");
+    .map_err(|e| anyhow::anyhow!("Failed to create cors config: {}", e))?;
 
-    let figment = rocket::Config::figment()
-        .merge(rocket::Config::default())
-        .merge(("databases.my_db.url", env.database_url));
+    let contract: AccountId = env
+        .contract
+        .parse()
+        .map_err(|e| anyhow::anyhow!("Invalid CONTRACT {:?}: {}", env.contract, e))?;
 
-    let contract: AccountId = env.contract.parse::<AccountId>().unwrap();
-    let nearblocks_api_key = env.nearblocks_api_key;
+    let rpc_service = match rpc_service {
+        Some(rpc_service) => rpc_service,
+        None => RpcService::for_network(network, contract.clone())?,
+    };
 
-    let rpc_service = rpc_service.unwrap_or(RpcService::mainnet(contract.clone()));
+    Ok(Settings {
+        cors,
+        contract,
+        nearblocks_api_key: env.nearblocks_api_key,
+        nearblocks_client,
+        rpc_service,
+    })
+}
+
+pub fn rocket(rpc_service: Option<RpcService>) -> rocket::Rocket<rocket::Build> {
+    dotenvy::dotenv().ok();
+
+    let mut figment = rocket::Config::figment().merge(rocket::Config::default());
+    if let Ok(database_url) = std::env::var("DATABASE_URL") {
+        figment = figment.merge(("databases.my_db.url", database_url));
+    }
 
     rocket::custom(figment)
-        .attach(cors)
+        // Environment errors stop the launch here with a message instead of a panic
+        .attach(AdHoc::try_on_ignite("Settings", |rocket| async move {
+            match load_settings(rpc_service) {
+                Ok(settings) => Ok(rocket
+                    .attach(settings.cors)
+                    .manage(settings.contract)
+                    .manage(settings.nearblocks_api_key)
+                    .manage(settings.nearblocks_client)
+                    .manage(settings.rpc_service)),
+                Err(e) => {
+                    eprintln!("Invalid configuration: {:?}", e);
+                    Err(rocket)
+                }
+            }
+        }))
         .attach(db::stage())
         .mount("/", routes![robots, index])
-        .manage(contract)
-        .manage(nearblocks_api_key)
-        .manage(rpc_service)
         .mount("/test", rocket::routes![test])
         .attach(entrypoints::stage())
         .mount(
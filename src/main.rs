@@ -1,9 +1,13 @@
+pub mod auth;
 pub mod changelog;
 pub mod db;
 pub mod entrypoints;
+pub mod jobs;
 pub mod nearblocks_client;
+pub mod reconciliation;
 pub mod rpc_service;
 pub mod types;
+pub mod webhooks;
 
 use chrono::DateTime;
 use regex::Regex;
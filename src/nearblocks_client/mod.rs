@@ -4,9 +4,14 @@ use serde::{Deserialize, Serialize};
 pub mod types;
 use types::Transaction;
 pub mod proposal;
+pub mod rate_limit;
 pub mod rfp;
 pub mod transactions;
+use crate::rpc_service::network::Network;
 use crate::rpc_service::Env;
+use rate_limit::{is_transient, retry_after, RateLimiter, RetryPolicy};
+
+pub const DEFAULT_BASE_URL: &str = "https://api.nearblocks.io/";
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ApiResponse {
     #[serde(default)]
@@ -14,11 +19,31 @@ pub struct ApiResponse {
     pub cursor: Option<String>,
 }
 
+/// `NEARBLOCKS_API_URL` when set, pointing at a mirror or a local mock server, otherwise
+/// the network's NearBlocks API. Custom networks have none, so they fail without the variable.
+pub fn base_url_for(network: &Network) -> anyhow::Result<String> {
+    dotenvy::dotenv().ok();
+
+    pick_base_url(network, std::env::var("NEARBLOCKS_API_URL").ok())
+}
+
+fn pick_base_url(network: &Network, configured: Option<String>) -> anyhow::Result<String> {
+    match (configured, network.nearblocks_base_url()) {
+        (Some(url), _) => Ok(url),
+        (None, Some(url)) => Ok(url.to_string()),
+        (None, None) => Err(anyhow::anyhow!(
+            "NEARBLOCKS_API_URL is required when NETWORK is a custom RPC url"
+        )),
+    }
+}
+
 #[derive(Clone)]
 pub struct ApiClient {
     base_url: String,
     client: Client,
     api_key: String,
+    rate_limiter: RateLimiter,
+    retry_policy: RetryPolicy,
 }
 
 impl Default for ApiClient {
@@ -31,14 +56,36 @@ impl ApiClient {
     pub fn new() -> Self {
         dotenvy::dotenv().ok();
         let env: Env = envy::from_env::<Env>().expect("Failed to load environment variables");
+        let network = Network::from_env().expect("Failed to parse NETWORK");
+        let base_url = base_url_for(&network).expect("Failed to find the NearBlocks API");
+
+        Self::with_base_url(&base_url, env.nearblocks_api_key)
+    }
 
+    pub fn with_base_url(base_url: &str, api_key: String) -> Self {
+        let mut base_url = base_url.to_string();
+        if !base_url.ends_with('/') {
+            base_url.push('/');
+        }
         Self {
-            base_url: "https://api.nearblocks.io/".to_string(),
+            base_url,
             client: Client::new(),
-            api_key: env.nearblocks_api_key,
+            api_key,
+            rate_limiter: RateLimiter::shared(),
+            retry_policy: RetryPolicy::default(),
         }
     }
 
+    pub fn with_rate_limiter(mut self, rate_limiter: RateLimiter) -> Self {
+        self.rate_limiter = rate_limiter;
+        self
+    }
+
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
     pub async fn get_account_txns_by_pagination(
         &self,
         account_id: AccountId,
@@ -55,21 +102,53 @@ impl ApiClient {
 
         println!("Fetching transactions from {}", url);
 
-        let response = self
-            .client
-            .get(&url)
-            .header("Authorization", format!("Bearer {}", self.api_key))
-            .send()
-            .await?;
+        let mut attempt = 0;
+        let response = loop {
+            self.rate_limiter.acquire().await;
+            let (error, retry_after) = match self
+                .client
+                .get(&url)
+                .header("Authorization", format!("Bearer {}", self.api_key))
+                .send()
+                .await
+            {
+                Ok(response) => {
+                    self.rate_limiter.observe(response.headers()).await;
+                    // Add debug information about the response
+                    println!("Response status: {}", response.status());
 
-        // Add debug information about the response
-        println!("Response status: {}", response.status());
+                    let status = response.status();
+                    if status.is_success() {
+                        break response;
+                    }
+                    let wait = retry_after(response.headers());
+                    let error_text = response.text().await.unwrap_or_default();
+                    eprintln!("API error response: {}", error_text);
+                    if !is_transient(status) || attempt >= self.retry_policy.max_retries {
+                        return Err(anyhow::anyhow!("API error response: {}", error_text));
+                    }
+                    if let Some(wait) = wait {
+                        // Retry-After applies to every call, not only this one
+                        self.rate_limiter.pause(wait).await;
+                    }
+                    (anyhow::anyhow!("{}: {}", status, error_text), wait)
+                }
+                Err(e) if attempt < self.retry_policy.max_retries => (anyhow::anyhow!(e), None),
+                Err(e) => return Err(e.into()),
+            };
 
-        if !response.status().is_success() {
-            let error_text = response.text().await?;
-            eprintln!("API error response: {}", error_text);
-            return Err(anyhow::anyhow!("API error response: {}", error_text));
-        }
+            // The server's Retry-After is the minimum, the backoff doesn't add to it
+            let delay = self
+                .retry_policy
+                .delay(attempt)
+                .max(retry_after.unwrap_or_default());
+            attempt += 1;
+            eprintln!(
+                "Retrying NearBlocks request in {:?} (attempt {}): {}",
+                delay, attempt, error
+            );
+            tokio::time::sleep(delay).await;
+        };
 
         let response_text = response.text().await?;
 
@@ -164,4 +243,18 @@ mod tests {
         let result = client.build_pagination_params(Some(100), Some("desc".to_string()), Some(5));
         assert_eq!(result, "?per_page=100&order=desc&page=5");
     }
+
+    #[test]
+    fn test_pick_base_url() {
+        let custom: Network = "http://127.0.0.1:3030".parse().unwrap();
+        assert!(pick_base_url(&custom, None).is_err());
+        assert_eq!(
+            pick_base_url(&custom, Some("http://127.0.0.1:4000/".to_string())).unwrap(),
+            "http://127.0.0.1:4000/"
+        );
+        assert_eq!(
+            pick_base_url(&Network::Mainnet, None).unwrap(),
+            DEFAULT_BASE_URL
+        );
+    }
 }
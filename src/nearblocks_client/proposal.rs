@@ -1,10 +1,11 @@
 use crate::db::db_types::ProposalSnapshotRecord;
 use crate::db::DB;
+use crate::entrypoints::cache::invalidate;
 use crate::entrypoints::proposal::proposal_types::{
     FromContractProposal, PartialEditProposalArgs, SetBlockHeightCallbackArgs,
 };
-use crate::nearblocks_client::types::{Transaction, BLOCK_HEIGHT_OFFSET};
-use crate::rpc_service::RpcService;
+use crate::nearblocks_client::types::Transaction;
+use crate::rpc_service::{proposal_snapshot_since, RpcService};
 use devhub_shared::proposal::VersionedProposal;
 use rocket::State;
 
@@ -41,23 +42,33 @@ pub async fn handle_set_block_height_callback(
 
     let id = args.clone().proposal.id.try_into().unwrap();
 
+    // The proposal exists from the callback's block on, so the first readable block is it
     let versioned_proposal_fallback: VersionedProposal = args.clone().proposal.into();
-    let versioned_proposal = match rpc_service.get_proposal(id).await {
-        Ok(proposal) => proposal.data,
+    let (versioned_proposal, state_block_height, approximate) = match rpc_service
+        .get_proposal_after_receipt(id, transaction.receipt_block.block_height, |_| true)
+        .await
+    {
+        Ok(read) => (
+            read.state.data,
+            Some(read.state.block_height as i64),
+            read.approximate,
+        ),
         Err(e) => {
             eprintln!(
                 "Failed to get proposal from RPC, using first snapshot as fallback {:?}",
                 e
             );
-            versioned_proposal_fallback
+            (versioned_proposal_fallback, None, false)
         }
     };
 
-    let snapshot = ProposalSnapshotRecord::from_contract_proposal(
+    let mut snapshot = ProposalSnapshotRecord::from_contract_proposal(
         versioned_proposal.clone().into(),
         transaction.block_timestamp.parse::<i64>().unwrap(),
         transaction.block.block_height,
     );
+    snapshot.state_block_height = state_block_height;
+    snapshot.approximate = approximate;
 
     DB::insert_proposal_snapshot(&mut tx, &snapshot)
         .await
@@ -80,6 +91,7 @@ pub async fn handle_set_block_height_callback(
         eprintln!("Failed to commit transaction: {:?}", e);
         anyhow::anyhow!("Failed to commit transaction")
     })?;
+    invalidate();
 
     Ok(())
 }
@@ -94,11 +106,11 @@ pub async fn handle_edit_proposal(
         anyhow::anyhow!("Failed to get proposal ID")
     })?;
     println!("Updating proposal {}", id);
+    let receipt_timestamp = transaction.receipt_block.block_timestamp as u64;
     let versioned_proposal = match rpc_service
-        .get_proposal_on_block(
-            id,
-            transaction.receipt_block.block_height + BLOCK_HEIGHT_OFFSET,
-        )
+        .get_proposal_after_receipt(id, transaction.receipt_block.block_height, |proposal| {
+            proposal_snapshot_since(proposal, receipt_timestamp)
+        })
         .await
     {
         Ok(proposal) => proposal,
@@ -113,11 +125,13 @@ pub async fn handle_edit_proposal(
         anyhow::anyhow!("Failed to begin transaction")
     })?;
 
-    let snapshot = ProposalSnapshotRecord::from_contract_proposal(
-        versioned_proposal.clone().into(),
+    let mut snapshot = ProposalSnapshotRecord::from_contract_proposal(
+        versioned_proposal.state.data.into(),
         transaction.block_timestamp.parse::<i64>().unwrap(),
         transaction.block.block_height,
     );
+    snapshot.state_block_height = Some(versioned_proposal.state.block_height as i64);
+    snapshot.approximate = versioned_proposal.approximate;
 
     DB::insert_proposal_snapshot(&mut tx, &snapshot)
         .await
@@ -140,6 +154,7 @@ pub async fn handle_edit_proposal(
         eprintln!("Failed to commit transaction: {:?}", e);
         anyhow::anyhow!("Failed to commit transaction")
     })?;
+    invalidate();
 
     Ok(())
 }
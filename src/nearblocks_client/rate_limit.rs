@@ -0,0 +1,210 @@
+use reqwest::header::HeaderMap;
+use reqwest::StatusCode;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+
+/// NearBlocks' documented quota for API keys.
+pub const DEFAULT_CALLS_PER_MINUTE: u32 = 150;
+
+#[derive(Debug)]
+struct LimiterState {
+    // Earliest time the next call may start
+    next_call: Instant,
+    // Set from Retry-After or an exhausted rate-limit window
+    blocked_until: Option<Instant>,
+}
+
+/// Spaces calls evenly over the per-minute quota and holds them back while the server
+/// asks us to wait. Clones share the same budget.
+#[derive(Clone, Debug)]
+pub struct RateLimiter {
+    interval: Duration,
+    state: Arc<Mutex<LimiterState>>,
+}
+
+impl RateLimiter {
+    pub fn per_minute(calls: u32) -> Self {
+        Self {
+            interval: Duration::from_secs(60) / calls.max(1),
+            state: Arc::new(Mutex::new(LimiterState {
+                next_call: Instant::now(),
+                blocked_until: None,
+            })),
+        }
+    }
+
+    /// The limiter clients use unless given their own. NearBlocks counts calls per API
+    /// key, so every client in the process draws from this one budget.
+    pub fn shared() -> Self {
+        static SHARED: OnceLock<RateLimiter> = OnceLock::new();
+        SHARED.get_or_init(RateLimiter::default).clone()
+    }
+
+    /// Waits until the next call is allowed.
+    pub async fn acquire(&self) {
+        let start = {
+            let mut state = self.state.lock().await;
+            let now = Instant::now();
+            let mut start = state.next_call.max(now);
+            if let Some(blocked_until) = state.blocked_until.take() {
+                start = start.max(blocked_until);
+            }
+            state.next_call = start + self.interval;
+            start
+        };
+        tokio::time::sleep_until(start).await;
+    }
+
+    /// Holds back every call for `delay`.
+    pub async fn pause(&self, delay: Duration) {
+        let until = Instant::now() + delay;
+        let mut state = self.state.lock().await;
+        state.blocked_until = Some(state.blocked_until.map_or(until, |u| u.max(until)));
+    }
+
+    /// Pauses until the window resets when the response says no calls are left in it.
+    pub async fn observe(&self, headers: &HeaderMap) {
+        if header_number(headers, "x-ratelimit-remaining") == Some(0) {
+            if let Some(reset) = header_number(headers, "x-ratelimit-reset") {
+                self.pause(reset_delay(reset)).await;
+            }
+        }
+    }
+}
+
+impl Default for RateLimiter {
+    fn default() -> Self {
+        Self::per_minute(DEFAULT_CALLS_PER_MINUTE)
+    }
+}
+
+fn header_number(headers: &HeaderMap, name: &str) -> Option<u64> {
+    headers.get(name)?.to_str().ok()?.trim().parse().ok()
+}
+
+// The reset header is either seconds until the reset or a unix timestamp. A window is a
+// minute or so, while timestamps are past 1.7e9, so anything above half of the current
+// unix time (about 27 years in seconds) can only be a timestamp.
+fn reset_delay(reset: u64) -> Duration {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    if reset > now / 2 {
+        Duration::from_secs(reset.saturating_sub(now))
+    } else {
+        Duration::from_secs(reset)
+    }
+}
+
+/// `Retry-After` in seconds; HTTP dates are not used by NearBlocks.
+pub fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    header_number(headers, "retry-after").map(Duration::from_secs)
+}
+
+/// Whether a failed request may succeed when repeated.
+pub fn is_transient(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS
+        || status == StatusCode::REQUEST_TIMEOUT
+        || status.is_server_error()
+}
+
+/// Exponential backoff with full jitter for transient errors.
+#[derive(Clone, Debug)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 5,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// A random delay of up to `base_delay * 2^attempt`, capped at `max_delay`.
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let ceiling = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.max_delay);
+        ceiling.mul_f64(jitter())
+    }
+}
+
+// Good enough randomness for spreading out retries, without pulling in `rand`
+fn jitter() -> f64 {
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .subsec_nanos();
+    let mixed = nanos.wrapping_mul(2_654_435_761);
+    mixed as f64 / u32::MAX as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    #[test]
+    fn test_retry_delay_is_capped_and_jittered() {
+        let policy = RetryPolicy {
+            max_retries: 3,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(1),
+        };
+        assert!(policy.delay(0) <= Duration::from_millis(100));
+        assert!(policy.delay(2) <= Duration::from_millis(400));
+        assert!(policy.delay(30) <= Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_transient_statuses() {
+        assert!(is_transient(StatusCode::TOO_MANY_REQUESTS));
+        assert!(is_transient(StatusCode::BAD_GATEWAY));
+        assert!(!is_transient(StatusCode::UNAUTHORIZED));
+        assert!(!is_transient(StatusCode::NOT_FOUND));
+    }
+
+    #[test]
+    fn test_retry_after_and_reset_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert("retry-after", HeaderValue::from_static("3"));
+        assert_eq!(retry_after(&headers), Some(Duration::from_secs(3)));
+
+        assert_eq!(reset_delay(20), Duration::from_secs(20));
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        assert!(reset_delay(now + 10) <= Duration::from_secs(10));
+    }
+
+    #[test]
+    fn test_shared_limiter_is_one_budget() {
+        let a = RateLimiter::shared();
+        let b = RateLimiter::shared();
+        assert!(Arc::ptr_eq(&a.state, &b.state));
+        assert!(!Arc::ptr_eq(&a.state, &RateLimiter::default().state));
+    }
+
+    #[tokio::test]
+    async fn test_calls_are_spaced_by_the_quota() {
+        let limiter = RateLimiter::per_minute(600);
+        let start = Instant::now();
+        for _ in 0..3 {
+            limiter.acquire().await;
+        }
+        // 100ms apart, the first call goes immediately
+        assert!(start.elapsed() >= Duration::from_millis(200));
+    }
+}
@@ -1,8 +1,9 @@
+use crate::db::db_types::RfpSnapshotRecord;
 use crate::db::DB;
+use crate::entrypoints::cache::invalidate;
 use crate::entrypoints::rfp::rfp_types::*;
 use crate::nearblocks_client::types::Transaction;
-use crate::rpc_service::RpcService;
-use crate::{db::db_types::RfpSnapshotRecord, nearblocks_client::types::BLOCK_HEIGHT_OFFSET};
+use crate::rpc_service::{rfp_snapshot_since, RpcService};
 use devhub_shared::rfp::VersionedRFP;
 use rocket::State;
 
@@ -34,23 +35,33 @@ pub async fn handle_set_rfp_block_height_callback(
 
     let id = args.clone().rfp.id.try_into().unwrap();
 
+    // The RFP exists from the callback's block on, so the first readable block is it
     let versioned_rfp_fallback: VersionedRFP = args.clone().rfp.into();
-    let versioned_rfp = match rpc_service.get_rfp(id).await {
-        Ok(rfp) => rfp.data,
+    let (versioned_rfp, state_block_height, approximate) = match rpc_service
+        .get_rfp_after_receipt(id, transaction.receipt_block.block_height, |_| true)
+        .await
+    {
+        Ok(read) => (
+            read.state.data,
+            Some(read.state.block_height as i64),
+            read.approximate,
+        ),
         Err(e) => {
             eprintln!(
                 "Failed to get RFP from RPC, using first snapshot as fallback {:?}",
                 e
             );
-            versioned_rfp_fallback
+            (versioned_rfp_fallback, None, false)
         }
     };
 
-    let snapshot = RfpSnapshotRecord::from_contract_rfp(
+    let mut snapshot = RfpSnapshotRecord::from_contract_rfp(
         versioned_rfp.into(),
         transaction.block_timestamp.parse::<i64>().unwrap(),
         transaction.block.block_height,
     );
+    snapshot.state_block_height = state_block_height;
+    snapshot.approximate = approximate;
 
     DB::insert_rfp_snapshot(&mut tx, &snapshot).await.unwrap();
 
@@ -64,6 +75,7 @@ pub async fn handle_set_rfp_block_height_callback(
     tx.commit()
         .await
         .map_err(|_e| anyhow::anyhow!("Failed to commit transaction"))?;
+    invalidate();
 
     Ok(())
 }
@@ -94,11 +106,11 @@ pub async fn handle_edit_rfp(
         anyhow::anyhow!("Failed to get RFP ID")
     })?;
     println!("Updating rfp {}", id);
+    let receipt_timestamp = transaction.receipt_block.block_timestamp as u64;
     let versioned_rfp = match rpc_service
-        .get_rfp_on_block(
-            id,
-            transaction.receipt_block.block_height + BLOCK_HEIGHT_OFFSET,
-        )
+        .get_rfp_after_receipt(id, transaction.receipt_block.block_height, |rfp| {
+            rfp_snapshot_since(rfp, receipt_timestamp)
+        })
         .await
     {
         Ok(rfp) => rfp,
@@ -113,17 +125,19 @@ pub async fn handle_edit_rfp(
         .await
         .map_err(|_e| anyhow::anyhow!("Failed to begin transaction"))?;
 
-    let contract_rfp: ContractRFP = versioned_rfp.clone().into();
+    let contract_rfp: ContractRFP = versioned_rfp.state.data.clone().into();
     println!(
         "RFP {} timestamp {}",
         contract_rfp.id, transaction.block_timestamp
     );
 
-    let snapshot = RfpSnapshotRecord::from_contract_rfp(
-        versioned_rfp.into(),
+    let mut snapshot = RfpSnapshotRecord::from_contract_rfp(
+        versioned_rfp.state.data.into(),
         transaction.block_timestamp.parse::<i64>().unwrap(),
         transaction.block.block_height,
     );
+    snapshot.state_block_height = Some(versioned_rfp.state.block_height as i64);
+    snapshot.approximate = versioned_rfp.approximate;
 
     DB::insert_rfp_snapshot(&mut tx, &snapshot)
         .await
@@ -139,6 +153,7 @@ pub async fn handle_edit_rfp(
     tx.commit()
         .await
         .map_err(|_e| anyhow::anyhow!("Failed to commit transaction"))?;
+    invalidate();
 
     Ok(())
 }
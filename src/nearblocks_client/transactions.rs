@@ -3,98 +3,186 @@ use crate::nearblocks_client;
 use crate::nearblocks_client::proposal::{handle_edit_proposal, handle_set_block_height_callback};
 use crate::nearblocks_client::rfp::{handle_edit_rfp, handle_set_rfp_block_height_callback};
 use crate::nearblocks_client::types::Transaction;
+use crate::nearblocks_client::ApiResponse;
 use crate::rpc_service::{Env, RpcService};
+use futures::{stream, Stream, StreamExt};
 use near_account_id::AccountId;
 use rocket::State;
+use tokio::sync::mpsc;
 
-pub async fn fetch_all_new_transactions(
-    nearblocks_client: &nearblocks_client::ApiClient,
-    after_block: Option<i64>,
-    max_transactions: Option<usize>,
-) -> anyhow::Result<(Vec<Transaction>, String)> {
-    let mut all_transactions = Vec::new();
-    let mut current_cursor = "".to_string();
+/// Pages fetched ahead of the one being processed during a sync.
+pub const SYNC_PAGE_BUFFER: usize = 2;
+
+/// How long a page waits for its blocks to become final before the sync stops.
+const FINALITY_WAIT: std::time::Duration = std::time::Duration::from_secs(30);
+const FINALITY_POLL: std::time::Duration = std::time::Duration::from_secs(1);
 
+// A second sync would resume from the same cursor and process every page twice
+static SYNC_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+fn sync_contract() -> AccountId {
     dotenvy::dotenv().ok();
     let env: Env = envy::from_env::<Env>().expect("Failed to load environment variables");
-    let contract: AccountId = env.contract.parse().expect("Failed to parse contract");
-
-    loop {
-        let response = match nearblocks_client
-            .get_account_txns_by_pagination(
-                contract.clone(),
-                current_cursor.clone(),
-                Some(25),
-                Some("asc".to_string()),
-                Some(1),
-                after_block,
-            )
-            .await
-        {
-            Ok(response) => response,
-            Err(e) => {
-                eprintln!("Failed to fetch transactions from nearblocks: {:?}", e);
-                break;
-            }
-        };
+    env.contract.parse().expect("Failed to parse contract")
+}
 
-        println!(
-            "Fetched {} transactions from nearblocks",
-            response.txns.len()
-        );
+async fn fetch_transactions_page(
+    nearblocks_client: &nearblocks_client::ApiClient,
+    contract: &AccountId,
+    cursor: &str,
+    after_block: Option<i64>,
+) -> anyhow::Result<ApiResponse> {
+    let response = nearblocks_client
+        .get_account_txns_by_pagination(
+            contract.clone(),
+            cursor.to_string(),
+            Some(25),
+            Some("asc".to_string()),
+            Some(1),
+            after_block,
+        )
+        .await?;
+    println!(
+        "Fetched {} transactions from nearblocks",
+        response.txns.len()
+    );
+    Ok(response)
+}
 
-        if let Some(max_transactions) = max_transactions {
-            if all_transactions.len() >= max_transactions {
-                println!("Reached max transactions, finished fetching transactions");
-                break;
+/// Pages of contract transactions from NearBlocks, starting at `cursor` (or at
+/// `after_block` when it is empty). The stream ends after the last page or the first error.
+pub fn transaction_pages(
+    nearblocks_client: nearblocks_client::ApiClient,
+    contract: AccountId,
+    cursor: String,
+    after_block: Option<i64>,
+) -> impl Stream<Item = anyhow::Result<ApiResponse>> {
+    // The client's rate limiter keeps the calls within the NearBlocks quota
+    stream::unfold(Some(cursor), move |cursor| {
+        let nearblocks_client = nearblocks_client.clone();
+        let contract = contract.clone();
+        async move {
+            let cursor = cursor?;
+            match fetch_transactions_page(&nearblocks_client, &contract, &cursor, after_block).await
+            {
+                Ok(page) => {
+                    let next = page.cursor.clone();
+                    Some((Ok(page), next))
+                }
+                Err(e) => Some((Err(e), None)),
             }
         }
+    })
+}
 
-        // Check if we've wrapped around or reached the end
-        if response.cursor.is_none() {
-            println!("Cursor has wrapped around, finished fetching transactions");
-            all_transactions.extend(response.txns);
-            current_cursor = "None".to_string();
-            break;
+/// Fetches pages in a background task while the caller processes earlier ones, holding at
+/// most `SYNC_PAGE_BUFFER` pages. Dropping the receiver stops the fetching.
+pub fn prefetch_transaction_pages(
+    nearblocks_client: nearblocks_client::ApiClient,
+    contract: AccountId,
+    cursor: String,
+    after_block: Option<i64>,
+) -> mpsc::Receiver<anyhow::Result<ApiResponse>> {
+    let (sender, receiver) = mpsc::channel(SYNC_PAGE_BUFFER);
+    tokio::spawn(async move {
+        let pages = transaction_pages(nearblocks_client, contract, cursor, after_block);
+        futures::pin_mut!(pages);
+        while let Some(page) = pages.next().await {
+            if sender.send(page).await.is_err() {
+                break;
+            }
         }
+    });
+    receiver
+}
 
-        // Add transactions to our collection
-        all_transactions.extend(response.txns);
-
-        // Update cursor for next iteration
-        current_cursor = response.cursor.unwrap();
-
-        // We only use the nearblocks api for the first sync. It has 150 calls / minute limit
-        // So we sleep for 500ms to avoid hitting the limit. We don't expect to run
-        // this more than once or twice after initialization
-        tokio::time::sleep(std::time::Duration::from_millis(750)).await;
-    }
-
-    Ok((all_transactions, current_cursor))
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SyncOutcome {
+    /// The last page was processed.
+    Finished,
+    /// Stopped at `max_transactions`; the next sync resumes from the checkpoint.
+    Paused,
+    /// The job running the sync was cancelled; the next sync resumes from the checkpoint.
+    Cancelled,
 }
 
+/// Syncs contract transactions from NearBlocks, processing and checkpointing each page as
+/// it arrives while the next ones are fetched. With `after_block` a new sync starts from
+/// that block; without it an unfinished sync resumes from its stored cursor, or a new one
+/// starts from the last updated block. When run by a job, progress is reported to `job_id`
+/// after every page and the sync stops once the job is cancelled.
 pub async fn update_nearblocks_data(
     db: &State<DB>,
     rpc_service: &State<RpcService>,
+    nearblocks_client: &nearblocks_client::ApiClient,
     after_block: Option<i64>,
     max_transactions: Option<usize>,
-) -> anyhow::Result<()> {
-    let nearblocks_client = nearblocks_client::ApiClient::new();
-
-    let (all_transactions, _current_cursor) =
-        match fetch_all_new_transactions(&nearblocks_client, after_block, max_transactions).await {
-            Ok(result) => result,
-            Err(e) => {
-                eprintln!("Error fetching transactions: {:?}", e);
-                return Err(anyhow::anyhow!("Error fetching transactions: {:?}", e));
+    job_id: Option<i32>,
+) -> anyhow::Result<SyncOutcome> {
+    let Ok(_guard) = SYNC_LOCK.try_lock() else {
+        return Err(anyhow::anyhow!("A NearBlocks sync is already running"));
+    };
+    let contract = sync_contract();
+
+    let info = db.get_last_updated_info().await?;
+    let (cursor, mut pages_fetched, after_block) = match after_block {
+        None if !info.cursor.is_empty() => {
+            println!("Resuming NearBlocks sync from cursor {}", info.cursor);
+            (info.cursor, info.sync_pages_fetched, None)
+        }
+        after_block => {
+            db.start_nearblocks_sync().await?;
+            ("".to_string(), 0, after_block.or(Some(info.after_block)))
+        }
+    };
+
+    let mut transactions_fetched = 0;
+    let mut outcome = SyncOutcome::Finished;
+    let mut pages =
+        prefetch_transaction_pages(nearblocks_client.clone(), contract, cursor, after_block);
+    while let Some(page) = pages.recv().await {
+        let response = page.map_err(|e| {
+            eprintln!("Failed to fetch transactions from nearblocks: {:?}", e);
+            anyhow::anyhow!("Error fetching transactions: {:?}", e)
+        })?;
+        let page_transactions = response.txns.len();
+        transactions_fetched += page_transactions;
+
+        process(&response.txns, db, rpc_service).await?;
+
+        pages_fetched += 1;
+        let last_block = response.txns.last().map(|t| t.block.block_height);
+        let cursor = response.cursor.unwrap_or_default();
+        db.checkpoint_nearblocks_sync(&cursor, pages_fetched, last_block)
+            .await?;
+
+        let cancelled = match job_id {
+            Some(job_id) => {
+                db.update_job_progress(job_id, page_transactions as i64, last_block)
+                    .await?
             }
+            None => false,
         };
 
-    println!("Total transactions fetched: {}", all_transactions.len());
+        if cursor.is_empty() {
+            println!("Finished NearBlocks sync after {} pages", pages_fetched);
+            break;
+        }
+        if cancelled {
+            println!("Job cancelled, stopping NearBlocks sync");
+            outcome = SyncOutcome::Cancelled;
+            break;
+        }
+        if max_transactions.is_some_and(|max| transactions_fetched >= max) {
+            println!("Reached max transactions, pausing NearBlocks sync");
+            outcome = SyncOutcome::Paused;
+            break;
+        }
+    }
 
-    nearblocks_client::transactions::process(&all_transactions, db, rpc_service).await?;
+    println!("Total transactions fetched: {}", transactions_fetched);
 
-    Ok(())
+    Ok(outcome)
 }
 
 fn is_fatal_error(error: &anyhow::Error) -> bool {
@@ -105,11 +193,39 @@ fn is_fatal_error(error: &anyhow::Error) -> bool {
     !non_fatal_errors.iter().any(|&msg| error_msg.contains(msg))
 }
 
+/// Waits until `block_height` is final so nothing from a block that can still be
+/// reorganised away gets committed.
+async fn wait_for_finality(rpc_service: &RpcService, block_height: i64) -> anyhow::Result<()> {
+    let deadline = tokio::time::Instant::now() + FINALITY_WAIT;
+    loop {
+        let final_height = rpc_service.get_final_height().await?;
+        if final_height as i64 >= block_height {
+            return Ok(());
+        }
+        if tokio::time::Instant::now() >= deadline {
+            return Err(anyhow::anyhow!(
+                "Block {} is not final yet (final head is {})",
+                block_height,
+                final_height
+            ));
+        }
+        tokio::time::sleep(FINALITY_POLL).await;
+    }
+}
+
 pub async fn process(
     transactions: &[Transaction],
     db: &State<DB>,
     rpc_service: &State<RpcService>,
 ) -> anyhow::Result<()> {
+    let latest_block = transactions
+        .iter()
+        .map(|t| t.block.block_height.max(t.receipt_block.block_height))
+        .max();
+    if let Some(block_height) = latest_block {
+        wait_for_finality(rpc_service, block_height).await?;
+    }
+
     for transaction in transactions.iter() {
         if let Some(action) = transaction
             .actions
@@ -154,7 +270,8 @@ pub async fn process(
             if let Err(e) = result {
                 if is_fatal_error(&e) {
                     eprintln!("Fatal error, stopping: {:?}", e);
-                    return Ok(());
+                    // The sync checkpoint must not move past this transaction
+                    return Err(e);
                 } else {
                     eprintln!("Non-fatal error, continuing: {:?}", e);
                     continue;
@@ -173,12 +290,20 @@ mod tests {
 
     #[tokio::test]
     #[ignore]
-    async fn test_fetch_all_transactions() {
+    async fn test_transaction_pages() {
         let client = nearblocks_client::ApiClient::new();
-        let (transactions, current_cursor) =
-            fetch_all_new_transactions(&client, Some(0), Some(601))
-                .await
-                .expect("Error fetching transactions");
+        let contract: AccountId = "devhub.near".parse().unwrap();
+        let pages = transaction_pages(client, contract, "".to_string(), Some(0));
+        futures::pin_mut!(pages);
+
+        let mut transactions = Vec::new();
+        while let Some(page) = pages.next().await {
+            let page = page.expect("Error fetching transactions");
+            transactions.extend(page.txns);
+            if transactions.len() > 600 {
+                break;
+            }
+        }
 
         // Check total count
         assert!(
@@ -187,12 +312,6 @@ mod tests {
             transactions.len()
         );
 
-        assert!(
-            current_cursor == "None",
-            "Current cursor should be None but is >{}<",
-            current_cursor
-        );
-
         // Check for duplicates
         let mut seen_transactions = HashSet::new();
         let mut duplicates = Vec::new();
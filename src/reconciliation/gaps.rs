@@ -0,0 +1,128 @@
+use super::types::{EntityGaps, GapBackfill, GapConfig, GapReport};
+use super::{fetch_proposal_snapshot, fetch_rfp_snapshot, repair_proposal, repair_rfp};
+use crate::db::DB;
+use crate::rpc_service::RpcService;
+use futures::{stream, StreamExt};
+use std::time::Duration;
+
+/// Compares the contract's proposal and RFP ids with the cached snapshots.
+pub async fn detect_gaps(db: &DB, rpc_service: &RpcService) -> anyhow::Result<GapReport> {
+    let proposal_ids = rpc_service
+        .get_all_proposal_ids()
+        .await
+        .map_err(|_| anyhow::anyhow!("Failed to get proposal ids from RPC"))?;
+    let rfp_ids = rpc_service
+        .get_all_rfp_ids()
+        .await
+        .map_err(|_| anyhow::anyhow!("Failed to get rfp ids from RPC"))?;
+
+    Ok(GapReport {
+        proposals: EntityGaps {
+            on_chain: proposal_ids.len(),
+            missing: db.get_missing_proposal_ids(&proposal_ids).await?,
+            ..Default::default()
+        },
+        rfps: EntityGaps {
+            on_chain: rfp_ids.len(),
+            missing: db.get_missing_rfp_ids(&rfp_ids).await?,
+            ..Default::default()
+        },
+        checked_at: chrono::Utc::now(),
+    })
+}
+
+/// Fetches every missing id in the report from RPC and caches it, recording which
+/// ones were backfilled and which failed.
+pub async fn backfill_gaps(
+    db: &DB,
+    rpc_service: &RpcService,
+    concurrency: usize,
+    report: &mut GapReport,
+) {
+    let mut fetched = stream::iter(report.proposals.missing.clone())
+        .map(|id| async move { (id, fetch_proposal_snapshot(rpc_service, id).await) })
+        .buffer_unordered(concurrency.max(1));
+    while let Some((id, result)) = fetched.next().await {
+        let stored = match result {
+            Ok((author_id, snapshot)) => repair_proposal(db, author_id, &snapshot)
+                .await
+                .map_err(|e| e.to_string()),
+            Err(e) => Err(e),
+        };
+        match stored {
+            Ok(()) => report.proposals.backfilled.push(id),
+            Err(e) => {
+                eprintln!("Failed to backfill proposal {}: {}", id, e);
+                report.proposals.failed.push(id);
+            }
+        }
+    }
+
+    let mut fetched = stream::iter(report.rfps.missing.clone())
+        .map(|id| async move { (id, fetch_rfp_snapshot(rpc_service, id).await) })
+        .buffer_unordered(concurrency.max(1));
+    while let Some((id, result)) = fetched.next().await {
+        let stored = match result {
+            Ok((author_id, snapshot)) => repair_rfp(db, author_id, &snapshot)
+                .await
+                .map_err(|e| e.to_string()),
+            Err(e) => Err(e),
+        };
+        match stored {
+            Ok(()) => report.rfps.backfilled.push(id),
+            Err(e) => {
+                eprintln!("Failed to backfill rfp {}: {}", id, e);
+                report.rfps.failed.push(id);
+            }
+        }
+    }
+
+    report.proposals.backfilled.sort();
+    report.proposals.failed.sort();
+    report.rfps.backfilled.sort();
+    report.rfps.failed.sort();
+}
+
+/// Checks for gaps and backfills them, recording the outcome in `backfill`, which the
+/// caller has started.
+pub async fn run_gap_backfill(
+    db: DB,
+    rpc_service: RpcService,
+    concurrency: usize,
+    backfill: GapBackfill,
+) {
+    let result = match detect_gaps(&db, &rpc_service).await {
+        Ok(mut report) => {
+            backfill_gaps(&db, &rpc_service, concurrency, &mut report).await;
+            Ok(report)
+        }
+        Err(e) => {
+            eprintln!("Failed to check for id gaps: {:?}", e);
+            Err(e)
+        }
+    };
+    backfill.finish(result);
+}
+
+pub async fn run_gap_monitor(db: DB, rpc_service: RpcService, config: GapConfig) {
+    loop {
+        tokio::time::sleep(Duration::from_secs(config.interval_secs)).await;
+        let mut report = match detect_gaps(&db, &rpc_service).await {
+            Ok(report) => report,
+            Err(e) => {
+                eprintln!("Failed to check for id gaps: {:?}", e);
+                continue;
+            }
+        };
+        if !report.has_gaps() {
+            continue;
+        }
+        println!(
+            "Missing proposals {:?} and rfps {:?}",
+            report.proposals.missing, report.rfps.missing
+        );
+        if config.backfill {
+            backfill_gaps(&db, &rpc_service, config.concurrency, &mut report).await;
+        }
+    }
+}
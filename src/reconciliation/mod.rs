@@ -0,0 +1,387 @@
+use self::types::*;
+use crate::db::db_types::{
+    ProposalSnapshotRecord, ProposalWithLatestSnapshotView, RfpSnapshotRecord,
+    RfpWithLatestSnapshotView,
+};
+use crate::db::DB;
+use crate::entrypoints::cache::invalidate;
+use crate::entrypoints::links::links_types::EntityKind;
+use crate::entrypoints::proposal::proposal_types::FromContractProposal;
+use crate::entrypoints::rfp::rfp_types::FromContractRFP;
+use crate::rpc_service::RpcService;
+use devhub_shared::proposal::Proposal;
+use devhub_shared::rfp::RFP;
+use futures::{stream, StreamExt};
+use near_api::types::reference::Reference;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+pub mod gaps;
+pub mod types;
+
+// Snapshot fields that come from contract state; block heights and timestamps depend
+// on when a change was indexed, so they are not compared
+const PROPOSAL_FIELDS: &[&str] = &[
+    "editor_id",
+    "labels",
+    "name",
+    "category",
+    "summary",
+    "description",
+    "linked_proposals",
+    "linked_rfp",
+    "requested_sponsorship_usd_amount",
+    "requested_sponsorship_paid_in_currency",
+    "requested_sponsor",
+    "receiver_account",
+    "supervisor",
+    "timeline",
+];
+
+const RFP_FIELDS: &[&str] = &[
+    "editor_id",
+    "labels",
+    "name",
+    "summary",
+    "description",
+    "linked_proposals",
+    "timeline",
+    "submission_deadline",
+];
+
+// Labels come from a set on chain and timelines are stored as JSON strings, so both
+// are normalized before comparing
+fn normalized_fields<T: Serialize>(record: &T) -> serde_json::Map<String, Value> {
+    let Ok(Value::Object(mut fields)) = serde_json::to_value(record) else {
+        return serde_json::Map::new();
+    };
+    if let Some(Value::Array(labels)) = fields.get_mut("labels") {
+        labels.sort_by_key(|label| label.to_string());
+    }
+    if let Some(Value::String(timeline)) = fields.get("timeline") {
+        if let Ok(timeline) = serde_json::from_str::<Value>(timeline) {
+            fields.insert("timeline".to_string(), timeline);
+        }
+    }
+    fields
+}
+
+fn compare_fields<C: Serialize, R: Serialize>(
+    names: &[&str],
+    cached: &C,
+    rpc: &R,
+) -> Vec<FieldMismatch> {
+    let cached = normalized_fields(cached);
+    let rpc = normalized_fields(rpc);
+    names
+        .iter()
+        .filter_map(|name| {
+            let cached = cached.get(*name).cloned().unwrap_or(Value::Null);
+            let rpc = rpc.get(*name).cloned().unwrap_or(Value::Null);
+            (cached != rpc).then(|| FieldMismatch {
+                field: name.to_string(),
+                cached,
+                rpc,
+            })
+        })
+        .collect()
+}
+
+pub fn compare_proposal(
+    cached: &ProposalWithLatestSnapshotView,
+    rpc: &ProposalSnapshotRecord,
+) -> Vec<FieldMismatch> {
+    compare_fields(PROPOSAL_FIELDS, cached, rpc)
+}
+
+pub fn compare_rfp(
+    cached: &RfpWithLatestSnapshotView,
+    rpc: &RfpSnapshotRecord,
+) -> Vec<FieldMismatch> {
+    compare_fields(RFP_FIELDS, cached, rpc)
+}
+
+/// Reads a proposal from the contract as a snapshot, with its author.
+pub async fn fetch_proposal_snapshot(
+    rpc_service: &RpcService,
+    id: i32,
+) -> Result<(String, ProposalSnapshotRecord), String> {
+    let data = rpc_service
+        .get_proposal_at(id, Reference::Final)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    let proposal: Proposal = data.data.into();
+    let author_id = proposal.author_id.to_string();
+    let timestamp = proposal.snapshot.timestamp as i64;
+    let mut snapshot = ProposalSnapshotRecord::from_contract_proposal(
+        proposal,
+        timestamp,
+        data.block_height as i64,
+    );
+    snapshot.state_block_height = Some(data.block_height as i64);
+    Ok((author_id, snapshot))
+}
+
+/// Reads an RFP from the contract as a snapshot, with its author.
+pub async fn fetch_rfp_snapshot(
+    rpc_service: &RpcService,
+    id: i32,
+) -> Result<(String, RfpSnapshotRecord), String> {
+    let data = rpc_service
+        .get_rfp_at(id, Reference::Final)
+        .await
+        .map_err(|e| format!("{:?}", e))?;
+    let rfp: RFP = data.data.into();
+    let author_id = rfp.author_id.to_string();
+    let timestamp = rfp.snapshot.timestamp as i64;
+    let mut snapshot =
+        RfpSnapshotRecord::from_contract_rfp(rfp, timestamp, data.block_height as i64);
+    snapshot.state_block_height = Some(data.block_height as i64);
+    Ok((author_id, snapshot))
+}
+
+pub(crate) async fn repair_proposal(
+    db: &DB,
+    author_id: String,
+    snapshot: &ProposalSnapshotRecord,
+) -> anyhow::Result<()> {
+    let mut tx = db.begin().await?;
+    DB::upsert_proposal(&mut tx, snapshot.proposal_id as u32, author_id).await?;
+    DB::insert_proposal_snapshot(&mut tx, snapshot).await?;
+    tx.commit().await?;
+    invalidate();
+    Ok(())
+}
+
+pub(crate) async fn repair_rfp(
+    db: &DB,
+    author_id: String,
+    snapshot: &RfpSnapshotRecord,
+) -> anyhow::Result<()> {
+    let mut tx = db.begin().await?;
+    DB::upsert_rfp(&mut tx, snapshot.rfp_id as u32, author_id).await?;
+    DB::insert_rfp_snapshot(&mut tx, snapshot).await?;
+    tx.commit().await?;
+    invalidate();
+    Ok(())
+}
+
+async fn reconcile_proposals(
+    db: &DB,
+    rpc_service: &RpcService,
+    config: &ReconciliationConfig,
+    repair: bool,
+    report: &mut ReconciliationReport,
+) -> anyhow::Result<()> {
+    let chain_ids: HashSet<i32> = rpc_service
+        .get_all_proposal_ids()
+        .await
+        .map_err(|_| anyhow::anyhow!("Failed to get proposal ids from RPC"))?
+        .into_iter()
+        .collect();
+    let cached: HashMap<i32, ProposalWithLatestSnapshotView> = db
+        .get_all_proposals_with_latest_snapshot()
+        .await?
+        .into_iter()
+        .map(|proposal| (proposal.proposal_id, proposal))
+        .collect();
+
+    let mut issues: Vec<ReconciliationIssue> = cached
+        .keys()
+        .filter(|id| !chain_ids.contains(id))
+        .map(|id| ReconciliationIssue::new(EntityKind::Proposal, *id, IssueKind::MissingOnChain))
+        .collect();
+
+    let mut fetched = stream::iter(chain_ids.iter().copied())
+        .map(|id| async move { (id, fetch_proposal_snapshot(rpc_service, id).await) })
+        .buffer_unordered(config.concurrency.max(1));
+    while let Some((id, result)) = fetched.next().await {
+        report.checked_proposals += 1;
+        let (author_id, mut snapshot) = match result {
+            Ok(fetched) => fetched,
+            Err(e) => {
+                let mut issue =
+                    ReconciliationIssue::new(EntityKind::Proposal, id, IssueKind::RpcError);
+                issue.error = Some(e);
+                issues.push(issue);
+                continue;
+            }
+        };
+
+        let mut issue = match cached.get(&id) {
+            None => ReconciliationIssue::new(EntityKind::Proposal, id, IssueKind::MissingInCache),
+            Some(cached) => {
+                let fields = compare_proposal(cached, &snapshot);
+                if fields.is_empty() {
+                    continue;
+                }
+                // Never insert behind the cached latest snapshot, or it wouldn't be read
+                snapshot.ts = snapshot.ts.max(cached.ts.unwrap_or_default());
+                let mut issue =
+                    ReconciliationIssue::new(EntityKind::Proposal, id, IssueKind::FieldMismatch);
+                issue.fields = fields;
+                issue
+            }
+        };
+        if repair {
+            match repair_proposal(db, author_id, &snapshot).await {
+                Ok(()) => issue.repaired = true,
+                Err(e) => {
+                    eprintln!("Failed to repair proposal {}: {:?}", id, e);
+                    issue.error = Some(format!("Repair failed: {}", e));
+                }
+            }
+        }
+        issues.push(issue);
+    }
+
+    issues.sort_by_key(|issue| issue.id);
+    report.issues.extend(issues);
+    Ok(())
+}
+
+async fn reconcile_rfps(
+    db: &DB,
+    rpc_service: &RpcService,
+    config: &ReconciliationConfig,
+    repair: bool,
+    report: &mut ReconciliationReport,
+) -> anyhow::Result<()> {
+    let chain_ids: HashSet<i32> = rpc_service
+        .get_all_rfp_ids()
+        .await
+        .map_err(|_| anyhow::anyhow!("Failed to get rfp ids from RPC"))?
+        .into_iter()
+        .collect();
+    let cached: HashMap<i32, RfpWithLatestSnapshotView> = db
+        .get_all_rfps_with_latest_snapshot()
+        .await?
+        .into_iter()
+        .map(|rfp| (rfp.rfp_id, rfp))
+        .collect();
+
+    let mut issues: Vec<ReconciliationIssue> = cached
+        .keys()
+        .filter(|id| !chain_ids.contains(id))
+        .map(|id| ReconciliationIssue::new(EntityKind::Rfp, *id, IssueKind::MissingOnChain))
+        .collect();
+
+    let mut fetched = stream::iter(chain_ids.iter().copied())
+        .map(|id| async move { (id, fetch_rfp_snapshot(rpc_service, id).await) })
+        .buffer_unordered(config.concurrency.max(1));
+    while let Some((id, result)) = fetched.next().await {
+        report.checked_rfps += 1;
+        let (author_id, mut snapshot) = match result {
+            Ok(fetched) => fetched,
+            Err(e) => {
+                let mut issue = ReconciliationIssue::new(EntityKind::Rfp, id, IssueKind::RpcError);
+                issue.error = Some(e);
+                issues.push(issue);
+                continue;
+            }
+        };
+
+        let mut issue = match cached.get(&id) {
+            None => ReconciliationIssue::new(EntityKind::Rfp, id, IssueKind::MissingInCache),
+            Some(cached) => {
+                let fields = compare_rfp(cached, &snapshot);
+                if fields.is_empty() {
+                    continue;
+                }
+                snapshot.ts = snapshot.ts.max(cached.ts);
+                let mut issue =
+                    ReconciliationIssue::new(EntityKind::Rfp, id, IssueKind::FieldMismatch);
+                issue.fields = fields;
+                issue
+            }
+        };
+        if repair {
+            match repair_rfp(db, author_id, &snapshot).await {
+                Ok(()) => issue.repaired = true,
+                Err(e) => {
+                    eprintln!("Failed to repair rfp {}: {:?}", id, e);
+                    issue.error = Some(format!("Repair failed: {}", e));
+                }
+            }
+        }
+        issues.push(issue);
+    }
+
+    issues.sort_by_key(|issue| issue.id);
+    report.issues.extend(issues);
+    Ok(())
+}
+
+/// Compares every cached proposal and RFP with the contract and, with `repair`, inserts a
+/// fresh snapshot for each one that is missing or differs.
+pub async fn reconcile(
+    db: &DB,
+    rpc_service: &RpcService,
+    config: &ReconciliationConfig,
+    repair: bool,
+) -> (ReconciliationReport, Option<String>) {
+    let mut report = ReconciliationReport::default();
+    let mut error = None;
+    if let Err(e) = reconcile_proposals(db, rpc_service, config, repair, &mut report).await {
+        eprintln!("Failed to reconcile proposals: {:?}", e);
+        error = Some(e.to_string());
+    }
+    if let Err(e) = reconcile_rfps(db, rpc_service, config, repair, &mut report).await {
+        eprintln!("Failed to reconcile rfps: {:?}", e);
+        error = Some(e.to_string());
+    }
+    (report, error)
+}
+
+/// Runs reconciliation for an already created run and stores its report.
+pub async fn execute_reconciliation_run(
+    db: DB,
+    rpc_service: RpcService,
+    config: ReconciliationConfig,
+    run_id: i32,
+    repair: bool,
+) {
+    let (report, error) = reconcile(&db, &rpc_service, &config, repair).await;
+    let issues = serde_json::to_value(&report.issues).unwrap_or_default();
+    if let Err(e) = db
+        .finish_reconciliation_run(
+            run_id,
+            report.checked_proposals,
+            report.checked_rfps,
+            issues,
+            error,
+        )
+        .await
+    {
+        eprintln!("Failed to store reconciliation run {}: {:?}", run_id, e);
+    }
+}
+
+pub async fn run_reconciliation_scheduler(
+    db: DB,
+    rpc_service: RpcService,
+    config: ReconciliationConfig,
+) {
+    loop {
+        tokio::time::sleep(Duration::from_secs(config.interval_secs)).await;
+        match db
+            .create_reconciliation_run("scheduled", config.repair)
+            .await
+        {
+            Ok(Some(run)) => {
+                execute_reconciliation_run(
+                    db.clone(),
+                    rpc_service.clone(),
+                    config.clone(),
+                    run.id,
+                    config.repair,
+                )
+                .await
+            }
+            // An admin-triggered run is still going
+            Ok(None) => {}
+            Err(e) => eprintln!("Failed to start scheduled reconciliation: {:?}", e),
+        }
+    }
+}
@@ -0,0 +1,169 @@
+use crate::entrypoints::links::links_types::EntityKind;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use utoipa::ToSchema;
+
+/// Schedule of the runs that start on their own. Runs started through
+/// `POST /reconciliation/runs` only use `concurrency`.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct ReconciliationConfig {
+    /// Seconds between scheduled runs; 0 disables them, leaving only admin-triggered runs.
+    pub interval_secs: u64,
+    /// Whether scheduled runs repair what they find.
+    pub repair: bool,
+    /// Proposals or RFPs fetched from RPC at the same time.
+    pub concurrency: usize,
+}
+
+impl Default for ReconciliationConfig {
+    fn default() -> Self {
+        Self {
+            interval_secs: 0,
+            repair: false,
+            concurrency: 4,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum IssueKind {
+    /// On chain but not in the cache.
+    MissingInCache,
+    /// In the cache but not returned by the contract.
+    MissingOnChain,
+    /// The latest cached snapshot differs from contract state.
+    FieldMismatch,
+    /// The contract could not be read for this id.
+    RpcError,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct FieldMismatch {
+    pub field: String,
+    pub cached: serde_json::Value,
+    pub rpc: serde_json::Value,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct ReconciliationIssue {
+    pub kind: EntityKind,
+    pub id: i32,
+    pub issue: IssueKind,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<FieldMismatch>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// A fresh snapshot from RPC was inserted
+    pub repaired: bool,
+}
+
+impl ReconciliationIssue {
+    pub fn new(kind: EntityKind, id: i32, issue: IssueKind) -> Self {
+        Self {
+            kind,
+            id,
+            issue,
+            fields: vec![],
+            error: None,
+            repaired: false,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ReconciliationReport {
+    pub checked_proposals: i32,
+    pub checked_rfps: i32,
+    pub issues: Vec<ReconciliationIssue>,
+}
+
+/// The background check for on-chain ids with no cached snapshot. `concurrency` applies
+/// to on-demand backfills as well.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct GapConfig {
+    /// Seconds between checks; 0 disables the background monitor.
+    pub interval_secs: u64,
+    /// Whether the monitor fetches missing entities from RPC.
+    pub backfill: bool,
+    pub concurrency: usize,
+}
+
+impl Default for GapConfig {
+    fn default() -> Self {
+        Self {
+            interval_secs: 600,
+            backfill: true,
+            concurrency: 4,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, ToSchema)]
+pub struct EntityGaps {
+    /// Ids the contract returns
+    pub on_chain: usize,
+    /// On-chain ids without any cached snapshot
+    pub missing: Vec<i32>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub backfilled: Vec<i32>,
+    /// Missing ids that could not be read from RPC or stored
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub failed: Vec<i32>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct GapReport {
+    pub proposals: EntityGaps,
+    pub rfps: EntityGaps,
+    pub checked_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl GapReport {
+    pub fn has_gaps(&self) -> bool {
+        !self.proposals.missing.is_empty() || !self.rfps.missing.is_empty()
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, ToSchema)]
+pub struct BackfillStatus {
+    pub running: bool,
+    /// Report of the last finished backfill
+    pub last_report: Option<GapReport>,
+    /// Why the last backfill could not check for gaps
+    pub last_error: Option<String>,
+}
+
+/// The on-demand gap backfill, which runs in the background one at a time.
+#[derive(Clone, Default)]
+pub struct GapBackfill(Arc<Mutex<BackfillStatus>>);
+
+impl GapBackfill {
+    /// Marks a backfill as running, `false` when one already is.
+    pub fn try_start(&self) -> bool {
+        let mut status = self.0.lock().unwrap();
+        if status.running {
+            return false;
+        }
+        status.running = true;
+        true
+    }
+
+    pub fn finish(&self, result: anyhow::Result<GapReport>) {
+        let mut status = self.0.lock().unwrap();
+        status.running = false;
+        match result {
+            Ok(report) => {
+                status.last_report = Some(report);
+                status.last_error = None;
+            }
+            Err(e) => status.last_error = Some(e.to_string()),
+        }
+    }
+
+    pub fn status(&self) -> BackfillStatus {
+        self.0.lock().unwrap().clone()
+    }
+}
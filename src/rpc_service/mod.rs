@@ -0,0 +1,664 @@
+use devhub_shared::proposal::{ProposalId, VersionedProposal};
+use devhub_shared::rfp::{RFPId, VersionedRFP};
+use near_account_id::AccountId;
+use near_api::{types::reference::Reference, types::Data};
+use near_api::{Chain, Contract, NetworkConfig, RPCEndpoint};
+use near_jsonrpc_client::methods::query::RpcQueryRequest;
+use network::Network;
+use pool::*;
+use rocket::http::Status;
+use rocket::serde::json::json;
+use serde::Deserialize;
+use std::future::Future;
+use std::sync::Arc;
+pub mod network;
+pub mod pool;
+
+#[derive(Debug, serde::Deserialize)]
+pub struct Env {
+    pub contract: String,
+    pub database_url: String,
+    pub nearblocks_api_key: String,
+    pub fastnear_api_key: String,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct ChangeLog {
+    pub block_id: u64,
+    pub block_timestamp: u64,
+    pub change_log_type: ChangeLogType,
+}
+
+#[derive(Deserialize, Clone)]
+pub enum ChangeLogType {
+    Proposal(ProposalId),
+    RFP(RFPId),
+}
+
+/// Contract state read for a historical receipt.
+pub struct HistoricalState<T> {
+    pub state: Data<T>,
+    /// The state is the latest final one rather than the one the receipt produced.
+    pub approximate: bool,
+}
+
+impl<T> HistoricalState<T> {
+    fn exact(state: Data<T>) -> Self {
+        Self {
+            state,
+            approximate: false,
+        }
+    }
+
+    fn approximate(state: Data<T>) -> Self {
+        Self {
+            state,
+            approximate: true,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct RpcResponse {
+    pub data: String,
+}
+
+#[derive(Clone)]
+pub struct RpcService {
+    pub network: NetworkConfig,
+    pub contract: Contract,
+    /// Shared by clones, so every reader of the managed service feeds the same scores
+    pub pool: Arc<EndpointPool>,
+}
+
+#[derive(Deserialize)]
+struct QueryResponse {
+    // jsonrpc: String,
+    result: QueryResponseResult,
+    // id: String,
+}
+
+#[derive(Deserialize)]
+struct QueryResponseResult {
+    // result is an array of bytes, to be specific it is an ASCII code of the string
+    result: Vec<i32>,
+}
+
+impl Default for RpcService {
+    /// DevHub's mainnet contract through NEAR's public RPC, without reading any
+    /// configuration. Deployments build theirs with `from_env`.
+    fn default() -> Self {
+        let network = NetworkConfig::mainnet();
+        let endpoints = vec![(
+            RPCEndpoint::mainnet().with_retries(3),
+            EndpointRole::Archival,
+        )];
+        Self {
+            pool: Arc::new(EndpointPool::new(network.clone(), endpoints)),
+            network,
+            contract: Contract(Network::Mainnet.default_contract()),
+        }
+    }
+}
+
+impl RpcService {
+    pub fn new() -> Self {
+        Self::from_env().expect("Failed to configure the RPC service")
+    }
+
+    /// The `NETWORK` network and `CONTRACT`, or DevHub's contract on that network.
+    pub fn from_env() -> anyhow::Result<Self> {
+        dotenvy::dotenv().ok();
+
+        let network = Network::from_env()?;
+        let contract = match std::env::var("CONTRACT") {
+            Ok(contract) => contract
+                .parse::<AccountId>()
+                .map_err(|e| anyhow::anyhow!("Invalid CONTRACT {:?}: {}", contract, e))?,
+            Err(_) => network.default_contract(),
+        };
+        Self::for_network(network, contract)
+    }
+
+    pub fn mainnet(contract: AccountId) -> Self {
+        Self::for_network(Network::Mainnet, contract)
+            .expect("Failed to configure the mainnet RPC service")
+    }
+
+    pub fn testnet(contract: AccountId) -> Self {
+        Self::for_network(Network::Testnet, contract)
+            .expect("Failed to configure the testnet RPC service")
+    }
+
+    /// Endpoints from the `rpc` config, or the network's default ones when none are set.
+    /// The defaults for mainnet and testnet need `FASTNEAR_API_KEY`.
+    pub fn for_network(network: Network, contract: AccountId) -> anyhow::Result<Self> {
+        let config: RpcConfig = rocket::Config::figment()
+            .extract_inner("rpc")
+            .unwrap_or_default();
+        let network_config = network.network_config();
+
+        let pool = if config.endpoints.is_empty() {
+            dotenvy::dotenv().ok();
+
+            let fastnear_api_key = match (&network, std::env::var("FASTNEAR_API_KEY")) {
+                (_, Ok(key)) => key,
+                (Network::Custom(_), Err(_)) => String::new(),
+                (_, Err(_)) => {
+                    return Err(anyhow::anyhow!(
+                        "FASTNEAR_API_KEY is required for the default {} endpoints",
+                        network.name()
+                    ))
+                }
+            };
+            EndpointPool::new(
+                network_config.clone(),
+                network.default_endpoints(&fastnear_api_key),
+            )
+        } else {
+            EndpointPool::from_config(network_config.clone(), &config)
+                .map_err(|e| anyhow::anyhow!("Invalid rpc config: {}", e))?
+        };
+
+        Ok(Self {
+            network: network_config,
+            contract: Contract(contract),
+            pool: Arc::new(pool),
+        })
+    }
+
+    /// Uses the network's endpoints in order, all for every kind of read.
+    pub fn sandbox(network: NetworkConfig, contract: AccountId) -> Self {
+        let endpoints = network
+            .rpc_endpoints
+            .iter()
+            .map(|endpoint| (endpoint.clone(), EndpointRole::Latest))
+            .collect();
+        Self {
+            pool: Arc::new(EndpointPool::new(network.clone(), endpoints)),
+            network,
+            contract: Contract(contract),
+        }
+    }
+
+    /// Status and score of every configured endpoint.
+    pub fn endpoint_status(&self) -> Vec<EndpointStatus> {
+        self.pool.status()
+    }
+
+    // near-api's QueryError is large, but callers inspect it, so it is returned unboxed
+    #[allow(clippy::result_large_err)]
+    pub async fn get_proposal(
+        &self,
+        proposal_id: i32,
+    ) -> Result<Data<VersionedProposal>, near_api::errors::QueryError<RpcQueryRequest>> {
+        self.get_proposal_at(proposal_id, Reference::Optimistic)
+            .await
+    }
+
+    #[allow(clippy::result_large_err)]
+    pub async fn get_proposal_at(
+        &self,
+        proposal_id: i32,
+        reference: Reference,
+    ) -> Result<Data<VersionedProposal>, near_api::errors::QueryError<RpcQueryRequest>> {
+        self.pool
+            .call(role_for(&reference), query_failure, |network| {
+                let reference = reference.clone();
+                async move {
+                    self.contract
+                        .call_function("get_proposal", json!({ "proposal_id": proposal_id }))
+                        .unwrap()
+                        .read_only()
+                        .at(reference)
+                        .fetch_from(&network)
+                        .await
+                }
+            })
+            .await
+    }
+
+    // See `get_proposal`
+    #[allow(clippy::result_large_err)]
+    pub async fn get_rfp(
+        &self,
+        rfp_id: i32,
+    ) -> Result<Data<VersionedRFP>, near_api::errors::QueryError<RpcQueryRequest>> {
+        self.get_rfp_at(rfp_id, Reference::Optimistic).await
+    }
+
+    #[allow(clippy::result_large_err)]
+    pub async fn get_rfp_at(
+        &self,
+        rfp_id: i32,
+        reference: Reference,
+    ) -> Result<Data<VersionedRFP>, near_api::errors::QueryError<RpcQueryRequest>> {
+        self.pool
+            .call(role_for(&reference), query_failure, |network| {
+                let reference = reference.clone();
+                async move {
+                    self.contract
+                        .call_function("get_rfp", json!({ "rfp_id": rfp_id }))
+                        .unwrap()
+                        .read_only()
+                        .at(reference)
+                        .fetch_from(&network)
+                        .await
+                }
+            })
+            .await
+    }
+
+    pub async fn get_all_proposal_ids(&self) -> Result<Vec<i32>, Status> {
+        let result: Result<Data<Vec<i32>>, _> = self
+            .pool
+            .call(EndpointRole::Latest, query_failure, |network| async move {
+                self.contract
+                    .call_function("get_all_proposal_ids", ())
+                    .unwrap()
+                    .read_only()
+                    .fetch_from(&network)
+                    .await
+            })
+            .await;
+
+        match result {
+            Ok(res) => Ok(res.data),
+            Err(e) => {
+                eprintln!("Failed to get all proposal ids: {:?}", e);
+                Err(Status::InternalServerError)
+            }
+        }
+    }
+
+    pub async fn get_all_rfp_ids(&self) -> Result<Vec<i32>, Status> {
+        let result: Result<Data<Vec<i32>>, _> = self
+            .pool
+            .call(EndpointRole::Latest, query_failure, |network| async move {
+                self.contract
+                    .call_function("get_all_rfp_ids", ())
+                    .unwrap()
+                    .read_only()
+                    .fetch_from(&network)
+                    .await
+            })
+            .await;
+
+        match result {
+            Ok(res) => Ok(res.data),
+            Err(e) => {
+                eprintln!("Failed to get all rfp ids: {:?}", e);
+                Err(Status::InternalServerError)
+            }
+        }
+    }
+
+    /// Height and timestamp (nanoseconds) of the latest block.
+    pub async fn get_chain_head(&self) -> anyhow::Result<(u64, u64)> {
+        match self
+            .pool
+            .call(
+                EndpointRole::Latest,
+                request_failure,
+                |network| async move { Chain::block().fetch_from(&network).await },
+            )
+            .await
+        {
+            Ok(block) => Ok((block.header.height, block.header.timestamp_nanosec)),
+            Err(e) => {
+                eprintln!("Failed to get latest block: {:?}", e);
+                Err(anyhow::anyhow!("Failed to get latest block: {:?}", e))
+            }
+        }
+    }
+
+    /// Height of the latest final block.
+    pub async fn get_final_height(&self) -> anyhow::Result<u64> {
+        match self
+            .pool
+            .call(
+                EndpointRole::Latest,
+                request_failure,
+                |network| async move {
+                    Chain::block()
+                        .at(Reference::Final)
+                        .fetch_from(&network)
+                        .await
+                },
+            )
+            .await
+        {
+            Ok(block) => Ok(block.header.height),
+            Err(e) => {
+                eprintln!("Failed to get final block: {:?}", e);
+                Err(anyhow::anyhow!("Failed to get final block: {:?}", e))
+            }
+        }
+    }
+
+    /// Reads the proposal as of a receipt, see [`RpcService::read_after_receipt`].
+    pub async fn get_proposal_after_receipt(
+        &self,
+        proposal_id: i32,
+        receipt_height: i64,
+        visible: impl Fn(&VersionedProposal) -> bool,
+    ) -> anyhow::Result<HistoricalState<VersionedProposal>> {
+        self.read_after_receipt(
+            &format!("proposal {}", proposal_id),
+            receipt_height,
+            visible,
+            |reference| self.get_proposal_at(proposal_id, reference),
+        )
+        .await
+    }
+
+    /// Reads the RFP as of a receipt, see [`RpcService::read_after_receipt`].
+    pub async fn get_rfp_after_receipt(
+        &self,
+        rfp_id: i32,
+        receipt_height: i64,
+        visible: impl Fn(&VersionedRFP) -> bool,
+    ) -> anyhow::Result<HistoricalState<VersionedRFP>> {
+        self.read_after_receipt(
+            &format!("rfp {}", rfp_id),
+            receipt_height,
+            visible,
+            |reference| self.get_rfp_at(rfp_id, reference),
+        )
+        .await
+    }
+
+    /// Reads contract state as of a receipt in three steps:
+    /// 1. the receipt's own block, which only archival nodes keep for long;
+    /// 2. a search for the first later final block that has state and where `visible`
+    ///    holds, i.e. the receipt's effects are applied;
+    /// 3. the latest final state, marked approximate.
+    async fn read_after_receipt<T, F, Fut>(
+        &self,
+        entity: &str,
+        receipt_height: i64,
+        visible: impl Fn(&T) -> bool,
+        read: F,
+    ) -> anyhow::Result<HistoricalState<T>>
+    where
+        F: Fn(Reference) -> Fut,
+        Fut: Future<Output = Result<Data<T>, near_api::errors::QueryError<RpcQueryRequest>>>,
+    {
+        let receipt_height = receipt_height as u64;
+        if let Ok(state) = read(Reference::AtBlock(receipt_height)).await {
+            if visible(&state.data) {
+                return Ok(HistoricalState::exact(state));
+            }
+        }
+
+        let final_height = self.get_final_height().await?;
+        let found = find_visible_state(receipt_height + 1, final_height, visible, |height| {
+            let state = read(Reference::AtBlock(height));
+            async move { state.await.ok() }
+        })
+        .await;
+        if let Some(state) = found {
+            return Ok(HistoricalState::exact(state));
+        }
+
+        eprintln!(
+            "No historical state for {} between blocks {} and {}, using final state",
+            entity, receipt_height, final_height
+        );
+        match read(Reference::Final).await {
+            Ok(state) => Ok(HistoricalState::approximate(state)),
+            Err(e) => {
+                eprintln!("Failed to get final state for {}: {:?}", entity, e);
+                Err(anyhow::anyhow!("Failed to get {} from RPC", entity))
+            }
+        }
+    }
+
+    pub async fn get_change_log(&self) -> Result<Vec<ChangeLog>, Status> {
+        let result: Result<Data<Vec<ChangeLog>>, _> = self
+            .pool
+            .call(EndpointRole::Latest, query_failure, |network| async move {
+                self.contract
+                    .call_function("get_change_log", json!({}))
+                    .unwrap()
+                    .read_only()
+                    .fetch_from(&network)
+                    .await
+            })
+            .await;
+
+        match result {
+            Ok(res) => Ok(res.data),
+            Err(e) => {
+                eprintln!("Failed to get change log: {:?}", e);
+                Err(Status::InternalServerError)
+            }
+        }
+    }
+
+    pub async fn get_change_log_since(&self, block_id: i64) -> anyhow::Result<Vec<ChangeLog>> {
+        let result: Result<Data<Vec<ChangeLog>>, _> = self
+            .pool
+            .call(EndpointRole::Latest, query_failure, |network| async move {
+                self.contract
+                    .call_function("get_change_log_since", json!({"since": block_id}))
+                    .unwrap()
+                    .read_only()
+                    .at(Reference::Final)
+                    .fetch_from(&network)
+                    .await
+            })
+            .await;
+        match result {
+            Ok(res) => Ok(res.data),
+            Err(e) => {
+                eprintln!(
+                    "Failed to get change log since: {:?} error: {:?}",
+                    block_id, e
+                );
+                Err(anyhow::anyhow!("Failed to get change log since: {:?}", e))
+            }
+        }
+    }
+
+    pub async fn query(
+        &self,
+        method_name: String,
+        block_id: i64,
+        args_base64: String,
+    ) -> Result<String, Status> {
+        let args = json!({
+          "request_type": "call_function",
+          "account_id": self.contract.0.to_string(),
+          "block_id": block_id,
+          "method_name": method_name,
+          "args_base64": args_base64
+        });
+
+        println!("Querying args: {:?}", args);
+
+        let result: Result<Data<QueryResponse>, _> = self
+            .pool
+            .call(EndpointRole::Archival, query_failure, |network| {
+                let args = args.clone();
+                async move {
+                    self.contract
+                        .call_function("query", args)
+                        .unwrap()
+                        .read_only()
+                        .fetch_from(&network)
+                        .await
+                }
+            })
+            .await;
+
+        match result {
+            Ok(res) => {
+                // From ascii code to string
+                let decoded = res
+                    .data
+                    .result
+                    .result
+                    .iter()
+                    .map(|c| *c as u8 as char)
+                    .collect();
+                // Should return JSON object?
+                Ok(decoded)
+            }
+            Err(e) => {
+                eprintln!("Failed to query: {:?}", e);
+                Err(Status::InternalServerError)
+            }
+        }
+    }
+}
+
+/// Reads at a past block go to archival endpoints first, everything else stays off them.
+fn role_for(reference: &Reference) -> EndpointRole {
+    match reference {
+        Reference::AtBlock(_) | Reference::AtBlockHash(_) => EndpointRole::Archival,
+        _ => EndpointRole::Latest,
+    }
+}
+
+/// Finds the earliest block in `from..=to` whose state satisfies `visible`.
+///
+/// State only ever moves forward, so once a receipt's effects are visible they stay
+/// visible. Probes gallop outwards from `from` (`from`, `from + 1`, `from + 3`, ...)
+/// until a visible state is found, then binary search narrows it down to the first
+/// one. Blocks `read` can't serve (skipped heights, pruned state) count as not visible.
+pub async fn find_visible_state<T, F, Fut>(
+    from: u64,
+    to: u64,
+    visible: impl Fn(&T) -> bool,
+    read: F,
+) -> Option<Data<T>>
+where
+    F: Fn(u64) -> Fut,
+    Fut: Future<Output = Option<Data<T>>>,
+{
+    if from > to {
+        return None;
+    }
+
+    let mut low = from;
+    let mut offset = 0u64;
+    let (mut high, mut found) = loop {
+        let height = from.saturating_add(offset).min(to);
+        if let Some(state) = read(height).await {
+            if visible(&state.data) {
+                break (height, state);
+            }
+        }
+        if height == to {
+            return None;
+        }
+        low = height + 1;
+        offset = offset.saturating_mul(2).saturating_add(1);
+    };
+
+    while low < high {
+        let mid = low + (high - low) / 2;
+        match read(mid).await {
+            Some(state) if visible(&state.data) => {
+                high = mid;
+                found = state;
+            }
+            _ => low = mid + 1,
+        }
+    }
+
+    Some(found)
+}
+
+/// Whether the proposal's latest snapshot was taken at or after `timestamp` (nanoseconds).
+pub fn proposal_snapshot_since(proposal: &VersionedProposal, timestamp: u64) -> bool {
+    match proposal {
+        VersionedProposal::V0(proposal) => proposal.snapshot.timestamp >= timestamp,
+    }
+}
+
+/// Whether the RFP's latest snapshot was taken at or after `timestamp` (nanoseconds).
+pub fn rfp_snapshot_since(rfp: &VersionedRFP, timestamp: u64) -> bool {
+    match rfp {
+        VersionedRFP::V0(rfp) => rfp.snapshot.timestamp >= timestamp,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_api::types::CryptoHash;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    // State value is the last height the entity changed at; `None` is a skipped block
+    fn chain(changed_at: u64, skipped: &[u64]) -> impl Fn(u64) -> Option<Data<u64>> + '_ {
+        move |height| {
+            if skipped.contains(&height) {
+                return None;
+            }
+            Some(Data {
+                data: if height >= changed_at { changed_at } else { 0 },
+                block_height: height,
+                block_hash: CryptoHash::default(),
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_finds_first_block_with_effects() {
+        let read = chain(1_037, &[1_036]);
+        let probes = AtomicUsize::new(0);
+        let found = find_visible_state(
+            1_000,
+            2_000,
+            |changed_at| *changed_at == 1_037,
+            |height| {
+                probes.fetch_add(1, Ordering::SeqCst);
+                std::future::ready(read(height))
+            },
+        )
+        .await
+        .expect("state found");
+
+        assert_eq!(found.block_height, 1_037);
+        assert!(probes.load(Ordering::SeqCst) < 20);
+    }
+
+    #[tokio::test]
+    async fn test_skips_unreadable_blocks() {
+        let read = chain(1_000, &[1_000, 1_001]);
+        let found = find_visible_state(
+            1_000,
+            2_000,
+            |changed_at| *changed_at == 1_000,
+            |height| std::future::ready(read(height)),
+        )
+        .await
+        .expect("state found");
+
+        assert_eq!(found.block_height, 1_002);
+    }
+
+    #[tokio::test]
+    async fn test_gives_up_at_the_upper_bound() {
+        let read = chain(3_000, &[]);
+        let found = find_visible_state(
+            1_000,
+            2_000,
+            |changed_at| *changed_at == 3_000,
+            |height| std::future::ready(read(height)),
+        )
+        .await;
+        assert!(found.is_none());
+
+        let found = find_visible_state(
+            2_001,
+            2_000,
+            |_| true,
+            |height| std::future::ready(read(height)),
+        )
+        .await;
+        assert!(found.is_none());
+    }
+}
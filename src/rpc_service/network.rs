@@ -0,0 +1,221 @@
+use super::pool::EndpointRole;
+use crate::nearblocks_client::DEFAULT_BASE_URL;
+use near_account_id::AccountId;
+use near_api::{NetworkConfig, RPCEndpoint};
+use reqwest::Url;
+use std::str::FromStr;
+
+/// Origins every instance accepts, for local development and Playwright.
+const LOCAL_ORIGINS: [&str; 3] = [
+    "http://localhost:3000",
+    "http://localhost:8080", // Playwright
+    "http://127.0.0.1:8080", // Local development
+];
+
+const MAINNET_ORIGINS: [&str; 9] = [
+    "https://dev.near.org",
+    "https://near.social",
+    "https://neardevhub.org",
+    "https://devhub.near.page",
+    "https://events-committee.near.page/",
+    "https://infrastructure-committee.near.page/",
+    "https://devhub-cache-api-rs-2.fly.dev",
+    "https://infra-cache-api-rs-2.fly.dev",
+    "https://events-cache-api-rs-2.fly.dev",
+    // TODO Add prod urls here
+];
+
+const TESTNET_ORIGINS: [&str; 3] = [
+    "https://test.near.org",
+    "https://test.near.social",
+    "https://devhub.testnet.page",
+];
+
+/// The chain an instance indexes, set with `NETWORK`: `mainnet` (the default), `testnet`
+/// or the URL of an RPC node for anything else, e.g. a localnet.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Network {
+    Mainnet,
+    Testnet,
+    Custom(Url),
+}
+
+impl FromStr for Network {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "" | "mainnet" => Ok(Network::Mainnet),
+            "testnet" => Ok(Network::Testnet),
+            url => Url::parse(url)
+                .ok()
+                .filter(|url| matches!(url.scheme(), "http" | "https"))
+                .map(Network::Custom)
+                .ok_or_else(|| {
+                    format!(
+                        "NETWORK must be mainnet, testnet or an http(s) RPC url, got {:?}",
+                        url
+                    )
+                }),
+        }
+    }
+}
+
+impl Network {
+    pub fn from_env() -> anyhow::Result<Self> {
+        dotenvy::dotenv().ok();
+
+        std::env::var("NETWORK")
+            .unwrap_or_default()
+            .parse()
+            .map_err(|e: String| anyhow::anyhow!(e))
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            Network::Mainnet => "mainnet",
+            Network::Testnet => "testnet",
+            Network::Custom(_) => "custom",
+        }
+    }
+
+    pub fn network_config(&self) -> NetworkConfig {
+        match self {
+            Network::Mainnet => NetworkConfig::mainnet(),
+            Network::Testnet => NetworkConfig::testnet(),
+            Network::Custom(url) => NetworkConfig {
+                network_name: self.name().to_string(),
+                rpc_endpoints: vec![RPCEndpoint::new(url.clone())],
+                linkdrop_account_id: None,
+                near_social_db_contract_account_id: None,
+                faucet_url: None,
+                meta_transaction_relayer_url: None,
+                fastnear_url: None,
+                staking_pools_factory_account_id: None,
+            },
+        }
+    }
+
+    /// Endpoints used when the `rpc` config lists none: fastnear's archival and regular
+    /// RPC, then NEAR's public archival RPC with its low rate limit as the last resort.
+    /// A custom network only has its own node.
+    pub fn default_endpoints(&self, fastnear_api_key: &str) -> Vec<(RPCEndpoint, EndpointRole)> {
+        let (archival_url, regular_url, public_endpoint) = match self {
+            Network::Mainnet => (
+                "https://archival-rpc.mainnet.fastnear.com",
+                "https://rpc.mainnet.fastnear.com/",
+                RPCEndpoint::mainnet(),
+            ),
+            Network::Testnet => (
+                "https://archival-rpc.testnet.fastnear.com",
+                "https://rpc.testnet.fastnear.com/",
+                RPCEndpoint::testnet(),
+            ),
+            Network::Custom(url) => {
+                return vec![(
+                    RPCEndpoint::new(url.clone())
+                        .with_retries(3)
+                        .with_exponential_backoff(true, 2),
+                    EndpointRole::Latest,
+                )]
+            }
+        };
+
+        let archival_endpoint = RPCEndpoint::new(archival_url.parse().unwrap())
+            .with_api_key(fastnear_api_key.parse().unwrap())
+            .with_retries(3)
+            .with_exponential_backoff(true, 2);
+
+        let custom_endpoint = RPCEndpoint::new(regular_url.parse().unwrap())
+            .with_api_key(fastnear_api_key.parse().unwrap())
+            .with_retries(5)
+            .with_exponential_backoff(true, 3);
+
+        vec![
+            (archival_endpoint, EndpointRole::Archival),
+            (custom_endpoint, EndpointRole::Latest),
+            (public_endpoint.with_retries(3), EndpointRole::Archival),
+        ]
+    }
+
+    /// NearBlocks API for the network. NearBlocks doesn't index custom networks, those have
+    /// none and must set `NEARBLOCKS_API_URL`.
+    pub fn nearblocks_base_url(&self) -> Option<&'static str> {
+        match self {
+            Network::Mainnet => Some(DEFAULT_BASE_URL),
+            Network::Testnet => Some("https://api-testnet.nearblocks.io/"),
+            Network::Custom(_) => None,
+        }
+    }
+
+    /// Frontends allowed to call the API.
+    pub fn cors_origins(&self) -> Vec<&'static str> {
+        let mut origins = LOCAL_ORIGINS.to_vec();
+        match self {
+            Network::Mainnet => origins.extend(MAINNET_ORIGINS),
+            Network::Testnet => origins.extend(TESTNET_ORIGINS),
+            Network::Custom(_) => {}
+        }
+        origins
+    }
+
+    /// DevHub's own contract on the network, used when `CONTRACT` isn't set.
+    pub fn default_contract(&self) -> AccountId {
+        match self {
+            Network::Mainnet => "devhub.near".parse().unwrap(),
+            Network::Testnet => "devhub.testnet".parse().unwrap(),
+            Network::Custom(_) => "devhub.test.near".parse().unwrap(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_network() {
+        assert_eq!("".parse::<Network>(), Ok(Network::Mainnet));
+        assert_eq!("mainnet".parse::<Network>(), Ok(Network::Mainnet));
+        assert_eq!("testnet".parse::<Network>(), Ok(Network::Testnet));
+        assert_eq!(
+            "http://127.0.0.1:3030".parse::<Network>(),
+            Ok(Network::Custom("http://127.0.0.1:3030".parse().unwrap()))
+        );
+        assert!("localnet".parse::<Network>().is_err());
+        assert!("ftp://rpc.example".parse::<Network>().is_err());
+    }
+
+    #[test]
+    fn test_testnet_settings() {
+        let testnet = Network::Testnet;
+        assert_eq!(testnet.network_config().network_name, "testnet");
+        assert_eq!(
+            testnet.nearblocks_base_url(),
+            Some("https://api-testnet.nearblocks.io/")
+        );
+        let urls: Vec<String> = testnet
+            .default_endpoints("key")
+            .iter()
+            .map(|(endpoint, _)| endpoint.url.to_string())
+            .collect();
+        assert!(urls.iter().all(|url| url.contains("testnet")));
+        let origins = testnet.cors_origins();
+        assert!(origins.contains(&"https://test.near.org"));
+        assert!(!origins.contains(&"https://dev.near.org"));
+    }
+
+    #[test]
+    fn test_custom_network_uses_its_node() {
+        let network: Network = "http://127.0.0.1:3030".parse().unwrap();
+        let endpoints = network.default_endpoints("key");
+        assert_eq!(endpoints.len(), 1);
+        assert_eq!(endpoints[0].0.url.as_str(), "http://127.0.0.1:3030/");
+        assert_eq!(
+            network.network_config().rpc_endpoints[0].url.as_str(),
+            "http://127.0.0.1:3030/"
+        );
+        assert_eq!(network.cors_origins(), LOCAL_ORIGINS.to_vec());
+        assert_eq!(network.nearblocks_base_url(), None);
+    }
+}
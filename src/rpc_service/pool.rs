@@ -0,0 +1,461 @@
+use near_api::errors::{QueryError, RetryError};
+use near_api::{NetworkConfig, RPCEndpoint};
+use near_jsonrpc_client::methods::query::{RpcQueryError, RpcQueryRequest};
+use near_jsonrpc_client::methods::RpcMethod;
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use utoipa::ToSchema;
+
+/// Weight of the latest call in the moving latency and error averages.
+const SMOOTHING: f64 = 0.2;
+/// An error rate of 1 costs as much as this many milliseconds of latency.
+const ERROR_PENALTY_MS: f64 = 5_000.0;
+/// Endpoints are tried last after this many failures in a row.
+const UNHEALTHY_AFTER: u32 = 3;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum EndpointRole {
+    /// Keeps all history; used first for reads at a past block.
+    Archival,
+    /// Recent state only; used for reads at the chain head.
+    Latest,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct EndpointConfig {
+    pub url: String,
+    pub role: EndpointRole,
+    #[serde(default)]
+    pub api_key: Option<String>,
+    #[serde(default = "default_retries")]
+    pub retries: u8,
+}
+
+fn default_retries() -> u8 {
+    3
+}
+
+/// Endpoints the pool picks from by role and score. When none are listed the network's
+/// defaults from `Network::default_endpoints` are used.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct RpcConfig {
+    pub endpoints: Vec<EndpointConfig>,
+}
+
+impl EndpointConfig {
+    pub fn to_endpoint(&self) -> anyhow::Result<RPCEndpoint> {
+        let url = self
+            .url
+            .parse()
+            .map_err(|e| anyhow::anyhow!("Invalid RPC url {}: {}", self.url, e))?;
+        let mut endpoint = RPCEndpoint::new(url)
+            .with_retries(self.retries)
+            .with_exponential_backoff(true, 2);
+        if let Some(api_key) = &self.api_key {
+            endpoint = endpoint.with_api_key(
+                api_key
+                    .parse()
+                    .map_err(|e| anyhow::anyhow!("Invalid API key for {}: {:?}", self.url, e))?,
+            );
+        }
+        Ok(endpoint)
+    }
+}
+
+/// How a failed call reflects on the endpoint that served it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Failure {
+    /// The endpoint could not be reached or kept failing.
+    Endpoint,
+    /// The endpoint answered but doesn't have the requested block; another may.
+    MissingState,
+    /// Every endpoint would fail the same way, e.g. a contract error or a response that
+    /// doesn't deserialize.
+    Answered,
+}
+
+pub fn query_failure(error: &QueryError<RpcQueryRequest>) -> Failure {
+    if let QueryError::JsonRpcError(RetryError::Critical(e)) = error {
+        return match e.handler_error() {
+            Some(RpcQueryError::GarbageCollectedBlock { .. })
+            | Some(RpcQueryError::UnknownBlock { .. })
+            | Some(RpcQueryError::NoSyncedBlocks)
+            | Some(RpcQueryError::UnavailableShard { .. }) => Failure::MissingState,
+            Some(RpcQueryError::InternalError { .. }) => Failure::Endpoint,
+            _ => Failure::Answered,
+        };
+    }
+    request_failure(error)
+}
+
+pub fn request_failure<M: RpcMethod>(error: &QueryError<M>) -> Failure
+where
+    M::Error: std::fmt::Debug + std::fmt::Display + 'static,
+{
+    match error {
+        QueryError::JsonRpcError(RetryError::Critical(_)) => Failure::Answered,
+        QueryError::JsonRpcError(_) | QueryError::InternalErrorNoResponse => Failure::Endpoint,
+        _ => Failure::Answered,
+    }
+}
+
+#[derive(Debug, Default)]
+struct EndpointStats {
+    latency_ms: f64,
+    error_rate: f64,
+    successes: u64,
+    failures: u64,
+    consecutive_failures: u32,
+    last_error: Option<String>,
+    last_error_at: Option<chrono::DateTime<chrono::Utc>>,
+    last_success_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl EndpointStats {
+    fn record_success(&mut self, latency: Duration) {
+        let latency_ms = latency.as_secs_f64() * 1_000.0;
+        self.latency_ms = if self.successes == 0 {
+            latency_ms
+        } else {
+            self.latency_ms + SMOOTHING * (latency_ms - self.latency_ms)
+        };
+        self.error_rate -= SMOOTHING * self.error_rate;
+        self.successes += 1;
+        self.consecutive_failures = 0;
+        self.last_success_at = Some(chrono::Utc::now());
+    }
+
+    fn record_failure(&mut self, error: String) {
+        self.error_rate += SMOOTHING * (1.0 - self.error_rate);
+        self.failures += 1;
+        self.consecutive_failures += 1;
+        self.last_error = Some(error);
+        self.last_error_at = Some(chrono::Utc::now());
+    }
+
+    fn healthy(&self) -> bool {
+        self.consecutive_failures < UNHEALTHY_AFTER
+    }
+
+    /// Lower is better.
+    fn score(&self) -> f64 {
+        self.latency_ms + ERROR_PENALTY_MS * self.error_rate
+    }
+}
+
+#[derive(Clone, Debug, Serialize, ToSchema)]
+pub struct EndpointStatus {
+    pub url: String,
+    pub role: EndpointRole,
+    pub healthy: bool,
+    /// Smoothed latency plus a penalty for recent errors, in milliseconds; lower is better
+    pub score: f64,
+    pub latency_ms: f64,
+    /// Smoothed share of recent calls that failed, from 0 to 1
+    pub error_rate: f64,
+    pub successes: u64,
+    pub failures: u64,
+    pub consecutive_failures: u32,
+    pub last_error: Option<String>,
+    pub last_error_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub last_success_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+struct PooledEndpoint {
+    endpoint: RPCEndpoint,
+    role: EndpointRole,
+    stats: Mutex<EndpointStats>,
+}
+
+/// RPC endpoints tried one at a time, best scoring first, with reads at the chain head
+/// kept off archival nodes unless nothing else answers.
+pub struct EndpointPool {
+    base: NetworkConfig,
+    endpoints: Vec<PooledEndpoint>,
+}
+
+impl EndpointPool {
+    pub fn new(base: NetworkConfig, endpoints: Vec<(RPCEndpoint, EndpointRole)>) -> Self {
+        Self {
+            base,
+            endpoints: endpoints
+                .into_iter()
+                .map(|(endpoint, role)| PooledEndpoint {
+                    endpoint,
+                    role,
+                    stats: Mutex::new(EndpointStats::default()),
+                })
+                .collect(),
+        }
+    }
+
+    pub fn from_config(base: NetworkConfig, config: &RpcConfig) -> anyhow::Result<Self> {
+        let endpoints = config
+            .endpoints
+            .iter()
+            .map(|endpoint| Ok((endpoint.to_endpoint()?, endpoint.role)))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        Ok(Self::new(base, endpoints))
+    }
+
+    /// Endpoint indices in the order a call for `role` tries them: endpoints with that
+    /// role first, healthy before unhealthy, then by score. Ties keep config order.
+    fn order(&self, role: EndpointRole) -> Vec<usize> {
+        let mut ranked: Vec<(bool, bool, f64, usize)> = self
+            .endpoints
+            .iter()
+            .enumerate()
+            .map(|(index, pooled)| {
+                let stats = pooled.stats.lock().unwrap();
+                (pooled.role != role, !stats.healthy(), stats.score(), index)
+            })
+            .collect();
+        ranked.sort_by(|a, b| {
+            (a.0, a.1)
+                .cmp(&(b.0, b.1))
+                .then(a.2.total_cmp(&b.2))
+                .then(a.3.cmp(&b.3))
+        });
+        ranked.into_iter().map(|(.., index)| index).collect()
+    }
+
+    /// Runs `request` against one endpoint at a time until one answers. A response counts
+    /// for the endpoint and an endpoint failure against it. Other errors, like contract
+    /// or deserialization errors, say nothing about the endpoint and leave its score alone.
+    pub async fn call<T, E, F, Fut>(
+        &self,
+        role: EndpointRole,
+        classify: impl Fn(&E) -> Failure,
+        request: F,
+    ) -> Result<T, E>
+    where
+        E: std::fmt::Display,
+        F: Fn(NetworkConfig) -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+    {
+        let mut last_error = None;
+        for index in self.order(role) {
+            let pooled = &self.endpoints[index];
+            let mut network = self.base.clone();
+            network.rpc_endpoints = vec![pooled.endpoint.clone()];
+
+            let start = Instant::now();
+            let result = request(network).await;
+            let latency = start.elapsed();
+            let failure = result.as_ref().err().map(&classify);
+            {
+                let mut stats = pooled.stats.lock().unwrap();
+                match (&result, failure) {
+                    (Ok(_), _) => stats.record_success(latency),
+                    (Err(e), Some(Failure::Endpoint)) => stats.record_failure(e.to_string()),
+                    (Err(_), _) => {}
+                }
+            }
+
+            match (result, failure) {
+                (Ok(value), _) => return Ok(value),
+                (Err(e), Some(Failure::Answered)) => return Err(e),
+                (Err(e), _) => last_error = Some(e),
+            }
+        }
+
+        match last_error {
+            Some(e) => Err(e),
+            None => request(self.base.clone()).await,
+        }
+    }
+
+    pub fn status(&self) -> Vec<EndpointStatus> {
+        self.endpoints
+            .iter()
+            .map(|pooled| {
+                let stats = pooled.stats.lock().unwrap();
+                let mut url = pooled.endpoint.url.clone();
+                url.set_query(None);
+                EndpointStatus {
+                    url: url.to_string(),
+                    role: pooled.role,
+                    healthy: stats.healthy(),
+                    score: stats.score(),
+                    latency_ms: stats.latency_ms,
+                    error_rate: stats.error_rate,
+                    successes: stats.successes,
+                    failures: stats.failures,
+                    consecutive_failures: stats.consecutive_failures,
+                    last_error: stats.last_error.clone(),
+                    last_error_at: stats.last_error_at,
+                    last_success_at: stats.last_success_at,
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn endpoint(url: &str) -> RPCEndpoint {
+        RPCEndpoint::new(url.parse().unwrap())
+    }
+
+    fn pool() -> EndpointPool {
+        EndpointPool::new(
+            NetworkConfig::mainnet(),
+            vec![
+                (
+                    endpoint("https://archival.example/"),
+                    EndpointRole::Archival,
+                ),
+                (endpoint("https://latest-a.example/"), EndpointRole::Latest),
+                (endpoint("https://latest-b.example/"), EndpointRole::Latest),
+            ],
+        )
+    }
+
+    fn served_by(network: &NetworkConfig) -> String {
+        network.rpc_endpoints[0].url.to_string()
+    }
+
+    #[test]
+    fn test_endpoints_from_config() {
+        use rocket::figment::providers::{Format, Toml};
+
+        let config: RpcConfig = rocket::figment::Figment::from(Toml::string(
+            r#"
+            [rpc]
+            endpoints = [
+                { url = "https://rpc.example/", role = "latest", api_key = "secret", retries = 5 },
+                { url = "https://archival.example/", role = "archival" },
+            ]
+            "#,
+        ))
+        .extract_inner("rpc")
+        .unwrap();
+        let pool = EndpointPool::from_config(NetworkConfig::mainnet(), &config).unwrap();
+
+        assert_eq!(pool.endpoints[0].endpoint.retries, 5);
+        assert!(pool.endpoints[0].endpoint.api_key.is_some());
+        assert_eq!(pool.endpoints[1].endpoint.retries, 3);
+        assert_eq!(pool.order(EndpointRole::Archival), vec![1, 0]);
+        let status = pool.status();
+        assert_eq!(status[0].url, "https://rpc.example/");
+        assert_eq!(status[1].role, EndpointRole::Archival);
+
+        let invalid = RpcConfig {
+            endpoints: vec![EndpointConfig {
+                url: "not a url".to_string(),
+                role: EndpointRole::Latest,
+                api_key: None,
+                retries: 3,
+            }],
+        };
+        assert!(EndpointPool::from_config(NetworkConfig::mainnet(), &invalid).is_err());
+    }
+
+    #[test]
+    fn test_reads_are_routed_by_role() {
+        let pool = pool();
+        assert_eq!(pool.order(EndpointRole::Latest), vec![1, 2, 0]);
+        assert_eq!(pool.order(EndpointRole::Archival), vec![0, 1, 2]);
+    }
+
+    #[tokio::test]
+    async fn test_failing_endpoint_is_skipped_and_demoted() {
+        let pool = pool();
+        let calls = AtomicUsize::new(0);
+        let result: Result<String, String> = pool
+            .call(
+                EndpointRole::Latest,
+                |_| Failure::Endpoint,
+                |network| {
+                    calls.fetch_add(1, Ordering::SeqCst);
+                    let url = served_by(&network);
+                    async move {
+                        if url.contains("latest-a") {
+                            Err("connection refused".to_string())
+                        } else {
+                            Ok(url)
+                        }
+                    }
+                },
+            )
+            .await;
+        assert_eq!(result.unwrap(), "https://latest-b.example/");
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+        assert_eq!(pool.order(EndpointRole::Latest), vec![2, 1, 0]);
+        let status = pool.status();
+        assert_eq!(status[1].failures, 1);
+        assert_eq!(status[1].last_error.as_deref(), Some("connection refused"));
+        assert_eq!(status[2].successes, 1);
+        assert!(status[1].score > status[2].score);
+    }
+
+    #[test]
+    fn test_unhealthy_endpoints_go_last() {
+        let pool = pool();
+        {
+            let mut stats = pool.endpoints[1].stats.lock().unwrap();
+            for _ in 0..UNHEALTHY_AFTER {
+                stats.record_failure("timeout".to_string());
+            }
+        }
+        {
+            // Slow, but answering
+            let mut stats = pool.endpoints[2].stats.lock().unwrap();
+            stats.record_success(Duration::from_secs(60));
+        }
+        assert!(!pool.status()[1].healthy);
+        assert_eq!(pool.order(EndpointRole::Latest), vec![2, 1, 0]);
+
+        pool.endpoints[1]
+            .stats
+            .lock()
+            .unwrap()
+            .record_success(Duration::from_millis(50));
+        assert!(pool.status()[1].healthy);
+        assert_eq!(pool.order(EndpointRole::Latest), vec![1, 2, 0]);
+    }
+
+    #[tokio::test]
+    async fn test_answers_stop_the_fallthrough() {
+        let pool = pool();
+        let calls = AtomicUsize::new(0);
+        let result: Result<(), String> = pool
+            .call(
+                EndpointRole::Archival,
+                |e: &String| {
+                    if e == "unknown block" {
+                        Failure::MissingState
+                    } else {
+                        Failure::Answered
+                    }
+                },
+                |network| {
+                    calls.fetch_add(1, Ordering::SeqCst);
+                    let url = served_by(&network);
+                    async move {
+                        if url.contains("archival") {
+                            Err("unknown block".to_string())
+                        } else {
+                            Err("contract panicked".to_string())
+                        }
+                    }
+                },
+            )
+            .await;
+        assert_eq!(result.unwrap_err(), "contract panicked");
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        // Neither error was a response or the endpoint's fault
+        assert!(pool
+            .status()
+            .iter()
+            .all(|s| s.failures == 0 && s.successes == 0));
+    }
+}
@@ -24,8 +24,7 @@ impl<T: Serialize> PaginatedResponse<T> {
         total_records: u64,
         newly_indexed: Option<usize>,
     ) -> Self {
-        let extra_page = if total_records % limit == 0 { 0 } else { 1 };
-        let total_pages = (total_records / limit) + extra_page;
+        let total_pages = total_records.div_ceil(limit);
         Self {
             records,
             page,
@@ -64,3 +63,10 @@ pub struct ProposalResponse {
     pub timeline: Option<serde_json::Value>,
     pub views: Option<i32>,
 }
+
+/// Which taxonomy the `/labels` and `/categories` endpoints count.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TaxonomyKind {
+    Labels,
+    Categories,
+}
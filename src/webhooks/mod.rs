@@ -0,0 +1,204 @@
+use self::types::*;
+use crate::db::db_types::{DueWebhookDelivery, ProposalSnapshotRecord};
+use crate::db::DB;
+use futures::future::join_all;
+use hmac::{Hmac, Mac};
+use serde_json::Value;
+use sha2::Sha256;
+use std::time::{Duration, Instant};
+pub mod types;
+
+pub const SIGNATURE_HEADER: &str = "X-Devhub-Signature";
+pub const TIMESTAMP_HEADER: &str = "X-Devhub-Timestamp";
+pub const EVENT_HEADER: &str = "X-Devhub-Event";
+pub const DELIVERY_HEADER: &str = "X-Devhub-Delivery";
+
+const APPROVED_STAGES: [&str; 2] = ["APPROVED", "APPROVED_CONDITIONALLY"];
+const FUNDED_STAGE: &str = "FUNDED";
+
+/// Lifecycle events implied by a new proposal snapshot, given the snapshot before it.
+pub fn proposal_events(
+    previous: Option<&ProposalSnapshotRecord>,
+    current: &ProposalSnapshotRecord,
+) -> Vec<WebhookEventType> {
+    let stage = timeline_field(&current.timeline, "status");
+    let Some(previous) = previous else {
+        let mut events = vec![WebhookEventType::ProposalCreated];
+        if is_approved(&stage) {
+            events.push(WebhookEventType::ProposalApproved);
+        }
+        return events;
+    };
+
+    let previous_stage = timeline_field(&previous.timeline, "status");
+    let mut events = vec![];
+    if stage != previous_stage {
+        events.push(WebhookEventType::ProposalStageChanged);
+        if is_approved(&stage) && !is_approved(&previous_stage) {
+            events.push(WebhookEventType::ProposalApproved);
+        }
+    }
+    let funded = stage.as_ref().and_then(Value::as_str) == Some(FUNDED_STAGE);
+    if funded
+        && (stage != previous_stage
+            || timeline_field(&current.timeline, "payouts")
+                != timeline_field(&previous.timeline, "payouts"))
+    {
+        events.push(WebhookEventType::ProposalPaymentRecorded);
+    }
+    events
+}
+
+pub fn proposal_event_payload(
+    event: WebhookEventType,
+    previous: Option<&ProposalSnapshotRecord>,
+    current: &ProposalSnapshotRecord,
+) -> Value {
+    serde_json::json!({
+        "event": event.as_str(),
+        "proposal_id": current.proposal_id,
+        "block_height": current.block_height,
+        "ts": current.ts,
+        "previous_stage": previous.and_then(|p| timeline_field(&p.timeline, "status")),
+        "stage": timeline_field(&current.timeline, "status"),
+        "snapshot": current,
+    })
+}
+
+// Timelines are stored as the JSON string the contract returns
+fn timeline_field(timeline: &Option<Value>, field: &str) -> Option<Value> {
+    let timeline = match timeline.as_ref()? {
+        Value::String(s) => serde_json::from_str::<Value>(s).ok()?,
+        value => value.clone(),
+    };
+    timeline.get(field).cloned()
+}
+
+fn is_approved(stage: &Option<Value>) -> bool {
+    stage
+        .as_ref()
+        .and_then(Value::as_str)
+        .is_some_and(|s| APPROVED_STAGES.contains(&s))
+}
+
+/// `sha256=<hex>` HMAC of `<timestamp>.<body>` keyed with the subscription secret.
+pub fn signature(secret: &str, timestamp: i64, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Sends every delivery that is due and records the outcome. Returns how many were attempted.
+pub async fn deliver_due_webhooks(
+    db: &DB,
+    client: &reqwest::Client,
+    config: &WebhookConfig,
+) -> anyhow::Result<usize> {
+    // Claimed deliveries come back after the lease if this worker dies mid-send
+    let lease = Duration::from_secs(config.request_timeout_secs * 2 + 1);
+    let due = db
+        .claim_due_webhook_deliveries(config.batch_size, lease)
+        .await?;
+    let count = due.len();
+
+    join_all(
+        due.into_iter()
+            .map(|delivery| deliver(db, client, config, delivery)),
+    )
+    .await;
+
+    Ok(count)
+}
+
+async fn deliver(
+    db: &DB,
+    client: &reqwest::Client,
+    config: &WebhookConfig,
+    delivery: DueWebhookDelivery,
+) {
+    let body = delivery.payload.to_string();
+    let timestamp = chrono::Utc::now().timestamp();
+    let started = Instant::now();
+
+    let result = client
+        .post(&delivery.url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .header(EVENT_HEADER, &delivery.event_type)
+        .header(DELIVERY_HEADER, delivery.id.to_string())
+        .header(TIMESTAMP_HEADER, timestamp.to_string())
+        .header(
+            SIGNATURE_HEADER,
+            signature(&delivery.secret, timestamp, body.as_bytes()),
+        )
+        .body(body)
+        .send()
+        .await;
+    let duration_ms = started.elapsed().as_millis() as i64;
+
+    let (status_code, error) = match result {
+        Ok(response) if response.status().is_success() => {
+            (Some(response.status().as_u16() as i32), None)
+        }
+        Ok(response) => (
+            Some(response.status().as_u16() as i32),
+            Some(format!("Endpoint responded with {}", response.status())),
+        ),
+        Err(e) => (None, Some(e.to_string())),
+    };
+
+    let attempts = delivery.attempts + 1;
+    let status = match (&error, attempts >= config.max_attempts) {
+        (None, _) => "delivered",
+        (Some(_), true) => "failed",
+        (Some(_), false) => "pending",
+    };
+    if let Some(error) = &error {
+        eprintln!(
+            "Webhook delivery {} attempt {} failed: {}",
+            delivery.id, attempts, error
+        );
+    }
+
+    if let Err(e) = db
+        .record_webhook_delivery_attempt(
+            delivery.id,
+            status,
+            status_code,
+            error,
+            duration_ms,
+            config.retry_delay(attempts),
+        )
+        .await
+    {
+        eprintln!(
+            "Failed to record webhook delivery {} attempt: {:?}",
+            delivery.id, e
+        );
+    }
+}
+
+pub async fn run_delivery_worker(db: DB, config: WebhookConfig) {
+    let client = match reqwest::Client::builder()
+        .timeout(Duration::from_secs(config.request_timeout_secs))
+        .build()
+    {
+        Ok(client) => client,
+        Err(e) => {
+            eprintln!("Failed to build webhook HTTP client: {:?}", e);
+            return;
+        }
+    };
+
+    loop {
+        match deliver_due_webhooks(&db, &client, &config).await {
+            // A full batch means there may be more waiting
+            Ok(count) if count as i64 == config.batch_size => continue,
+            Ok(_) => {}
+            Err(e) => eprintln!("Failed to deliver webhooks: {:?}", e),
+        }
+        tokio::time::sleep(Duration::from_secs(config.poll_interval_secs)).await;
+    }
+}
@@ -0,0 +1,62 @@
+use rocket::serde::{Deserialize, Serialize};
+use std::time::Duration;
+use utoipa::ToSchema;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookEventType {
+    ProposalCreated,
+    ProposalStageChanged,
+    ProposalApproved,
+    ProposalPaymentRecorded,
+}
+
+impl WebhookEventType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WebhookEventType::ProposalCreated => "proposal_created",
+            WebhookEventType::ProposalStageChanged => "proposal_stage_changed",
+            WebhookEventType::ProposalApproved => "proposal_approved",
+            WebhookEventType::ProposalPaymentRecorded => "proposal_payment_recorded",
+        }
+    }
+}
+
+/// Retry schedule, timeouts and batching of the delivery worker.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct WebhookConfig {
+    /// Delay before the first retry; doubles on every further failure.
+    pub retry_base_secs: u64,
+    pub max_retry_delay_secs: u64,
+    /// A delivery is marked failed after this many attempts.
+    pub max_attempts: i32,
+    pub poll_interval_secs: u64,
+    pub request_timeout_secs: u64,
+    pub batch_size: i64,
+}
+
+impl Default for WebhookConfig {
+    fn default() -> Self {
+        Self {
+            retry_base_secs: 10,
+            max_retry_delay_secs: 3600,
+            max_attempts: 8,
+            poll_interval_secs: 5,
+            request_timeout_secs: 10,
+            batch_size: 50,
+        }
+    }
+}
+
+impl WebhookConfig {
+    /// Delay after the given number of failed attempts.
+    pub fn retry_delay(&self, failed_attempts: i32) -> Duration {
+        let exponent = failed_attempts.saturating_sub(1).clamp(0, 20) as u32;
+        let secs = self
+            .retry_base_secs
+            .saturating_mul(2u64.saturating_pow(exponent))
+            .min(self.max_retry_delay_secs);
+        Duration::from_secs(secs)
+    }
+}